@@ -0,0 +1,610 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use nalgebra::{DMatrix, DVector, Isometry3, Matrix3, Point3, Vector2, Vector3};
+
+use super::{Dump, DumpError, StereoFeatures, StereoRig, LighthouseConfig, TrackedCameraConfig, apply_pose};
+use crate::vision::{Feature, match_features};
+
+/// Strongest stereo matches of each dump that are used as points.
+pub const MAX_POINTS_PER_DUMP: usize = 400;
+/// Radius around the predicted position searched when matching points into other dumps, in rectified pixels.
+pub const GUIDED_MATCH_RADIUS: f64 = 24.0;
+/// Residuals longer than this, in pixels, are down weighted.
+pub const HUBER_THRESHOLD: f64 = 2.0;
+pub const MAX_ITERATIONS: usize = 50;
+/// Triangulated points closer or further than this, in meters, are dropped.
+pub const MIN_DEPTH: f64 = 0.1;
+pub const MAX_DEPTH: f64 = 20.0;
+
+/// Prior standard deviations, they keep the parameters the dumps can't observe at their config values.
+pub const ROTATION_PRIOR: f64 = 2.0;
+pub const TRANSLATION_PRIOR: f64 = 0.01;
+pub const FOCAL_PRIOR: f64 = 10.0;
+pub const CENTER_PRIOR: f64 = 10.0;
+pub const COEFFICIENT_PRIOR: f64 = 0.05;
+
+const POSE_PARAMETERS: usize = 6;
+const INTRINSIC_PARAMETERS: usize = 8;
+
+/// A point seen by `camera` of `dump` at raw image `pixel`.
+#[derive(Debug, Clone, Copy)]
+pub struct Observation {
+	pub point: usize,
+	pub dump: usize,
+	pub camera: usize,
+	pub pixel: Vector2<f64>,
+}
+
+/// Points triangulated from the stereo matches of every dump, and their observations in all dumps.
+pub struct BundleProblem {
+	pub rig: StereoRig,
+	/// Standing headset pose of each dump.
+	pub world_from_head: Vec<Isometry3<f64>>,
+	pub points: Vec<Point3<f64>>,
+	pub observations: Vec<Observation>,
+	/// How many observations are of points triangulated in another dump.
+	pub cross_observations: usize,
+}
+
+impl BundleProblem {
+	/// Builds the problem from dumps of a single headset, with the rig of the first one as the starting point.
+	pub fn new(dumps: &[Dump]) -> Result<BundleProblem, DumpError> {
+		let world_from_head = dumps.iter().map(|dump| dump.pose.ok_or(DumpError::NoPose)).collect::<Result<Vec<_>, _>>()?;
+		let stereo = dumps.iter().map(StereoFeatures::new).collect::<Result<Vec<_>, _>>()?;
+		let rig = stereo.first().ok_or(DumpError::NoDumps)?.rig.clone();
+		
+		let mut points = vec![];
+		let mut observations = vec![];
+		// Point index and left rectified feature of every point, per dump
+		let mut tracks = vec![vec![]; dumps.len()];
+		
+		for (dump, stereo) in stereo.iter().enumerate() {
+			let mut matches = stereo.matches.clone();
+			matches.sort_by(|a, b| b.correlation.total_cmp(&a.correlation));
+			matches.truncate(MAX_POINTS_PER_DUMP);
+			
+			for m in matches {
+				let pixels = match (stereo.rectification.unrectify_point(&rig, 0, &Vector2::from(m.first)),
+				                    stereo.rectification.unrectify_point(&rig, 1, &Vector2::from(m.second))) {
+					(Some(first), Some(second)) => [first, second],
+					_ => continue,
+				};
+				
+				let point = match triangulate(&rig, &pixels) {
+					Some(point) => point,
+					None => continue,
+				};
+				
+				let index = points.len();
+				points.push(world_from_head[dump] * point);
+				for (camera, pixel) in pixels.iter().enumerate() {
+					observations.push(Observation { point: index, dump, camera, pixel: *pixel });
+				}
+				
+				tracks[dump].push((index, Feature { x: m.first[0] as u32, y: m.first[1] as u32, response: 0.0 }));
+			}
+		}
+		
+		// Look for the points of every dump in the others, around where the headset poses say they should be
+		let mut cross_observations = 0;
+		
+		for (source, source_tracks) in tracks.iter().enumerate() {
+			for (dump, target) in stereo.iter().enumerate() {
+				if dump == source {
+					continue;
+				}
+				
+				for camera in 0..2 {
+					let predicted = source_tracks.iter().filter_map(|(point, feature)| {
+						let local = (world_from_head[dump] * rig.head_from_camera[camera]).inverse_transform_point(&points[*point]);
+						let pixel = target.rectification.camera.project(&(target.rectification.rotations[camera] * local.coords))?;
+						
+						Some(((feature.x, feature.y), (*point, pixel)))
+					}).collect::<HashMap<_, _>>();
+					
+					let features = source_tracks.iter().map(|(_, feature)| *feature).collect::<Vec<_>>();
+					let matches = match_features(&stereo[source].images[0], &target.images[camera], &features, &target.features[camera], |feature, other| {
+						predicted.get(&(feature.x, feature.y)).is_some_and(|(_, pixel)| {
+							(pixel - Vector2::new(other.x as f64 + 0.5, other.y as f64 + 0.5)).norm() <= GUIDED_MATCH_RADIUS
+						})
+					});
+					
+					for m in matches {
+						let point = predicted[&(m.first[0] as u32, m.first[1] as u32)].0;
+						
+						if let Some(pixel) = target.rectification.unrectify_point(&rig, camera, &Vector2::from(m.second)) {
+							observations.push(Observation { point, dump, camera, pixel });
+							cross_observations += 1;
+						}
+					}
+				}
+			}
+		}
+		
+		Ok(BundleProblem { rig, world_from_head, points, observations, cross_observations })
+	}
+}
+
+/// Midpoint of the closest approach of the two camera rays, in head space.
+fn triangulate(rig: &StereoRig, pixels: &[Vector2<f64>; 2]) -> Option<Point3<f64>> {
+	let ray = |camera: usize| -> Option<(Vector3<f64>, Vector3<f64>)> {
+		let pose = &rig.head_from_camera[camera];
+		Some((pose.translation.vector, pose.rotation * rig.cameras[camera].unproject(&pixels[camera])?))
+	};
+	
+	let (first_origin, first) = ray(0)?;
+	let (second_origin, second) = ray(1)?;
+	let w = first_origin - second_origin;
+	let b = first.dot(&second);
+	let (d, e) = (first.dot(&w), second.dot(&w));
+	let denominator = 1.0 - b * b;
+	
+	if denominator < 1e-9 {
+		return None;
+	}
+	
+	let s = (b * e - d) / denominator;
+	let t = (e - b * d) / denominator;
+	
+	if s < MIN_DEPTH || t < MIN_DEPTH || s > MAX_DEPTH || t > MAX_DEPTH {
+		return None;
+	}
+	
+	Some(Point3::from((first_origin + first * s + second_origin + second * t) / 2.0))
+}
+
+/// Refined calibration, with the rig parameterized as offsets from the config one.
+///
+/// The parameters are the first camera to head pose, the second camera pose relative to the first one (both as
+/// rotation vector and translation applied on the camera side) and, optionally, focal length, principal point and
+/// distortion coefficients of both cameras.
+pub struct BundleAdjustment {
+	pub initial_rig: StereoRig,
+	pub rig: StereoRig,
+	pub intrinsics: bool,
+	pub delta: DVector<f64>,
+	/// One standard deviation of every parameter.
+	pub sigma: DVector<f64>,
+	pub prior: DVector<f64>,
+	pub initial_rms: f64,
+	pub final_rms: f64,
+	pub iterations: usize,
+	pub dumps: usize,
+	pub points: usize,
+	pub observations: usize,
+	pub cross_observations: usize,
+}
+
+/// Normal equations of the problem, split between calibration and per point blocks.
+struct Linearization {
+	calibration: DMatrix<f64>,
+	gradient: DVector<f64>,
+	/// Point hessian, calibration/point cross term and point gradient.
+	points: Vec<(Matrix3<f64>, DMatrix<f64>, Vector3<f64>)>,
+}
+
+impl BundleAdjustment {
+	pub fn solve(problem: &BundleProblem, intrinsics: bool) -> BundleAdjustment {
+		let prior = prior(intrinsics);
+		let mut delta = DVector::zeros(prior.len());
+		let mut points = problem.points.clone();
+		let mut cost = total_cost(problem, &delta, &points, &prior);
+		let initial_rms = rms(problem, &rig(&problem.rig, &delta), &points);
+		let mut lambda = 1e-3;
+		let mut iterations = 0;
+		
+		while iterations < MAX_ITERATIONS && lambda < 1e10 {
+			iterations += 1;
+			let linearization = linearize(problem, &delta, &points, &prior, 1.0);
+			
+			loop {
+				let (step, point_steps) = match solve_step(&linearization, lambda) {
+					Some(step) => step,
+					None => { lambda *= 10.0; if lambda < 1e10 { continue } else { break } },
+				};
+				
+				let new_delta = &delta + &step;
+				let new_points = points.iter().zip(&point_steps).map(|(point, step)| point + step).collect::<Vec<_>>();
+				let new_cost = total_cost(problem, &new_delta, &new_points, &prior);
+				
+				if new_cost < cost {
+					let improvement = (cost - new_cost) / cost;
+					delta = new_delta;
+					points = new_points;
+					cost = new_cost;
+					lambda = (lambda / 10.0).max(1e-9);
+					
+					if improvement < 1e-8 {
+						lambda = f64::INFINITY;
+					}
+					break;
+				}
+				
+				lambda *= 10.0;
+				if lambda >= 1e10 {
+					break;
+				}
+			}
+		}
+		
+		let rig = rig(&problem.rig, &delta);
+		
+		// Covariance with the measurement noise estimated from the residuals
+		let residuals = problem.observations.iter().filter_map(|o| residual(&rig, problem, o, &points[o.point])).collect::<Vec<_>>();
+		let degrees_of_freedom = (2 * residuals.len()).saturating_sub(delta.len() + 3 * points.len()).max(1);
+		let variance = residuals.iter().map(|r| huber_weight(r.norm()) * r.norm_squared()).sum::<f64>() / degrees_of_freedom as f64;
+		let linearization = linearize(problem, &delta, &points, &prior, 1.0 / variance.max(1e-12));
+		let sigma = schur(&linearization, 0.0).and_then(|(normal, _, _)| normal.try_inverse())
+		                                     .map(|covariance| covariance.diagonal().map(|v| v.max(0.0).sqrt()))
+		                                     .unwrap_or_else(|| prior.clone());
+		
+		BundleAdjustment {
+			initial_rig: problem.rig.clone(),
+			final_rms: rms(problem, &rig, &points),
+			rig,
+			intrinsics,
+			delta,
+			sigma,
+			prior,
+			initial_rms,
+			iterations,
+			dumps: problem.world_from_head.len(),
+			points: points.len(),
+			observations: problem.observations.len(),
+			cross_observations: problem.cross_observations,
+		}
+	}
+	
+	/// The tracked cameras of `config` with the refined extrinsics, and intrinsics if they were refined.
+	pub fn corrected_cameras(&self, config: &LighthouseConfig) -> Vec<TrackedCameraConfig> {
+		config.tracked_cameras.iter().enumerate().map(|(index, camera)| {
+			let mut camera = camera.clone();
+			
+			if index < 2 {
+				camera.extrinsics = config.camera_extrinsics(&self.rig.head_from_camera[index]);
+				
+				if self.intrinsics {
+					camera.intrinsics = self.rig.cameras[index].to_config(&camera.intrinsics);
+				}
+			}
+			
+			camera
+		}).collect()
+	}
+	
+	/// Whether the dumps constrain a parameter, rather than it staying at its prior.
+	pub fn constrained(&self, parameter: usize) -> bool {
+		self.sigma[parameter] < self.prior[parameter] * 0.5
+	}
+}
+
+fn prior(intrinsics: bool) -> DVector<f64> {
+	let pose = [ROTATION_PRIOR.to_radians(), ROTATION_PRIOR.to_radians(), ROTATION_PRIOR.to_radians(), TRANSLATION_PRIOR, TRANSLATION_PRIOR, TRANSLATION_PRIOR];
+	let camera = [FOCAL_PRIOR, FOCAL_PRIOR, CENTER_PRIOR, CENTER_PRIOR, COEFFICIENT_PRIOR, COEFFICIENT_PRIOR, COEFFICIENT_PRIOR, COEFFICIENT_PRIOR];
+	let mut prior = pose.iter().chain(pose.iter()).cloned().collect::<Vec<_>>();
+	
+	if intrinsics {
+		prior.extend(camera.iter().chain(camera.iter()));
+	}
+	
+	DVector::from_vec(prior)
+}
+
+/// The rig with parameter offsets `delta` applied.
+fn rig(initial: &StereoRig, delta: &DVector<f64>) -> StereoRig {
+	let first_from_second = initial.head_from_camera[0].inverse() * initial.head_from_camera[1];
+	let first = apply_pose(&initial.head_from_camera[0], &delta.as_slice()[0..POSE_PARAMETERS]);
+	let second = first * apply_pose(&first_from_second, &delta.as_slice()[POSE_PARAMETERS..2 * POSE_PARAMETERS]);
+	let mut cameras = initial.cameras;
+	
+	if delta.len() > 2 * POSE_PARAMETERS {
+		for (index, camera) in cameras.iter_mut().enumerate() {
+			let d = &delta.as_slice()[2 * POSE_PARAMETERS + index * INTRINSIC_PARAMETERS..][..INTRINSIC_PARAMETERS];
+			camera.focal += Vector2::new(d[0], d[1]);
+			camera.center += Vector2::new(d[2], d[3]);
+			for (coeff, d) in camera.coeffs.iter_mut().zip(&d[4..]) {
+				*coeff += d;
+			}
+		}
+	}
+	
+	StereoRig { cameras, head_from_camera: [first, second] }
+}
+
+fn residual(rig: &StereoRig, problem: &BundleProblem, observation: &Observation, point: &Point3<f64>) -> Option<Vector2<f64>> {
+	let world_from_camera = problem.world_from_head[observation.dump] * rig.head_from_camera[observation.camera];
+	let local = world_from_camera.inverse_transform_point(point);
+	
+	if local.z <= 0.0 {
+		return None;
+	}
+	
+	Some(rig.cameras[observation.camera].project(&local.coords)? - observation.pixel)
+}
+
+fn huber_weight(norm: f64) -> f64 {
+	if norm <= HUBER_THRESHOLD { 1.0 } else { HUBER_THRESHOLD / norm }
+}
+
+fn huber_cost(norm: f64) -> f64 {
+	if norm <= HUBER_THRESHOLD { norm * norm / 2.0 } else { HUBER_THRESHOLD * (norm - HUBER_THRESHOLD / 2.0) }
+}
+
+fn total_cost(problem: &BundleProblem, delta: &DVector<f64>, points: &[Point3<f64>], prior: &DVector<f64>) -> f64 {
+	let rig = rig(&problem.rig, delta);
+	let data = problem.observations.iter()
+	                               .filter_map(|o| residual(&rig, problem, o, &points[o.point]))
+	                               .map(|r| huber_cost(r.norm()))
+	                               .sum::<f64>();
+	
+	data + delta.component_div(prior).norm_squared() / 2.0
+}
+
+fn rms(problem: &BundleProblem, rig: &StereoRig, points: &[Point3<f64>]) -> f64 {
+	let residuals = problem.observations.iter().filter_map(|o| residual(rig, problem, o, &points[o.point])).collect::<Vec<_>>();
+	
+	(residuals.iter().map(|r| r.norm_squared()).sum::<f64>() / residuals.len().max(1) as f64).sqrt()
+}
+
+/// Gauss-Newton normal equations with numeric jacobians and Huber weights, data terms scaled by `data_weight`.
+fn linearize(problem: &BundleProblem, delta: &DVector<f64>, points: &[Point3<f64>], prior: &DVector<f64>, data_weight: f64) -> Linearization {
+	let parameters = delta.len();
+	let rig_at = rig(&problem.rig, delta);
+	let perturbed = (0..parameters).map(|i| {
+		let step = 1e-6 * delta[i].abs().max(1.0);
+		let mut plus = delta.clone();
+		let mut minus = delta.clone();
+		plus[i] += step;
+		minus[i] -= step;
+		
+		(rig(&problem.rig, &plus), rig(&problem.rig, &minus), step)
+	}).collect::<Vec<_>>();
+	
+	let mut calibration = DMatrix::from_diagonal(&prior.map(|p| 1.0 / (p * p)));
+	let mut gradient = delta.component_div(&prior.component_mul(prior));
+	let mut blocks = vec![(Matrix3::zeros(), DMatrix::zeros(parameters, 3), Vector3::zeros()); points.len()];
+	
+	for observation in &problem.observations {
+		let point = &points[observation.point];
+		let r = match residual(&rig_at, problem, observation, point) {
+			Some(r) => r,
+			None => continue,
+		};
+		
+		let mut jacobian = DMatrix::zeros(2, parameters);
+		let mut point_jacobian = nalgebra::Matrix2x3::zeros();
+		let mut valid = true;
+		
+		for (i, (plus, minus, step)) in perturbed.iter().enumerate() {
+			match (residual(plus, problem, observation, point), residual(minus, problem, observation, point)) {
+				(Some(plus), Some(minus)) => jacobian.set_column(i, &((plus - minus) / (2.0 * step))),
+				_ => valid = false,
+			}
+		}
+		
+		for axis in 0..3 {
+			let step = 1e-6 * point.coords.norm().max(1.0);
+			let mut offset = Vector3::zeros();
+			offset[axis] = step;
+			
+			match (residual(&rig_at, problem, observation, &(point + offset)), residual(&rig_at, problem, observation, &(point - offset))) {
+				(Some(plus), Some(minus)) => point_jacobian.set_column(axis, &((plus - minus) / (2.0 * step))),
+				_ => valid = false,
+			}
+		}
+		
+		if !valid {
+			continue;
+		}
+		
+		let weight = huber_weight(r.norm()) * data_weight;
+		let (hessian, cross, point_gradient) = &mut blocks[observation.point];
+		
+		calibration += jacobian.transpose() * &jacobian * weight;
+		gradient += jacobian.transpose() * r * weight;
+		*hessian += point_jacobian.transpose() * point_jacobian * weight;
+		*cross += jacobian.transpose() * point_jacobian * weight;
+		*point_gradient += point_jacobian.transpose() * r * weight;
+	}
+	
+	Linearization { calibration, gradient, points: blocks }
+}
+
+/// Normal matrix and gradient of the calibration parameters, and the inverse damped point hessians.
+type ReducedSystem = (DMatrix<f64>, DVector<f64>, Vec<Option<Matrix3<f64>>>);
+
+/// Eliminates the points, returning the reduced calibration system and the inverse damped point hessians.
+fn schur(linearization: &Linearization, lambda: f64) -> Option<ReducedSystem> {
+	let mut normal = linearization.calibration.clone();
+	let mut gradient = linearization.gradient.clone();
+	
+	for i in 0..normal.nrows() {
+		normal[(i, i)] *= 1.0 + lambda;
+	}
+	
+	let inverses = linearization.points.iter().map(|(hessian, cross, point_gradient)| {
+		let damped = hessian + Matrix3::from_diagonal(&hessian.diagonal()) * lambda;
+		let inverse = damped.try_inverse()?;
+		
+		normal -= cross * inverse * cross.transpose();
+		gradient -= cross * (inverse * point_gradient);
+		Some(inverse)
+	}).collect::<Vec<_>>();
+	
+	if normal.iter().all(|v| v.is_finite()) { Some((normal, gradient, inverses)) } else { None }
+}
+
+/// Levenberg-Marquardt step for the calibration and every point.
+fn solve_step(linearization: &Linearization, lambda: f64) -> Option<(DVector<f64>, Vec<Vector3<f64>>)> {
+	let (normal, gradient, inverses) = schur(linearization, lambda)?;
+	let step = -normal.cholesky()?.solve(&gradient);
+	
+	let point_steps = linearization.points.iter().zip(&inverses).map(|((_, cross, point_gradient), inverse)| match inverse {
+		Some(inverse) => -(inverse * (point_gradient + cross.transpose() * &step)),
+		None => Vector3::zeros(),
+	}).collect();
+	
+	Some((step, point_steps))
+}
+
+const POSE_NAMES: [&str; POSE_PARAMETERS] = ["rotation x", "rotation y", "rotation z", "translation x", "translation y", "translation z"];
+const INTRINSIC_NAMES: [&str; INTRINSIC_PARAMETERS] = ["focal x", "focal y", "center x", "center y", "k1", "k2", "k3", "k4"];
+
+impl BundleAdjustment {
+	fn write_parameters(&self, f: &mut Formatter<'_>, offset: usize, names: &[&str], units: &dyn Fn(usize) -> (f64, &'static str)) -> Result<(), fmt::Error> {
+		for (i, name) in names.iter().enumerate() {
+			let (scale, unit) = units(i);
+			
+			writeln!(f, "  {:<14} {:+12.6} {} ± {:.6} {}{}", name, self.delta[offset + i] * scale, unit, self.sigma[offset + i] * scale, unit,
+			         if self.constrained(offset + i) { "" } else { "  (not constrained, prior)" })?;
+		}
+		
+		Ok(())
+	}
+}
+
+impl Display for BundleAdjustment {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		let pose_units = |i: usize| if i < 3 { (180.0 / std::f64::consts::PI, "deg") } else { (1000.0, "mm") };
+		let intrinsic_units = |i: usize| if i < 4 { (1.0, "px") } else { (1.0, "") };
+		let baseline = |rig: &StereoRig| (rig.head_from_camera[1].translation.vector - rig.head_from_camera[0].translation.vector).norm() * 1000.0;
+		
+		writeln!(f, "Bundle adjustment over {} dumps, {} points, {} observations ({} across dumps), {} iterations",
+		         self.dumps, self.points, self.observations, self.cross_observations, self.iterations)?;
+		writeln!(f, "Reprojection RMS: {:.3} px initial, {:.3} px refined", self.initial_rms, self.final_rms)?;
+		writeln!(f, "Baseline: {:.3} mm initial, {:.3} mm refined", baseline(&self.initial_rig), baseline(&self.rig))?;
+		
+		if self.cross_observations == 0 {
+			writeln!(f, "No points were matched across dumps, camera to head can't be refined")?;
+		}
+		
+		writeln!(f)?;
+		writeln!(f, "Camera 0 to head change:")?;
+		self.write_parameters(f, 0, &POSE_NAMES, &pose_units)?;
+		writeln!(f, "Camera 1 to camera 0 change:")?;
+		self.write_parameters(f, POSE_PARAMETERS, &POSE_NAMES, &pose_units)?;
+		
+		if self.intrinsics {
+			for index in 0..2 {
+				writeln!(f, "Camera {} intrinsics change:", index)?;
+				self.write_parameters(f, 2 * POSE_PARAMETERS + index * INTRINSIC_PARAMETERS, &INTRINSIC_NAMES, &intrinsic_units)?;
+			}
+		}
+		
+		for index in 0..2 {
+			let pose = &self.rig.head_from_camera[index];
+			let change = self.initial_rig.head_from_camera[index].inverse() * pose;
+			
+			writeln!(f)?;
+			writeln!(f, "Camera {} to head: position {:.6} {:.6} {:.6} m, moved {:.3} mm, rotated {:.4} deg",
+			         index, pose.translation.vector.x, pose.translation.vector.y, pose.translation.vector.z,
+			         change.translation.vector.norm() * 1000.0, change.rotation.angle().to_degrees())?;
+			
+			if self.intrinsics {
+				let camera = &self.rig.cameras[index];
+				writeln!(f, "Camera {} intrinsics: focal {:.3} {:.3}, center {:.3} {:.3}, coeffs {:?}",
+				         index, camera.focal.x, camera.focal.y, camera.center.x, camera.center.y, camera.coeffs)?;
+			}
+		}
+		
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nalgebra::{Translation3, UnitQuaternion};
+	use crate::calibration::FisheyeCamera;
+	
+	fn camera() -> FisheyeCamera {
+		FisheyeCamera {
+			focal: Vector2::new(280.0, 280.0),
+			center: Vector2::new(480.0, 480.0),
+			coeffs: [0.05, -0.01, 0.002, 0.0],
+			width: 960,
+			height: 960,
+		}
+	}
+	
+	/// Cameras 13 cm apart looking down -Z of the head, the second one slightly turned.
+	fn true_rig() -> StereoRig {
+		let forward = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f64::consts::PI);
+		let turn = UnitQuaternion::from_euler_angles(0.01, -0.02, 0.005);
+		
+		StereoRig {
+			cameras: [camera(), camera()],
+			head_from_camera: [
+				Isometry3::from_parts(Translation3::new(-0.065, 0.01, -0.08), forward),
+				Isometry3::from_parts(Translation3::new(0.066, 0.012, -0.079), forward * turn),
+			],
+		}
+	}
+	
+	/// Every point seen by both cameras of every head pose, without noise.
+	fn problem(rig: &StereoRig) -> BundleProblem {
+		// Turning the head in every direction is what separates the camera to head transforms from the points.
+		let world_from_head = [
+			(0.0, 0.0, 0.0, 0.0), (0.3, 0.2, 0.35, -0.1), (-0.2, -0.3, -0.35, 0.3), (0.1, 0.3, -0.1, -0.4), (-0.3, -0.2, 0.3, 0.35),
+		].iter().map(|&(x, roll, pitch, yaw)| {
+			Isometry3::from_parts(Translation3::new(x, 1.6, 0.0), UnitQuaternion::from_euler_angles(roll, pitch, yaw))
+		}).collect::<Vec<_>>();
+		
+		let mut seed = 12345u32;
+		let mut random = move || {
+			seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+			(seed >> 8) as f64 / (1 << 24) as f64
+		};
+		let points = (0..80).map(|_| Point3::new(random() * 3.0 - 1.5, 1.6 + random() * 2.0 - 1.0, -1.5 - random() * 3.0)).collect::<Vec<_>>();
+		
+		let mut observations = vec![];
+		for (dump, world_from_head) in world_from_head.iter().enumerate() {
+			for camera in 0..2 {
+				for (index, point) in points.iter().enumerate() {
+					let local = (world_from_head * rig.head_from_camera[camera]).inverse_transform_point(point);
+					let pixel = rig.cameras[camera].project(&local.coords).unwrap();
+					
+					observations.push(Observation { point: index, dump, camera, pixel });
+				}
+			}
+		}
+		
+		BundleProblem { rig: rig.clone(), world_from_head, points, cross_observations: observations.len(), observations }
+	}
+	
+	#[test]
+	fn recovers_the_rig() {
+		let truth = true_rig();
+		let mut problem = problem(&truth);
+		
+		// Start from a config that is off by about a degree and a few millimeters. The priors keep a little of that.
+		problem.rig.head_from_camera[0] = apply_pose(&truth.head_from_camera[0], &[0.004, -0.006, 0.01, 0.003, -0.002, 0.004]);
+		problem.rig.head_from_camera[1] = apply_pose(&truth.head_from_camera[1], &[-0.012, 0.008, -0.005, -0.004, 0.003, 0.002]);
+		for (index, point) in problem.points.iter_mut().enumerate() {
+			*point += Vector3::new(0.01, -0.02, 0.015) * (index % 3) as f64;
+		}
+		
+		let adjustment = BundleAdjustment::solve(&problem, false);
+		
+		assert!(adjustment.initial_rms > 1.0);
+		assert!(adjustment.final_rms < 0.05, "rms {}", adjustment.final_rms);
+		
+		for camera in 0..2 {
+			let error = truth.head_from_camera[camera].inverse() * adjustment.rig.head_from_camera[camera];
+			assert!(error.translation.vector.norm() < 1e-3, "camera {} off by {} m", camera, error.translation.vector.norm());
+			assert!(error.rotation.angle().to_degrees() < 0.02, "camera {} off by {} deg", camera, error.rotation.angle().to_degrees());
+		}
+		
+		let (second, expected) = (adjustment.rig.second_from_first(), truth.second_from_first());
+		assert!((second.translation.vector - expected.translation.vector).norm() < 1e-3);
+	}
+	
+	#[test]
+	fn triangulates_between_the_cameras() {
+		let rig = true_rig();
+		let point = Point3::new(0.2, -0.1, -2.0);
+		let pixels = [0, 1].map(|camera| rig.cameras[camera].project(&rig.head_from_camera[camera].inverse_transform_point(&point).coords).unwrap());
+		
+		assert!((triangulate(&rig, &pixels).unwrap() - point).norm() < 1e-9);
+	}
+}
@@ -0,0 +1,479 @@
+use std::fmt::{self, Display, Formatter};
+use nalgebra::{DMatrix, DVector, Isometry3, Point3, Vector2};
+
+use super::{Recording, DumpError, StereoRig, LighthouseConfig, TrackedCameraConfig, PoseDiff, apply_pose, solve_pnp, reprojection_errors};
+use crate::vision::{Board, BoardDetection, to_gray};
+
+pub const CALIBRATION_ITERATIONS: usize = 100;
+/// Views with fewer corners than this are not used.
+pub const MIN_BOARD_CORNERS: usize = 8;
+
+const INTRINSIC_PARAMETERS: usize = 8;
+const POSE_PARAMETERS: usize = 6;
+const RIG_OFFSET: usize = 2 * INTRINSIC_PARAMETERS;
+const POSES_OFFSET: usize = RIG_OFFSET + POSE_PARAMETERS;
+
+/// Board corners found in both cameras of every frame of a recording.
+pub struct BoardDetections {
+	pub rig: StereoRig,
+	/// Per frame, then per camera.
+	pub detections: Vec<[Option<BoardDetection>; 2]>,
+}
+
+impl BoardDetections {
+	/// Detects the board in every frame, with the config rig scaled to the recorded image size.
+	pub fn new(recording: &Recording, board: &Board) -> Result<BoardDetections, DumpError> {
+		let mut rig = None;
+		let mut detections = vec![];
+		
+		for frame in &recording.frames {
+			let images = frame.camera_images()?;
+			
+			if images.len() != 2 {
+				return Err(DumpError::NotStereo);
+			}
+			
+			if rig.is_none() {
+				rig = Some(StereoRig::from_config(&recording.config, images[0].width(), images[0].height()).ok_or(DumpError::NotStereo)?);
+			}
+			
+			let detect = |index: usize| board.detect(&to_gray(&images[index])).filter(|detection| detection.ids.len() >= MIN_BOARD_CORNERS);
+			detections.push([detect(0), detect(1)]);
+		}
+		
+		Ok(BoardDetections { rig: rig.ok_or(DumpError::NoDumps)?, detections })
+	}
+}
+
+/// Reprojection error of one camera of one frame.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewResidual {
+	/// Frame number in the recording.
+	pub frame: usize,
+	pub camera: usize,
+	pub corners: usize,
+	pub rms: f64,
+	pub max: f64,
+	/// RMS with the lighthouse calibration and the board pose solved for this view alone.
+	pub lighthouse_rms: Option<f64>,
+}
+
+/// Intrinsics of both cameras and their relative pose, fitted to board detections.
+///
+/// The first camera keeps its config pose in head space, the board can't tell where the head is.
+pub struct BoardCalibration {
+	pub initial_rig: StereoRig,
+	pub rig: StereoRig,
+	/// Whether some frame had the board in both cameras, otherwise the extrinsics are the config ones.
+	pub stereo: bool,
+	pub views: Vec<ViewResidual>,
+	/// One standard deviation of focal, center and coefficients of both cameras, then of the second camera pose.
+	pub sigma: DVector<f64>,
+	pub iterations: usize,
+	pub frames: usize,
+}
+
+/// Board points, pixels, camera and frame of one view, `pose` indexes the board poses of the frames with the board.
+struct View {
+	frame: usize,
+	pose: usize,
+	camera: usize,
+	object: Vec<Point3<f64>>,
+	pixels: Vec<Vector2<f64>>,
+}
+
+/// Current estimate: cameras, pose of the second camera relative to the first one and board pose in the first camera
+/// of every frame.
+#[derive(Clone)]
+struct State {
+	rig: StereoRig,
+	second_from_first: Isometry3<f64>,
+	poses: Vec<Isometry3<f64>>,
+}
+
+impl State {
+	fn camera_from_board(&self, view: &View) -> Isometry3<f64> {
+		if view.camera == 0 { self.poses[view.pose] } else { self.second_from_first * self.poses[view.pose] }
+	}
+	
+	fn residuals(&self, view: &View) -> Vec<Option<Vector2<f64>>> {
+		reprojection_errors(&self.rig.cameras[view.camera], &self.camera_from_board(view), &view.object, &view.pixels)
+	}
+	
+	fn cost(&self, views: &[View]) -> Option<f64> {
+		views.iter().flat_map(|view| self.residuals(view)).map(|r| r.map(|r| r.norm_squared())).sum()
+	}
+	
+	fn step(&self, step: &DVector<f64>) -> State {
+		let mut state = self.clone();
+		
+		for (index, camera) in state.rig.cameras.iter_mut().enumerate() {
+			let d = &step.as_slice()[index * INTRINSIC_PARAMETERS..][..INTRINSIC_PARAMETERS];
+			camera.focal += Vector2::new(d[0], d[1]);
+			camera.center += Vector2::new(d[2], d[3]);
+			for (coeff, d) in camera.coeffs.iter_mut().zip(&d[4..]) {
+				*coeff += d;
+			}
+		}
+		
+		state.second_from_first = apply_pose(&self.second_from_first, &step.as_slice()[RIG_OFFSET..POSES_OFFSET]);
+		for (frame, pose) in state.poses.iter_mut().enumerate() {
+			*pose = apply_pose(pose, &step.as_slice()[POSES_OFFSET + frame * POSE_PARAMETERS..][..POSE_PARAMETERS]);
+		}
+		
+		state
+	}
+	
+	/// Gauss-Newton normal equations with numeric jacobians, and the residual count.
+	fn linearize(&self, views: &[View], fixed: &[bool]) -> (DMatrix<f64>, DVector<f64>, usize) {
+		let parameters = fixed.len();
+		let mut normal = DMatrix::zeros(parameters, parameters);
+		let mut gradient = DVector::zeros(parameters);
+		let mut count = 0;
+		
+		for view in views {
+			// Only the intrinsics of its camera, the rig pose and its frame pose affect a view
+			let mut touched = (view.camera * INTRINSIC_PARAMETERS..(view.camera + 1) * INTRINSIC_PARAMETERS).collect::<Vec<_>>();
+			if view.camera == 1 {
+				touched.extend(RIG_OFFSET..POSES_OFFSET);
+			}
+			touched.extend(POSES_OFFSET + view.pose * POSE_PARAMETERS..POSES_OFFSET + (view.pose + 1) * POSE_PARAMETERS);
+			
+			let residuals = self.residuals(view);
+			let perturbed = touched.iter().map(|&parameter| {
+				let mut plus = DVector::zeros(parameters);
+				let mut minus = DVector::zeros(parameters);
+				plus[parameter] = 1e-6;
+				minus[parameter] = -1e-6;
+				
+				(self.step(&plus).residuals(view), self.step(&minus).residuals(view))
+			}).collect::<Vec<_>>();
+			
+			for (point, r) in residuals.iter().enumerate() {
+				let r = match r {
+					Some(r) => r,
+					None => continue,
+				};
+				
+				let jacobian = perturbed.iter().map(|(plus, minus)| match (plus[point], minus[point]) {
+					(Some(plus), Some(minus)) => Some((plus - minus) / 2e-6),
+					_ => None,
+				}).collect::<Option<Vec<_>>>();
+				
+				let jacobian = match jacobian {
+					Some(jacobian) => jacobian,
+					None => continue,
+				};
+				
+				count += 1;
+				for (a, &i) in touched.iter().enumerate() {
+					gradient[i] += jacobian[a].dot(r);
+					for (b, &j) in touched.iter().enumerate() {
+						normal[(i, j)] += jacobian[a].dot(&jacobian[b]);
+					}
+				}
+			}
+		}
+		
+		for (parameter, _) in fixed.iter().enumerate().filter(|(_, &fixed)| fixed) {
+			normal.row_mut(parameter).fill(0.0);
+			normal.column_mut(parameter).fill(0.0);
+			normal[(parameter, parameter)] = 1.0;
+			gradient[parameter] = 0.0;
+		}
+		
+		(normal, gradient, count)
+	}
+}
+
+impl BoardCalibration {
+	/// Fits the calibration with Levenberg-Marquardt, starting from the config rig and a PnP pose per frame.
+	pub fn solve(detections: &BoardDetections, board: &Board) -> Option<BoardCalibration> {
+		let initial_rig = detections.rig.clone();
+		let second_from_first = initial_rig.second_from_first();
+		let mut views = vec![];
+		let mut poses = vec![];
+		let mut lighthouse_rms = vec![];
+		
+		for (frame, detection) in detections.detections.iter().enumerate() {
+			let frame_views = detection.iter().enumerate().filter_map(|(camera, detection)| {
+				let detection = detection.as_ref()?;
+				let object = detection.ids.iter().map(|&id| Point3::from(board.object_point(id))).collect::<Vec<_>>();
+				let pixels = detection.pixels.iter().map(|&pixel| Vector2::from(pixel)).collect::<Vec<_>>();
+				let pose = solve_pnp(&initial_rig.cameras[camera], &object, &pixels)?;
+				
+				Some((View { frame, pose: poses.len(), camera, object, pixels }, pose))
+			}).collect::<Vec<_>>();
+			
+			let first_from_board = match frame_views.first() {
+				Some((view, pose)) if view.camera == 0 => *pose,
+				Some((_, pose)) => second_from_first.inverse() * pose,
+				None => continue,
+			};
+			
+			for (view, pose) in frame_views {
+				lighthouse_rms.push(rms(&reprojection_errors(&initial_rig.cameras[view.camera], &pose, &view.object, &view.pixels)));
+				views.push(view);
+			}
+			poses.push(first_from_board);
+		}
+		
+		if views.is_empty() {
+			return None;
+		}
+		
+		let stereo = (0..poses.len()).any(|pose| views.iter().filter(|view| view.pose == pose).count() == 2);
+		let mut fixed = vec![false; POSES_OFFSET + poses.len() * POSE_PARAMETERS];
+		for camera in 0..2 {
+			if !views.iter().any(|view| view.camera == camera) {
+				fixed[camera * INTRINSIC_PARAMETERS..(camera + 1) * INTRINSIC_PARAMETERS].iter_mut().for_each(|fixed| *fixed = true);
+			}
+		}
+		if !stereo {
+			fixed[RIG_OFFSET..POSES_OFFSET].iter_mut().for_each(|fixed| *fixed = true);
+		}
+		
+		let mut state = State { rig: initial_rig.clone(), second_from_first, poses };
+		let mut cost = state.cost(&views)?;
+		let mut lambda = 1e-3;
+		let mut iterations = 0;
+		
+		while iterations < CALIBRATION_ITERATIONS {
+			iterations += 1;
+			let (normal, gradient, _) = state.linearize(&views, &fixed);
+			let mut improved = false;
+			
+			while lambda < 1e10 {
+				let damped = &normal + DMatrix::from_diagonal(&normal.diagonal()) * lambda;
+				let step = match damped.cholesky() {
+					Some(cholesky) => -cholesky.solve(&gradient),
+					None => { lambda *= 10.0; continue },
+				};
+				
+				let candidate = state.step(&step);
+				match candidate.cost(&views) {
+					Some(new_cost) if new_cost < cost => {
+						improved = (cost - new_cost) / cost.max(1e-12) > 1e-10;
+						state = candidate;
+						cost = new_cost;
+						lambda = (lambda / 10.0).max(1e-9);
+						break;
+					},
+					_ => lambda *= 10.0,
+				}
+			}
+			
+			if !improved {
+				break;
+			}
+		}
+		
+		// Covariance with the measurement noise estimated from the residuals
+		let (normal, _, count) = state.linearize(&views, &fixed);
+		let degrees_of_freedom = (2 * count).saturating_sub(fixed.iter().filter(|&&fixed| !fixed).count()).max(1);
+		let variance = cost / degrees_of_freedom as f64;
+		let sigma = normal.try_inverse()
+		                  .map(|covariance| covariance.diagonal().rows(0, POSES_OFFSET).map(|v| (v * variance).max(0.0).sqrt()))
+		                  .unwrap_or_else(|| DVector::from_element(POSES_OFFSET, f64::NAN));
+		let sigma = DVector::from_iterator(POSES_OFFSET, sigma.iter().zip(&fixed).map(|(&sigma, &fixed)| if fixed { 0.0 } else { sigma }));
+		
+		let residuals = views.iter().zip(lighthouse_rms).map(|(view, lighthouse_rms)| {
+			let errors = state.residuals(view);
+			
+			ViewResidual {
+				frame: view.frame,
+				camera: view.camera,
+				corners: view.object.len(),
+				rms: rms(&errors),
+				max: errors.iter().flatten().map(|r| r.norm()).fold(0.0, f64::max),
+				lighthouse_rms: Some(lighthouse_rms).filter(|rms| rms.is_finite()),
+			}
+		}).collect();
+		
+		let head_from_first = initial_rig.head_from_camera[0];
+		
+		Some(BoardCalibration {
+			rig: StereoRig {
+				cameras: state.rig.cameras,
+				head_from_camera: [head_from_first, head_from_first * state.second_from_first.inverse()],
+			},
+			initial_rig,
+			stereo,
+			views: residuals,
+			sigma,
+			iterations,
+			frames: state.poses.len(),
+		})
+	}
+	
+	/// The tracked cameras of `config` with the calibrated intrinsics and second camera extrinsics.
+	pub fn corrected_cameras(&self, config: &LighthouseConfig) -> Vec<TrackedCameraConfig> {
+		config.tracked_cameras.iter().enumerate().map(|(index, camera)| {
+			let mut camera = camera.clone();
+			
+			if index < 2 {
+				camera.intrinsics = self.rig.cameras[index].to_config(&camera.intrinsics);
+				
+				if index == 1 && self.stereo {
+					camera.extrinsics = config.camera_extrinsics(&self.rig.head_from_camera[index]);
+				}
+			}
+			
+			camera
+		}).collect()
+	}
+	
+	/// RMS over all views of a camera, with this calibration and with the lighthouse one. Views the lighthouse
+	/// calibration couldn't project are left out of its RMS.
+	pub fn camera_rms(&self, camera: usize) -> (f64, f64) {
+		let views = self.views.iter().filter(|view| view.camera == camera);
+		let (mut sum, mut count, mut lighthouse_sum, mut lighthouse_count) = (0.0, 0, 0.0, 0);
+		
+		for view in views {
+			sum += view.rms * view.rms * view.corners as f64;
+			count += view.corners;
+			
+			if let Some(lighthouse_rms) = view.lighthouse_rms {
+				lighthouse_sum += lighthouse_rms * lighthouse_rms * view.corners as f64;
+				lighthouse_count += view.corners;
+			}
+		}
+		
+		((sum / count.max(1) as f64).sqrt(), (lighthouse_sum / lighthouse_count.max(1) as f64).sqrt())
+	}
+}
+
+fn rms(errors: &[Option<Vector2<f64>>]) -> f64 {
+	let errors = errors.iter().flatten().collect::<Vec<_>>();
+	
+	if errors.is_empty() {
+		return f64::NAN;
+	}
+	
+	(errors.iter().map(|r| r.norm_squared()).sum::<f64>() / errors.len() as f64).sqrt()
+}
+
+impl Display for BoardCalibration {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Frames with the board: {}, views: {}, iterations: {}", self.frames, self.views.len(), self.iterations)?;
+		
+		for (index, (initial, camera)) in self.initial_rig.cameras.iter().zip(&self.rig.cameras).enumerate() {
+			let sigma = &self.sigma.as_slice()[index * INTRINSIC_PARAMETERS..][..INTRINSIC_PARAMETERS];
+			let (rms, lighthouse_rms) = self.camera_rms(index);
+			
+			writeln!(f)?;
+			writeln!(f, "Camera {}: RMS {:.3} px, lighthouse calibration {:.3} px", index, rms, lighthouse_rms)?;
+			writeln!(f, "  Focal: {:.3} {:.3} (lighthouse {:.3} {:.3}, sigma {:.3} {:.3})",
+			         camera.focal.x, camera.focal.y, initial.focal.x, initial.focal.y, sigma[0], sigma[1])?;
+			writeln!(f, "  Center: {:.3} {:.3} (lighthouse {:.3} {:.3}, sigma {:.3} {:.3})",
+			         camera.center.x, camera.center.y, initial.center.x, initial.center.y, sigma[2], sigma[3])?;
+			
+			for (coeff, ((value, initial), sigma)) in camera.coeffs.iter().zip(&initial.coeffs).zip(&sigma[4..]).enumerate() {
+				writeln!(f, "  k{}: {:+.6} (lighthouse {:+.6}, sigma {:.6})", coeff + 1, value, initial, sigma)?;
+			}
+		}
+		
+		writeln!(f)?;
+		if self.stereo {
+			let diff = PoseDiff::new(&self.initial_rig.second_from_first(), &self.rig.second_from_first());
+			let sigma = &self.sigma.as_slice()[RIG_OFFSET..POSES_OFFSET];
+			
+			writeln!(f, "Second camera from first: baseline {:.6} m (lighthouse {:.6} m)",
+			         self.rig.second_from_first().translation.vector.norm(), self.initial_rig.second_from_first().translation.vector.norm())?;
+			writeln!(f, "  Difference to lighthouse: {}", diff)?;
+			writeln!(f, "  Sigma: rotation {:.4} {:.4} {:.4} deg, translation {:.3} {:.3} {:.3} mm",
+			         sigma[0].to_degrees(), sigma[1].to_degrees(), sigma[2].to_degrees(), sigma[3] * 1000.0, sigma[4] * 1000.0, sigma[5] * 1000.0)?;
+		} else {
+			writeln!(f, "No frame has the board in both cameras, the extrinsics are not calibrated")?;
+		}
+		
+		writeln!(f)?;
+		writeln!(f, "Per view residuals:")?;
+		for view in &self.views {
+			write!(f, "  Frame {} camera {}: {} corners, RMS {:.3} px, max {:.3} px", view.frame, view.camera, view.corners, view.rms, view.max)?;
+			match view.lighthouse_rms {
+				Some(lighthouse_rms) => writeln!(f, ", lighthouse calibration RMS {:.3} px", lighthouse_rms)?,
+				None => writeln!(f)?,
+			}
+		}
+		
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nalgebra::{Translation3, UnitQuaternion, Vector3};
+	use crate::calibration::FisheyeCamera;
+	
+	fn rig() -> StereoRig {
+		let camera = FisheyeCamera {
+			focal: Vector2::new(280.0, 280.0),
+			center: Vector2::new(480.0, 480.0),
+			coeffs: [0.05, -0.01, 0.002, 0.0],
+			width: 960,
+			height: 960,
+		};
+		let forward = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f64::consts::PI);
+		
+		StereoRig {
+			cameras: [camera, camera],
+			head_from_camera: [
+				Isometry3::from_parts(Translation3::new(-0.065, 0.0, -0.08), forward),
+				Isometry3::from_parts(Translation3::new(0.065, 0.0, -0.08), forward),
+			],
+		}
+	}
+	
+	/// The board in the recording frames listed, seen by the cameras listed with it.
+	fn detections(board: &Board, frames: &[(usize, &[usize])]) -> BoardDetections {
+		let rig = rig();
+		let mut detections = vec![[None, None]; frames.iter().map(|&(frame, _)| frame + 1).max().unwrap()];
+		
+		for (index, &(frame, cameras)) in frames.iter().enumerate() {
+			let tilt = index as f64 * 0.15 - 0.2;
+			let first_from_board = Isometry3::from_parts(Translation3::new(-0.1, -0.08, 0.5), UnitQuaternion::from_euler_angles(tilt, -tilt * 0.5, tilt * 0.3));
+			
+			for &camera in cameras {
+				let camera_from_board = if camera == 0 { first_from_board } else { rig.second_from_first() * first_from_board };
+				let ids = (0..board.corner_count() as u32).collect::<Vec<_>>();
+				let pixels = ids.iter().map(|&id| {
+					let pixel = rig.cameras[camera].project(&(camera_from_board * Point3::from(board.object_point(id))).coords).unwrap();
+					[pixel.x, pixel.y]
+				}).collect();
+				
+				detections[frame][camera] = Some(BoardDetection { ids, pixels });
+			}
+		}
+		
+		BoardDetections { rig, detections }
+	}
+	
+	#[test]
+	fn views_keep_their_recording_frame() {
+		let board = Board::parse("checkerboard:8x6:0.03").unwrap();
+		let detections = detections(&board, &[(1, &[0]), (3, &[0, 1]), (4, &[1]), (7, &[0, 1])]);
+		let calibration = BoardCalibration::solve(&detections, &board).unwrap();
+		
+		let frames = calibration.views.iter().map(|view| (view.frame, view.camera)).collect::<Vec<_>>();
+		assert_eq!(frames, [(1, 0), (3, 0), (3, 1), (4, 1), (7, 0), (7, 1)]);
+		assert_eq!(calibration.frames, 4);
+		assert!(calibration.stereo);
+		assert!(calibration.camera_rms(0).0 < 1e-3);
+	}
+	
+	#[test]
+	fn lighthouse_rms_skips_views_without_one() {
+		let board = Board::parse("checkerboard:8x6:0.03").unwrap();
+		let detections = detections(&board, &[(0, &[0, 1]), (1, &[0, 1]), (2, &[0, 1])]);
+		let mut calibration = BoardCalibration::solve(&detections, &board).unwrap();
+		
+		calibration.views[0].lighthouse_rms = None;
+		calibration.views[2].lighthouse_rms = Some(0.5);
+		calibration.views[4].lighthouse_rms = Some(0.5);
+		
+		let (_, lighthouse_rms) = calibration.camera_rms(0);
+		assert!((lighthouse_rms - 0.5).abs() < 1e-9, "{}", lighthouse_rms);
+	}
+}
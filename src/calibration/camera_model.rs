@@ -0,0 +1,229 @@
+use nalgebra::{Vector2, Vector3, Matrix3};
+
+use super::IntrinsicsConfig;
+
+/// Kannala-Brandt fisheye model, which is what the lighthouse config calls `DISTORT_FTHETA`.
+///
+/// Works in the computer vision camera frame: +X right, +Y down, +Z forward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FisheyeCamera {
+	pub focal: Vector2<f64>,
+	pub center: Vector2<f64>,
+	pub coeffs: [f64; 4],
+	pub width: u32,
+	pub height: u32,
+}
+
+impl FisheyeCamera {
+	/// Builds the model from config intrinsics, rescaled to an image of `width`x`height`.
+	pub fn from_config(intrinsics: &IntrinsicsConfig, width: u32, height: u32) -> FisheyeCamera {
+		let scale = Vector2::new(width as f64 / intrinsics.width as f64, height as f64 / intrinsics.height as f64);
+		let mut coeffs = [0.0; 4];
+		for (out, coeff) in coeffs.iter_mut().zip(intrinsics.distort.coeffs.iter()) {
+			*out = *coeff;
+		}
+		
+		FisheyeCamera {
+			focal: Vector2::new(intrinsics.focal_x, intrinsics.focal_y).component_mul(&scale),
+			center: Vector2::new(intrinsics.center_x, intrinsics.center_y).component_mul(&scale),
+			coeffs,
+			width,
+			height,
+		}
+	}
+	
+	/// Inverse of `from_config`, scaled back to the resolution of `original`.
+	pub fn to_config(self, original: &IntrinsicsConfig) -> IntrinsicsConfig {
+		let scale = Vector2::new(original.width as f64 / self.width as f64, original.height as f64 / self.height as f64);
+		let focal = self.focal.component_mul(&scale);
+		let center = self.center.component_mul(&scale);
+		let mut distort = original.distort.clone();
+		
+		for (coeff, value) in distort.coeffs.iter_mut().zip(self.coeffs.iter()) {
+			*coeff = *value;
+		}
+		
+		IntrinsicsConfig {
+			center_x: center.x,
+			center_y: center.y,
+			focal_x: focal.x,
+			focal_y: focal.y,
+			distort,
+			..original.clone()
+		}
+	}
+	
+	pub fn distort_theta(&self, theta: f64) -> f64 {
+		let theta2 = theta * theta;
+		let [k1, k2, k3, k4] = self.coeffs;
+		
+		theta * (1.0 + theta2 * (k1 + theta2 * (k2 + theta2 * (k3 + theta2 * k4))))
+	}
+	
+	fn distort_theta_derivative(&self, theta: f64) -> f64 {
+		let theta2 = theta * theta;
+		let [k1, k2, k3, k4] = self.coeffs;
+		
+		1.0 + theta2 * (3.0 * k1 + theta2 * (5.0 * k2 + theta2 * (7.0 * k3 + theta2 * 9.0 * k4)))
+	}
+	
+	/// Inverts `distort_theta` with Newton's method.
+	pub fn undistort_theta(&self, theta_d: f64) -> Option<f64> {
+		let mut theta = theta_d;
+		
+		for _ in 0..20 {
+			let step = (self.distort_theta(theta) - theta_d) / self.distort_theta_derivative(theta);
+			theta -= step;
+			
+			if step.abs() < 1e-12 {
+				break;
+			}
+		}
+		
+		if theta.is_finite() && theta >= 0.0 && (self.distort_theta(theta) - theta_d).abs() < 1e-6 {
+			Some(theta)
+		} else {
+			None
+		}
+	}
+	
+	/// Projects a point in camera space to pixel coordinates.
+	pub fn project(&self, point: &Vector3<f64>) -> Option<Vector2<f64>> {
+		let r = point.xy().norm();
+		let theta = r.atan2(point.z);
+		
+		// The polynomial is only fitted over the lens FOV, past that it folds back on itself.
+		if self.distort_theta_derivative(theta) <= 0.0 {
+			return None;
+		}
+		
+		let scale = if r > 1e-12 { self.distort_theta(theta) / r } else { 1.0 / point.z };
+		
+		Some(self.center + self.focal.component_mul(&(point.xy() * scale)))
+	}
+	
+	/// Returns the unit ray for a pixel.
+	pub fn unproject(&self, pixel: &Vector2<f64>) -> Option<Vector3<f64>> {
+		let m = (pixel - self.center).component_div(&self.focal);
+		let theta_d = m.norm();
+		
+		if theta_d < 1e-12 {
+			return Some(Vector3::z());
+		}
+		
+		let theta = self.undistort_theta(theta_d)?;
+		let xy = m * (theta.sin() / theta_d);
+		
+		Some(Vector3::new(xy.x, xy.y, theta.cos()))
+	}
+	
+	pub fn contains(&self, pixel: &Vector2<f64>) -> bool {
+		pixel.x >= 0.0 && pixel.y >= 0.0 && pixel.x < self.width as f64 && pixel.y < self.height as f64
+	}
+}
+
+/// Ideal pinhole camera, used for undistorted and rectified images.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PinholeCamera {
+	pub focal: Vector2<f64>,
+	pub center: Vector2<f64>,
+	pub width: u32,
+	pub height: u32,
+}
+
+impl PinholeCamera {
+	pub fn project(&self, point: &Vector3<f64>) -> Option<Vector2<f64>> {
+		if point.z <= 0.0 {
+			return None;
+		}
+		
+		Some(self.center + self.focal.component_mul(&(point.xy() / point.z)))
+	}
+	
+	/// Returns the ray with z = 1 for a pixel.
+	pub fn unproject(&self, pixel: &Vector2<f64>) -> Vector3<f64> {
+		let m = (pixel - self.center).component_div(&self.focal);
+		
+		Vector3::new(m.x, m.y, 1.0)
+	}
+	
+	pub fn matrix(&self) -> Matrix3<f64> {
+		Matrix3::new(self.focal.x, 0.0, self.center.x,
+		             0.0, self.focal.y, self.center.y,
+		             0.0, 0.0, 1.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::calibration::DistortConfig;
+	
+	/// Roughly one of the Index cameras at the resolution of a single camera image.
+	fn fisheye() -> FisheyeCamera {
+		FisheyeCamera {
+			focal: Vector2::new(420.0, 421.5),
+			center: Vector2::new(481.0, 476.5),
+			coeffs: [0.075, -0.021, 0.004, -0.0007],
+			width: 960,
+			height: 960,
+		}
+	}
+	
+	#[test]
+	fn fisheye_rays_survive_projection() {
+		let camera = fisheye();
+		
+		for degrees in [0.0, 5.0, 30.0, 60.0, 80.0] {
+			for azimuth in [0.0, 1.0, 2.5, 4.0] {
+				let theta = f64::to_radians(degrees);
+				let ray = Vector3::new(theta.sin() * f64::cos(azimuth), theta.sin() * f64::sin(azimuth), theta.cos());
+				
+				let pixel = camera.project(&(ray * 3.0)).unwrap();
+				let back = camera.unproject(&pixel).unwrap();
+				
+				assert!((back - ray).norm() < 1e-9, "{} deg at {}: {} != {}", degrees, azimuth, back, ray);
+			}
+		}
+	}
+	
+	#[test]
+	fn fisheye_pixels_survive_unprojection() {
+		let camera = fisheye();
+		
+		for pixel in [Vector2::new(481.0, 476.5), Vector2::new(0.5, 0.5), Vector2::new(900.0, 100.0), Vector2::new(300.0, 700.0)] {
+			let back = camera.project(&camera.unproject(&pixel).unwrap()).unwrap();
+			
+			assert!((back - pixel).norm() < 1e-6, "{} != {}", back, pixel);
+		}
+	}
+	
+	#[test]
+	fn pinhole_pixels_survive_unprojection() {
+		let camera = PinholeCamera { focal: Vector2::new(300.0, 310.0), center: Vector2::new(320.5, 240.0), width: 640, height: 480 };
+		let pixel = Vector2::new(12.25, 470.0);
+		
+		assert!((camera.project(&(camera.unproject(&pixel) * 2.0)).unwrap() - pixel).norm() < 1e-9);
+		assert_eq!(camera.project(&Vector3::new(0.0, 0.0, -1.0)), None);
+	}
+	
+	#[test]
+	fn config_survives_rescaling() {
+		let config = IntrinsicsConfig {
+			center_x: 481.0,
+			center_y: 476.5,
+			focal_x: 420.0,
+			focal_y: 421.5,
+			width: 960,
+			height: 960,
+			distort: DistortConfig { coeffs: vec![0.075, -0.021, 0.004, -0.0007], ty: "DISTORT_FTHETA".into() },
+		};
+		
+		let half = FisheyeCamera::from_config(&config, 480, 480);
+		assert_eq!(half.focal, Vector2::new(210.0, 210.75));
+		
+		let back = half.to_config(&config);
+		assert_eq!((back.focal_x, back.focal_y, back.center_x, back.center_y), (420.0, 421.5, 481.0, 476.5));
+		assert_eq!(back.distort.coeffs, config.distort.coeffs);
+	}
+}
@@ -0,0 +1,364 @@
+use std::fmt::{self, Display, Formatter};
+use nalgebra::{Isometry3, Vector3};
+use serde_json::Value;
+
+use super::{ConfigError, LighthouseConfig, FieldChange, diff_json, PIXEL_TOLERANCE, COEFFICIENT_TOLERANCE};
+
+/// Smallest position difference worth reporting, in meters.
+pub const POSITION_TOLERANCE: f64 = 0.0001;
+/// Smallest rotation difference worth reporting, in degrees.
+pub const ROTATION_TOLERANCE: f64 = 0.01;
+/// Other numbers are equal when they differ by less than this, relative to their magnitude.
+pub const NUMBER_TOLERANCE: f64 = 1e-9;
+
+/// Camera intrinsics fields `CameraDiff` reports, besides the distortion coefficients.
+const INTRINSICS_FIELDS: &[&str] = &["center_x", "center_y", "focal_x", "focal_y", "width", "height", "distort.type"];
+
+/// Pose of `new` relative to `old`.
+#[derive(Debug, Clone, Copy)]
+pub struct PoseDiff {
+	/// Position change in the parent frame, meters.
+	pub translation: Vector3<f64>,
+	/// Rotation angle, degrees.
+	pub angle: f64,
+	/// Rotation vector in the `old` frame, degrees.
+	pub rotation: Vector3<f64>,
+}
+
+impl PoseDiff {
+	pub fn new(old: &Isometry3<f64>, new: &Isometry3<f64>) -> PoseDiff {
+		let rotation = old.rotation.inverse() * new.rotation;
+		
+		PoseDiff {
+			translation: new.translation.vector - old.translation.vector,
+			angle: rotation.angle().to_degrees(),
+			rotation: rotation.scaled_axis().map(f64::to_degrees),
+		}
+	}
+	
+	pub fn differs(&self) -> bool {
+		self.translation.norm() > POSITION_TOLERANCE || self.angle > ROTATION_TOLERANCE
+	}
+}
+
+impl Display for PoseDiff {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		let t = self.translation * 1000.0;
+		let r = &self.rotation;
+		
+		write!(f, "moved {:.3} mm ({:+.3} {:+.3} {:+.3}), rotated {:.4} deg ({:+.4} {:+.4} {:+.4}){}",
+		       t.norm(), t.x, t.y, t.z, self.angle, r.x, r.y, r.z, marker(self.differs()))
+	}
+}
+
+fn marker(differs: bool) -> &'static str {
+	if differs { "  [differs]" } else { "" }
+}
+
+#[derive(Debug, Clone)]
+pub struct CameraDiff {
+	pub index: usize,
+	pub names: (String, String),
+	/// Extrinsics as written in the configs.
+	pub extrinsics: PoseDiff,
+	/// Camera to head, when either config has a head pose.
+	pub head: Option<PoseDiff>,
+	/// Focal length and principal point shifts in pixels, at the resolution of the old config.
+	pub focal: [f64; 2],
+	pub center: [f64; 2],
+	pub resolutions: ((u32, u32), (u32, u32)),
+	pub distortion: (String, String),
+	/// Coefficient differences, if both configs have the same number of them.
+	pub coeffs: Option<Vec<f64>>,
+}
+
+impl CameraDiff {
+	pub fn intrinsics_differ(&self) -> bool {
+		self.focal.iter().chain(self.center.iter()).any(|d| d.abs() > PIXEL_TOLERANCE)
+			|| self.distortion.0 != self.distortion.1
+			|| self.coeffs.as_ref().is_none_or(|coeffs| coeffs.iter().any(|d| d.abs() > COEFFICIENT_TOLERANCE))
+	}
+	
+	pub fn differs(&self) -> bool {
+		self.extrinsics.differs() || self.head.is_some_and(|head| head.differs()) || self.intrinsics_differ()
+	}
+}
+
+/// Semantic differences between two lighthouse configs.
+#[derive(Debug, Clone)]
+pub struct ConfigDiff {
+	pub camera_counts: (usize, usize),
+	pub cameras: Vec<CameraDiff>,
+	/// Baselines in meters, and the second camera pose relative to the first one.
+	pub stereo: Option<((f64, f64), PoseDiff)>,
+	pub head: Option<PoseDiff>,
+	/// Every other field that was added, removed or changed.
+	pub fields: Vec<FieldChange>,
+}
+
+impl ConfigDiff {
+	pub fn new(old: &str, new: &str) -> Result<ConfigDiff, ConfigError> {
+		let (old_config, new_config) = (LighthouseConfig::parse(old)?, LighthouseConfig::parse(new)?);
+		let (old_raw, new_raw): (Value, Value) = (serde_json::from_str(old)?, serde_json::from_str(new)?);
+		let has_head = old_config.head.is_some() || new_config.head.is_some();
+		
+		let cameras = old_config.tracked_cameras.iter().zip(&new_config.tracked_cameras).enumerate().map(|(index, (old, new))| {
+			let (a, b) = (&old.intrinsics, &new.intrinsics);
+			let scale = [a.width as f64 / b.width as f64, a.height as f64 / b.height as f64];
+			
+			CameraDiff {
+				index,
+				names: (old.name.clone(), new.name.clone()),
+				extrinsics: PoseDiff::new(&old.extrinsics.isometry(), &new.extrinsics.isometry()),
+				head: if has_head {
+					Some(PoseDiff::new(&old_config.head_from_camera(index).unwrap(), &new_config.head_from_camera(index).unwrap()))
+				} else {
+					None
+				},
+				focal: [b.focal_x * scale[0] - a.focal_x, b.focal_y * scale[1] - a.focal_y],
+				center: [b.center_x * scale[0] - a.center_x, b.center_y * scale[1] - a.center_y],
+				resolutions: ((a.width, a.height), (b.width, b.height)),
+				distortion: (a.distort.ty.clone(), b.distort.ty.clone()),
+				coeffs: if a.distort.coeffs.len() == b.distort.coeffs.len() {
+					Some(a.distort.coeffs.iter().zip(&b.distort.coeffs).map(|(a, b)| b - a).collect())
+				} else {
+					None
+				},
+			}
+		}).collect::<Vec<_>>();
+		
+		let stereo = match (old_config.head_from_camera(1), new_config.head_from_camera(1)) {
+			(Some(old_second), Some(new_second)) => {
+				let old_first = old_config.head_from_camera(0).unwrap();
+				let new_first = new_config.head_from_camera(0).unwrap();
+				let baseline = |first: &Isometry3<f64>, second: &Isometry3<f64>| (second.translation.vector - first.translation.vector).norm();
+				
+				Some((
+					(baseline(&old_first, &old_second), baseline(&new_first, &new_second)),
+					PoseDiff::new(&(old_first.inverse() * old_second), &(new_first.inverse() * new_second)),
+				))
+			},
+			_ => None,
+		};
+		
+		let head = match (&old_config.head, &new_config.head) {
+			(Some(old), Some(new)) => Some(PoseDiff::new(&old.isometry(), &new.isometry())),
+			_ => None,
+		};
+		
+		// Fields covered above are left out, as are numbers that only differ by rounding
+		let covered = |path: &str| {
+			match (0..cameras.len()).find_map(|index| path.strip_prefix(&format!("tracked_cameras[{}].", index))) {
+				Some(field) => {
+					field.strip_prefix("extrinsics.").is_some_and(is_pose_field)
+						|| field.strip_prefix("intrinsics.").is_some_and(|field| INTRINSICS_FIELDS.contains(&field) || is_coefficient(field))
+				},
+				None => head.is_some() && path.strip_prefix("head.").is_some_and(is_pose_field),
+			}
+		};
+		
+		let fields = diff_json(&old_raw, &new_raw).into_iter().filter(|change| match change {
+			FieldChange::Changed(path, Value::Number(a), Value::Number(b)) if !covered(path) => {
+				let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+				(a - b).abs() > NUMBER_TOLERANCE * a.abs().max(b.abs()).max(1.0)
+			},
+			change => !covered(change.path()),
+		}).collect();
+		
+		Ok(ConfigDiff {
+			camera_counts: (old_config.tracked_cameras.len(), new_config.tracked_cameras.len()),
+			cameras,
+			stereo,
+			head,
+			fields,
+		})
+	}
+	
+	/// How many things differ beyond their tolerance.
+	pub fn differences(&self) -> usize {
+		self.cameras.iter().filter(|camera| camera.differs()).count()
+			+ self.stereo.map_or(0, |(_, pose)| pose.differs() as usize)
+			+ self.head.map_or(0, |head| head.differs() as usize)
+			+ self.fields.len()
+			+ (self.camera_counts.0 != self.camera_counts.1) as usize
+	}
+}
+
+/// `plus_x[0]` to `position[2]` of an `ExtrinsicsConfig`.
+fn is_pose_field(field: &str) -> bool {
+	["plus_x", "plus_z", "position"].iter().any(|name| (0..3).any(|index| field == format!("{}[{}]", name, index)))
+}
+
+/// `distort.coeffs[<index>]`.
+fn is_coefficient(field: &str) -> bool {
+	field.strip_prefix("distort.coeffs[")
+	     .and_then(|index| index.strip_suffix(']'))
+	     .is_some_and(|index| index.parse::<usize>().is_ok())
+}
+
+impl Display for ConfigDiff {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Tolerances: position {} mm, rotation {} deg, pixels {} px, coefficients {}",
+		         POSITION_TOLERANCE * 1000.0, ROTATION_TOLERANCE, PIXEL_TOLERANCE, COEFFICIENT_TOLERANCE)?;
+		
+		if self.camera_counts.0 != self.camera_counts.1 {
+			writeln!(f, "Camera count: {} -> {}  [differs]", self.camera_counts.0, self.camera_counts.1)?;
+		}
+		
+		for camera in &self.cameras {
+			writeln!(f)?;
+			if camera.names.0 == camera.names.1 {
+				writeln!(f, "Camera {} ({}):", camera.index, camera.names.0)?;
+			} else {
+				writeln!(f, "Camera {} ({} -> {}):", camera.index, camera.names.0, camera.names.1)?;
+			}
+			
+			writeln!(f, "  Extrinsics: {}", camera.extrinsics)?;
+			if let Some(head) = &camera.head {
+				writeln!(f, "  Camera to head: {}", head)?;
+			}
+			
+			let (old, new) = camera.resolutions;
+			if old != new {
+				writeln!(f, "  Resolution: {}x{} -> {}x{}, shifts are at {}x{}", old.0, old.1, new.0, new.1, old.0, old.1)?;
+			}
+			
+			writeln!(f, "  Focal: {:+.3} {:+.3} px, principal point: {:+.3} {:+.3} px{}",
+			         camera.focal[0], camera.focal[1], camera.center[0], camera.center[1], marker(camera.intrinsics_differ()))?;
+			
+			if camera.distortion.0 != camera.distortion.1 {
+				writeln!(f, "  Distortion: {} -> {}", camera.distortion.0, camera.distortion.1)?;
+			}
+			
+			match &camera.coeffs {
+				Some(coeffs) => writeln!(f, "  Distortion coefficients: {}",
+				                         coeffs.iter().map(|d| format!("{:+.6}", d)).collect::<Vec<_>>().join(" "))?,
+				None => writeln!(f, "  Distortion coefficients: different count")?,
+			}
+		}
+		
+		if let Some(((old, new), pose)) = &self.stereo {
+			writeln!(f)?;
+			writeln!(f, "Stereo: baseline {:.3} -> {:.3} mm ({:+.3}), camera 1 to camera 0 {}", old * 1000.0, new * 1000.0, (new - old) * 1000.0, pose)?;
+		}
+		
+		if let Some(head) = &self.head {
+			writeln!(f, "Head: {}", head)?;
+		}
+		
+		if !self.fields.is_empty() {
+			writeln!(f)?;
+			writeln!(f, "Other fields:")?;
+			
+			for change in &self.fields {
+				match change {
+					FieldChange::Changed(path, Value::Number(a), Value::Number(b)) => {
+						let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+						writeln!(f, "  ~ {}: {} -> {} ({:+})", path, a, b, b - a)?;
+					},
+					change => writeln!(f, "  {}", change)?,
+				}
+			}
+		}
+		
+		writeln!(f)?;
+		match self.differences() {
+			0 => writeln!(f, "Verdict: identical within tolerance"),
+			count => writeln!(f, "Verdict: {} differences beyond tolerance", count),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nalgebra::{Translation3, UnitQuaternion};
+	use serde_json::json;
+	use crate::calibration::ExtrinsicsConfig;
+	
+	/// Two cameras 10 cm apart looking forward, at 640x480.
+	fn config() -> Value {
+		let camera = |name: &str, x: f64| json!({
+			"name": name,
+			"extrinsics": { "plus_x": [1.0, 0.0, 0.0], "plus_z": [0.0, 0.0, 1.0], "position": [x, 0.0, 0.0] },
+			"intrinsics": {
+				"center_x": 320.0, "center_y": 240.0, "focal_x": 300.0, "focal_y": 300.0, "width": 640, "height": 480,
+				"distort": { "coeffs": [0.1, 0.01, 0.0, 0.0], "type": "DISTORT_FTHETA" },
+			},
+		});
+		
+		json!({ "device_serial_number": "LHR-00000000", "tracked_cameras": [camera("left", -0.05), camera("right", 0.05)] })
+	}
+	
+	fn compare(old: &Value, new: &Value) -> ConfigDiff {
+		ConfigDiff::new(&old.to_string(), &new.to_string()).unwrap()
+	}
+	
+	#[test]
+	fn reports_an_extrinsic_rotation() {
+		let old = config();
+		let mut new = config();
+		let turned = Isometry3::from_parts(Translation3::new(-0.05, 0.0, 0.0), UnitQuaternion::from_euler_angles(0.0, 0.0, 0.5f64.to_radians()));
+		new["tracked_cameras"][0]["extrinsics"] = serde_json::to_value(ExtrinsicsConfig::from_isometry(&turned)).unwrap();
+		
+		let diff = compare(&old, &new);
+		assert!((diff.cameras[0].extrinsics.angle - 0.5).abs() < 1e-6);
+		assert!((diff.cameras[0].extrinsics.rotation.z - 0.5).abs() < 1e-6);
+		assert!(diff.cameras[0].differs() && !diff.cameras[1].differs());
+		assert!(diff.stereo.unwrap().1.differs());
+		assert!(diff.fields.is_empty(), "{:?}", diff.fields);
+	}
+	
+	#[test]
+	fn compares_focal_lengths_at_the_old_resolution() {
+		let old = config();
+		let mut new = config();
+		new["tracked_cameras"][0]["intrinsics"] = json!({
+			"center_x": 640.0, "center_y": 480.0, "focal_x": 604.0, "focal_y": 604.0, "width": 1280, "height": 960,
+			"distort": { "coeffs": [0.1, 0.01, 0.0, 0.0], "type": "DISTORT_FTHETA" },
+		});
+		
+		let camera = &compare(&old, &new).cameras[0];
+		assert_eq!(camera.resolutions, ((640, 480), (1280, 960)));
+		assert!((camera.focal[0] - 2.0).abs() < 1e-9 && (camera.focal[1] - 2.0).abs() < 1e-9);
+		assert!(camera.center.iter().all(|d| d.abs() < 1e-9));
+		assert!(camera.intrinsics_differ());
+		assert!(compare(&old, &new).fields.is_empty());
+	}
+	
+	#[test]
+	fn keeps_unknown_fields_next_to_the_camera_ones() {
+		let old = config();
+		let mut new = config();
+		new["tracked_cameras"][0]["intrinsics"]["skew"] = json!(0.5);
+		new["tracked_cameras"][1]["intrinsics_foo"] = json!(1);
+		new["tracked_cameras"][1]["extrinsics"]["position_offset"] = json!([0.0, 0.0, 0.0]);
+		
+		let diff = compare(&old, &new);
+		let paths = diff.fields.iter().map(|change| change.path()).collect::<Vec<_>>();
+		assert_eq!(paths, [
+			"tracked_cameras[0].intrinsics.skew",
+			"tracked_cameras[1].extrinsics.position_offset",
+			"tracked_cameras[1].intrinsics_foo",
+		]);
+		assert_eq!(diff.differences(), 3);
+	}
+	
+	#[test]
+	fn ignores_rounding() {
+		let old = config();
+		let mut new = config();
+		new["tracked_cameras"][0]["intrinsics"]["focal_x"] = json!(300.0 + 1e-10);
+		new["tracked_cameras"][0]["extrinsics"]["position"][0] = json!(-0.05 + 1e-12);
+		new["exposure"] = json!(1.0);
+		let mut rounded = old.clone();
+		rounded["exposure"] = json!(1.0 + 1e-12);
+		
+		let diff = compare(&rounded, &new);
+		assert!(diff.fields.is_empty(), "{:?}", diff.fields);
+		assert_eq!(diff.differences(), 0);
+		
+		new["exposure"] = json!(1.5);
+		assert_eq!(compare(&rounded, &new).fields.len(), 1);
+	}
+}
@@ -0,0 +1,141 @@
+use nalgebra::{Isometry3, Matrix3, Rotation3, Translation3, UnitQuaternion, Vector3};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use super::ConfigError;
+use crate::system::DistortionFunction;
+
+/// The parts of `config/lighthouse/<serial>/config.json` we care about.
+#[derive(Debug, Clone)]
+pub struct LighthouseConfig {
+	/// `device_serial_number`, if the config has one.
+	pub serial: Option<String>,
+	pub tracked_cameras: Vec<TrackedCameraConfig>,
+	/// Head pose in the same space as the camera extrinsics, if the config has one.
+	pub head: Option<ExtrinsicsConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedCameraConfig {
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub name: String,
+	pub extrinsics: ExtrinsicsConfig,
+	pub intrinsics: IntrinsicsConfig,
+}
+
+/// Camera pose in head space, given as the camera +X and +Z axes and its position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtrinsicsConfig {
+	pub plus_x: [f64; 3],
+	pub plus_z: [f64; 3],
+	pub position: [f64; 3],
+}
+
+impl ExtrinsicsConfig {
+	/// The pose as an isometry, keeping the OpenGL style axes of the config (camera looks down -Z).
+	pub fn isometry(&self) -> Isometry3<f64> {
+		let plus_x = Vector3::from(self.plus_x).normalize();
+		let plus_z = Vector3::from(self.plus_z);
+		let plus_z = (plus_z - plus_x * plus_x.dot(&plus_z)).normalize();
+		let plus_y = plus_z.cross(&plus_x);
+		let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[plus_x, plus_y, plus_z]));
+		
+		Isometry3::from_parts(Translation3::from(Vector3::from(self.position)),
+		                      UnitQuaternion::from_rotation_matrix(&rotation))
+	}
+	
+	pub fn from_isometry(isometry: &Isometry3<f64>) -> ExtrinsicsConfig {
+		let rotation = isometry.rotation.to_rotation_matrix();
+		
+		ExtrinsicsConfig {
+			plus_x: (rotation * Vector3::x()).into(),
+			plus_z: (rotation * Vector3::z()).into(),
+			position: isometry.translation.vector.into(),
+		}
+	}
+}
+
+/// Rotates the computer vision camera frame (+Y down, +Z forward) into the OpenGL one (+Y up, -Z forward).
+pub fn gl_from_cv() -> Isometry3<f64> {
+	Isometry3::from_parts(Translation3::identity(),
+	                      UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f64::consts::PI))
+}
+
+/// Converts an OpenVR `HmdMatrix34_t`, orthonormalizing the rotation part.
+pub fn isometry_from_matrix34(m: &[[f32; 4]; 3]) -> Isometry3<f64> {
+	let rotation = Matrix3::from_fn(|row, column| m[row][column] as f64);
+	
+	Isometry3::from_parts(Translation3::new(m[0][3] as f64, m[1][3] as f64, m[2][3] as f64),
+	                      UnitQuaternion::from_matrix(&rotation))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntrinsicsConfig {
+	pub center_x: f64,
+	pub center_y: f64,
+	pub focal_x: f64,
+	pub focal_y: f64,
+	pub width: u32,
+	pub height: u32,
+	pub distort: DistortConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistortConfig {
+	pub coeffs: Vec<f64>,
+	#[serde(rename = "type")]
+	pub ty: String,
+}
+
+impl DistortConfig {
+	pub fn function(&self) -> DistortionFunction {
+		match self.ty.as_str() {
+			"DISTORT_FTHETA" => DistortionFunction::FTheta,
+			"DISTORT_FTHETA_EXTENDED" | "DISTORT_EXTENDED_FTHETA" => DistortionFunction::ExtendedFTheta,
+			_ => DistortionFunction::None,
+		}
+	}
+}
+
+impl LighthouseConfig {
+	pub fn parse(text: &str) -> Result<LighthouseConfig, ConfigError> {
+		let raw: Value = serde_json::from_str(text)?;
+		let tracked_cameras: Vec<TrackedCameraConfig> = match raw.get("tracked_cameras") {
+			Some(cameras) => serde_json::from_value(cameras.clone())?,
+			None => return Err(ConfigError::NoCameras),
+		};
+		
+		if tracked_cameras.is_empty() {
+			return Err(ConfigError::NoCameras);
+		}
+		
+		let head = match raw.get("head") {
+			Some(head) => serde_json::from_value(head.clone()).ok(),
+			None => None,
+		};
+		
+		let serial = raw.get("device_serial_number").and_then(Value::as_str).map(String::from);
+		
+		Ok(LighthouseConfig { serial, tracked_cameras, head })
+	}
+	
+	/// Transform from camera space (computer vision convention) to head space.
+	pub fn head_from_camera(&self, index: usize) -> Option<Isometry3<f64>> {
+		let camera = self.tracked_cameras.get(index)?.extrinsics.isometry() * gl_from_cv();
+		
+		Some(match &self.head {
+			Some(head) => head.isometry().inverse() * camera,
+			None => camera,
+		})
+	}
+	
+	/// Inverse of `head_from_camera`, the config extrinsics for a camera pose in head space.
+	pub fn camera_extrinsics(&self, head_from_camera: &Isometry3<f64>) -> ExtrinsicsConfig {
+		let camera = head_from_camera * gl_from_cv().inverse();
+		
+		ExtrinsicsConfig::from_isometry(&match &self.head {
+			Some(head) => head.isometry() * camera,
+			None => camera,
+		})
+	}
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use image::RgbaImage;
+use nalgebra::Isometry3;
+
+use super::{LighthouseConfig, DumpError, Manifest, FrameHeader};
+use crate::tracked_camera::{FrameLayout, FrameSize};
+use crate::vision::split_frame;
+
+/// A dump folder with the dump, or why it couldn't be loaded.
+pub type LoadedDump = (PathBuf, Result<Dump, DumpError>);
+
+/// A folder written by a previous run, `dumps/<timestamp>`.
+pub struct Dump {
+	pub path: PathBuf,
+	pub config: LighthouseConfig,
+	pub camera_image: RgbaImage,
+	/// Standing pose of the headset when the frame was captured, `frame.json` or `frame.txt` header, if it was valid.
+	/// Poses captured in another space are converted with the transforms in the manifest.
+	pub pose: Option<Isometry3<f64>>,
+	pub manifest: Option<Manifest>,
+}
+
+impl Dump {
+	pub fn load(path: &Path) -> Result<Dump, DumpError> {
+		let config_path = path.join("config.json");
+		let config = fs::read_to_string(&config_path).map_err(|err| DumpError::Io(config_path, err))?;
+		let config = LighthouseConfig::parse(&config)?;
+		
+		let image_path = path.join("camera.png");
+		let camera_image = image::open(&image_path).map_err(|err| DumpError::Image(image_path, err))?.to_rgba8();
+		
+		let manifest = Manifest::load(path)?;
+		let header = match FrameHeader::load(&path.join("frame.json"))? {
+			Some(header) => Some(header),
+			None => FrameHeader::load_legacy(&path.join("frame.txt"))?,
+		};
+		let pose = header.and_then(|header| header.pose());
+		let pose = pose.map(|pose| manifest.as_ref().map_or(pose, |manifest| manifest.standing_from_tracking() * pose));
+		
+		Ok(Dump { path: path.to_path_buf(), config, camera_image, pose, manifest })
+	}
+	
+	/// Loads every dump in `dumps`, oldest first.
+	pub fn load_all(dumps: &Path) -> Result<Vec<LoadedDump>, DumpError> {
+		let mut paths = fs::read_dir(dumps).map_err(|err| DumpError::Io(dumps.to_path_buf(), err))?
+		                                   .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		                                   .filter(|path| path.is_dir())
+		                                   .collect::<Vec<_>>();
+		paths.sort();
+		
+		Ok(paths.into_iter().map(|path| {
+			let dump = Dump::load(&path);
+			(path, dump)
+		}).collect())
+	}
+	
+	pub fn serial(&self) -> &str {
+		self.config.serial.as_deref().unwrap_or("unknown")
+	}
+	
+	/// Frame layout, guessed from the frame shape since older dumps don't have the property.
+	pub fn layout(&self) -> FrameLayout {
+		FrameLayout::new(None, &FrameSize { width: self.camera_image.width(), height: self.camera_image.height(), frame_buffer_size: 0 })
+	}
+	
+	pub fn camera_images(&self) -> Vec<RgbaImage> {
+		split_frame(&self.camera_image, self.layout())
+	}
+}
@@ -0,0 +1,80 @@
+use std::{error, fmt, io};
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum ConfigError {
+	Io(io::Error),
+	Json(serde_json::Error),
+	NoCameras,
+	Invalid(String),
+}
+
+impl error::Error for ConfigError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			ConfigError::Io(err) => Some(err),
+			ConfigError::Json(err) => Some(err),
+			ConfigError::NoCameras | ConfigError::Invalid(_) => None,
+		}
+	}
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ConfigError::Io(err) => write!(f, "Unable to read config: {}", err),
+			ConfigError::Json(err) => write!(f, "Unable to parse config: {}", err),
+			ConfigError::NoCameras => f.pad("Config has no tracked_cameras"),
+			ConfigError::Invalid(reason) => write!(f, "Invalid config: {}", reason),
+		}
+	}
+}
+
+impl From<io::Error> for ConfigError {
+	fn from(err: io::Error) -> Self { ConfigError::Io(err) }
+}
+
+impl From<serde_json::Error> for ConfigError {
+	fn from(err: serde_json::Error) -> Self { ConfigError::Json(err) }
+}
+
+#[derive(Debug)]
+pub enum DumpError {
+	Io(PathBuf, io::Error),
+	Config(ConfigError),
+	Image(PathBuf, image::ImageError),
+	NotStereo,
+	NoPose,
+	NoDumps,
+	/// `manifest.json` or a frame header that couldn't be parsed, and why.
+	Invalid(PathBuf, String),
+}
+
+impl error::Error for DumpError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			DumpError::Io(_, err) => Some(err),
+			DumpError::Config(err) => Some(err),
+			DumpError::Image(_, err) => Some(err),
+			DumpError::NotStereo | DumpError::NoPose | DumpError::NoDumps | DumpError::Invalid(..) => None,
+		}
+	}
+}
+
+impl fmt::Display for DumpError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DumpError::Io(path, err) => write!(f, "Unable to read {}: {}", path.display(), err),
+			DumpError::Config(err) => err.fmt(f),
+			DumpError::Image(path, err) => write!(f, "Unable to load {}: {}", path.display(), err),
+			DumpError::NotStereo => f.pad("Dump needs two cameras with extrinsics in the config"),
+			DumpError::NoPose => f.pad("Dump has no valid headset pose in frame.json"),
+			DumpError::NoDumps => f.pad("No usable dumps"),
+			DumpError::Invalid(path, reason) => write!(f, "Invalid {}: {}", path.display(), reason),
+		}
+	}
+}
+
+impl From<ConfigError> for DumpError {
+	fn from(err: ConfigError) -> Self { DumpError::Config(err) }
+}
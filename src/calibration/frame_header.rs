@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::Path;
+use nalgebra::Isometry3;
+use serde::{Serialize, Deserialize};
+
+use super::{DumpError, isometry_from_matrix34};
+use crate::tracked_camera::CameraVideoStreamFrameHeader;
+
+/// Camera frame header as saved next to the frame, `frame.json` in dumps and `<number>.json` in recordings. Dumps made
+/// before those have the `{:#?}` of the header in `frame.txt` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameHeader {
+	pub width: u32,
+	pub height: u32,
+	pub bytes_per_pixel: u32,
+	pub frame_sequence: u32,
+	/// Headset pose when the frame was captured, in the space listed in `manifest.json`.
+	pub device_to_absolute_tracking: [[f32; 4]; 3],
+	pub velocity: [f32; 3],
+	pub angular_velocity: [f32; 3],
+	/// `ETrackingResult`.
+	pub tracking_result: u32,
+	pub pose_is_valid: bool,
+	pub device_is_connected: bool,
+	pub frame_exposure_time: u64,
+}
+
+impl From<&CameraVideoStreamFrameHeader> for FrameHeader {
+	fn from(header: &CameraVideoStreamFrameHeader) -> Self {
+		let pose = &header.standingTrackedDevicePose;
+		
+		FrameHeader {
+			width: header.nWidth,
+			height: header.nHeight,
+			bytes_per_pixel: header.nBytesPerPixel,
+			frame_sequence: header.nFrameSequence,
+			device_to_absolute_tracking: pose.mDeviceToAbsoluteTracking.m,
+			velocity: pose.vVelocity.v,
+			angular_velocity: pose.vAngularVelocity.v,
+			tracking_result: pose.eTrackingResult,
+			pose_is_valid: pose.bPoseIsValid,
+			device_is_connected: pose.bDeviceIsConnected,
+			frame_exposure_time: header.ulFrameExposureTime,
+		}
+	}
+}
+
+impl FrameHeader {
+	/// Reads a header saved at `path`, `None` if there is none.
+	pub fn load(path: &Path) -> Result<Option<FrameHeader>, DumpError> {
+		if !path.is_file() {
+			return Ok(None);
+		}
+		
+		let header = fs::read_to_string(path).map_err(|err| DumpError::Io(path.to_path_buf(), err))?;
+		let header = serde_json::from_str(&header).map_err(|err| DumpError::Invalid(path.to_path_buf(), err.to_string()))?;
+		
+		Ok(Some(header))
+	}
+	
+	/// Reads a `frame.txt` header saved at `path`, `None` if there is none.
+	pub fn load_legacy(path: &Path) -> Result<Option<FrameHeader>, DumpError> {
+		if !path.is_file() {
+			return Ok(None);
+		}
+		
+		let header = fs::read_to_string(path).map_err(|err| DumpError::Io(path.to_path_buf(), err))?;
+		let header = FrameHeader::parse_debug(&header).ok_or_else(|| DumpError::Invalid(path.to_path_buf(), "not a frame header".to_string()))?;
+		
+		Ok(Some(header))
+	}
+	
+	/// Reads the header back from the `{:#?}` of `CameraVideoStreamFrameHeader_t`.
+	pub fn parse_debug(text: &str) -> Option<FrameHeader> {
+		let value = |field: &str| {
+			let start = text.find(&format!("{}:", field))? + field.len() + 1;
+			text[start..].split([',', '\n']).next().map(str::trim)
+		};
+		let numbers = |field: &str, count: usize| -> Option<Vec<f32>> {
+			let start = text.find(&format!("{}:", field))?;
+			let array = &text[start + text[start..].find('[')?..];
+			let values = array.split(|c: char| c == '[' || c == ']' || c == ',' || c.is_whitespace())
+			                  .filter(|token| !token.is_empty())
+			                  .take(count)
+			                  .map(|token| token.parse().ok())
+			                  .collect::<Option<Vec<_>>>()?;
+			
+			if values.len() == count { Some(values) } else { None }
+		};
+		
+		let matrix = numbers("mDeviceToAbsoluteTracking", 12)?;
+		let mut device_to_absolute_tracking = [[0.0; 4]; 3];
+		for (i, value) in matrix.into_iter().enumerate() {
+			device_to_absolute_tracking[i / 4][i % 4] = value;
+		}
+		let vector = |field| numbers(field, 3).map(|v| [v[0], v[1], v[2]]);
+		
+		Some(FrameHeader {
+			width: value("nWidth")?.parse().ok()?,
+			height: value("nHeight")?.parse().ok()?,
+			bytes_per_pixel: value("nBytesPerPixel")?.parse().ok()?,
+			frame_sequence: value("nFrameSequence")?.parse().ok()?,
+			device_to_absolute_tracking,
+			velocity: vector("vVelocity")?,
+			angular_velocity: vector("vAngularVelocity")?,
+			tracking_result: value("eTrackingResult")?.parse().ok()?,
+			pose_is_valid: value("bPoseIsValid")?.parse().ok()?,
+			device_is_connected: value("bDeviceIsConnected")?.parse().ok()?,
+			// Older SDKs had no exposure time
+			frame_exposure_time: value("ulFrameExposureTime").and_then(|time| time.parse().ok()).unwrap_or(0),
+		})
+	}
+	
+	/// The headset pose, if it was valid.
+	pub fn pose(&self) -> Option<Isometry3<f64>> {
+		if self.pose_is_valid {
+			Some(isometry_from_matrix34(&self.device_to_absolute_tracking))
+		} else {
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nalgebra::{Translation3, UnitQuaternion, Vector3};
+	use openvr_sys as sys;
+	
+	fn header(valid: bool) -> CameraVideoStreamFrameHeader {
+		CameraVideoStreamFrameHeader {
+			eFrameType: sys::EVRTrackedCameraFrameType_VRTrackedCameraFrameType_Distorted,
+			nWidth: 1920,
+			nHeight: 960,
+			nBytesPerPixel: 4,
+			nFrameSequence: 42,
+			standingTrackedDevicePose: sys::TrackedDevicePose_t {
+				mDeviceToAbsoluteTracking: sys::HmdMatrix34_t { m: [[0.0, -1.0, 0.0, 0.5], [1.0, 0.0, 0.0, 1.6], [0.0, 0.0, 1.0, -0.25]] },
+				vVelocity: sys::HmdVector3_t { v: [0.1, 0.0, 0.0] },
+				vAngularVelocity: sys::HmdVector3_t { v: [0.0, 0.2, 0.0] },
+				eTrackingResult: sys::ETrackingResult_TrackingResult_Running_OK,
+				bPoseIsValid: valid,
+				bDeviceIsConnected: true,
+			},
+			ulFrameExposureTime: 123456789,
+		}
+	}
+	
+	#[test]
+	fn pose_survives_json() {
+		let json = serde_json::to_string_pretty(&FrameHeader::from(&header(true))).unwrap();
+		let header = serde_json::from_str::<FrameHeader>(&json).unwrap();
+		let expected = Isometry3::from_parts(Translation3::new(0.5, 1.6, -0.25),
+		                                     UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2));
+		
+		assert_eq!((header.width, header.height, header.frame_sequence, header.frame_exposure_time), (1920, 960, 42, 123456789));
+		assert!((header.pose().unwrap().to_homogeneous() - expected.to_homogeneous()).abs().max() < 1e-6);
+	}
+	
+	#[test]
+	fn invalid_poses_are_dropped() {
+		assert!(FrameHeader::from(&header(false)).pose().is_none());
+	}
+	
+	#[test]
+	fn reads_the_frame_txt_of_old_dumps() {
+		// What dumps wrote before `frame.json`
+		let text = format!("{:#?}", header(true));
+		let parsed = FrameHeader::parse_debug(&text).unwrap();
+		let expected = FrameHeader::from(&header(true));
+		
+		assert_eq!((parsed.width, parsed.height, parsed.bytes_per_pixel, parsed.frame_sequence), (1920, 960, 4, 42));
+		assert_eq!(parsed.device_to_absolute_tracking, expected.device_to_absolute_tracking);
+		assert_eq!((parsed.velocity, parsed.angular_velocity), (expected.velocity, expected.angular_velocity));
+		assert_eq!((parsed.tracking_result, parsed.device_is_connected, parsed.frame_exposure_time), (expected.tracking_result, true, 123456789));
+		assert!(parsed.pose().is_some());
+		
+		assert!(FrameHeader::parse_debug(&format!("{:#?}", header(false))).unwrap().pose().is_none());
+		assert!(FrameHeader::parse_debug("CameraVideoStreamFrameHeader_t { nWidth: 1920 }").is_none());
+	}
+}
@@ -0,0 +1,374 @@
+use std::fmt::{self, Display, Formatter};
+use nalgebra::{DMatrix, DVector, Isometry3, Matrix3, Point3, Rotation3, Translation3, UnitQuaternion, Vector2, Vector3};
+
+use super::{Recording, BoardDetections, FisheyeCamera, PoseDiff, apply_pose, solve_pnp, reprojection_errors};
+use crate::vision::Board;
+
+/// Pairs of views rotating less than this, in degrees, say little about the rotation and are skipped.
+pub const MIN_PAIR_ROTATION: f64 = 2.0;
+pub const MIN_HAND_EYE_VIEWS: usize = 3;
+pub const HAND_EYE_ITERATIONS: usize = 50;
+/// Differences to the headset's own camera to head transforms worth calling a misalignment.
+pub const MISALIGNMENT_POSITION: f64 = 0.003;
+pub const MISALIGNMENT_ROTATION: f64 = 0.5;
+
+/// A frame where the static board was seen, with the tracked headset pose from its header.
+#[derive(Debug, Clone)]
+pub struct HandEyeView {
+	pub frame: usize,
+	pub world_from_head: Isometry3<f64>,
+	pub camera_from_board: Isometry3<f64>,
+	pub object: Vec<Point3<f64>>,
+	pub pixels: Vec<Vector2<f64>>,
+}
+
+/// Camera to head transform solved from head motion and camera motion, `A X = X B`.
+#[derive(Debug, Clone)]
+pub struct HandEye {
+	pub camera: usize,
+	/// Closed form estimate from the relative motions.
+	pub linear: Isometry3<f64>,
+	/// Estimate refined on the reprojection error, with the board pose in tracking space.
+	pub head_from_camera: Isometry3<f64>,
+	pub world_from_board: Isometry3<f64>,
+	pub views: usize,
+	pub pairs: usize,
+	/// Median rotation left between `A X` and `X B` over all pairs, degrees.
+	pub rotation_residual: f64,
+	/// Reprojection RMS per view, frame and pixels.
+	pub residuals: Vec<(usize, f64)>,
+	pub rms: f64,
+}
+
+impl HandEye {
+	pub fn solve(camera_model: &FisheyeCamera, camera: usize, views: &[HandEyeView]) -> Option<HandEye> {
+		if views.len() < MIN_HAND_EYE_VIEWS {
+			return None;
+		}
+		
+		// Relative motions, A = A_j⁻¹ A_i of the head and B = B_j B_i⁻¹ of the camera
+		let mut motions = vec![];
+		for (i, first) in views.iter().enumerate() {
+			for second in &views[i + 1..] {
+				let a = second.world_from_head.inverse() * first.world_from_head;
+				let b = second.camera_from_board * first.camera_from_board.inverse();
+				
+				if a.rotation.angle().to_degrees() >= MIN_PAIR_ROTATION && b.rotation.angle().to_degrees() >= MIN_PAIR_ROTATION {
+					motions.push((a, b));
+				}
+			}
+		}
+		
+		if motions.len() < 2 {
+			return None;
+		}
+		
+		// Rotation axes of the motions agree once rotated by X, as in Park and Martin
+		let correlation = motions.iter().map(|(a, b)| b.rotation.scaled_axis() * a.rotation.scaled_axis().transpose()).sum::<Matrix3<f64>>();
+		let svd = correlation.svd(true, true);
+		let (u, v_t) = (svd.u?, svd.v_t?);
+		let mut correction = Matrix3::identity();
+		correction[(2, 2)] = (v_t.transpose() * u.transpose()).determinant().signum();
+		let rotation = Rotation3::from_matrix_unchecked(v_t.transpose() * correction * u.transpose());
+		
+		// (R_A - I) t_X = R_X t_B - t_A, in the least squares sense
+		let mut normal = Matrix3::zeros();
+		let mut target = Vector3::zeros();
+		for (a, b) in &motions {
+			let lhs = a.rotation.to_rotation_matrix().matrix() - Matrix3::identity();
+			let rhs = rotation * b.translation.vector - a.translation.vector;
+			normal += lhs.transpose() * lhs;
+			target += lhs.transpose() * rhs;
+		}
+		let translation = normal.try_inverse()? * target;
+		let linear = Isometry3::from_parts(Translation3::from(translation), UnitQuaternion::from_rotation_matrix(&rotation));
+		
+		let rotation_residual = {
+			let mut angles = motions.iter().map(|(a, b)| ((a * linear).rotation.inverse() * (linear * b).rotation).angle().to_degrees()).collect::<Vec<_>>();
+			angles.sort_by(|a, b| a.total_cmp(b));
+			angles[angles.len() / 2]
+		};
+		
+		let world_from_board = views[0].world_from_head * linear * views[0].camera_from_board;
+		let (head_from_camera, world_from_board) = refine(camera_model, views, linear, world_from_board);
+		
+		let residuals = views.iter().map(|view| {
+			let camera_from_board = (view.world_from_head * head_from_camera).inverse() * world_from_board;
+			(view.frame, rms(&reprojection_errors(camera_model, &camera_from_board, &view.object, &view.pixels)))
+		}).collect::<Vec<_>>();
+		let rms = (residuals.iter().map(|(_, rms)| rms * rms).sum::<f64>() / residuals.len() as f64).sqrt();
+		
+		Some(HandEye {
+			camera,
+			linear,
+			head_from_camera,
+			world_from_board,
+			views: views.len(),
+			pairs: motions.len(),
+			rotation_residual,
+			residuals,
+			rms,
+		})
+	}
+}
+
+/// Levenberg-Marquardt on the reprojection error of every view, over the camera to head and board to world poses.
+fn refine(camera: &FisheyeCamera, views: &[HandEyeView], head_from_camera: Isometry3<f64>, world_from_board: Isometry3<f64>) -> (Isometry3<f64>, Isometry3<f64>) {
+	let apply = |poses: &(Isometry3<f64>, Isometry3<f64>), delta: &[f64]| (apply_pose(&poses.0, &delta[..6]), apply_pose(&poses.1, &delta[6..]));
+	let residuals = |poses: &(Isometry3<f64>, Isometry3<f64>)| -> Vec<Option<Vector2<f64>>> {
+		views.iter().flat_map(|view| {
+			reprojection_errors(camera, &((view.world_from_head * poses.0).inverse() * poses.1), &view.object, &view.pixels)
+		}).collect()
+	};
+	let cost = |residuals: &[Option<Vector2<f64>>]| -> Option<f64> { residuals.iter().map(|r| r.map(|r| r.norm_squared())).sum() };
+	
+	let mut poses = (head_from_camera, world_from_board);
+	let mut current = match cost(&residuals(&poses)) {
+		Some(cost) => cost,
+		None => return poses,
+	};
+	let mut lambda = 1e-3;
+	
+	for _ in 0..HAND_EYE_ITERATIONS {
+		let r = residuals(&poses);
+		let mut jacobian = DMatrix::zeros(2 * r.len(), 12);
+		
+		for parameter in 0..12 {
+			let mut delta = [0.0; 12];
+			delta[parameter] = 1e-6;
+			let plus = residuals(&apply(&poses, &delta));
+			delta[parameter] = -1e-6;
+			let minus = residuals(&apply(&poses, &delta));
+			
+			for (index, (plus, minus)) in plus.iter().zip(&minus).enumerate() {
+				if let (Some(plus), Some(minus)) = (plus, minus) {
+					let derivative = (plus - minus) / 2e-6;
+					jacobian[(2 * index, parameter)] = derivative.x;
+					jacobian[(2 * index + 1, parameter)] = derivative.y;
+				}
+			}
+		}
+		
+		let r = DVector::from_iterator(2 * r.len(), r.iter().flat_map(|r| r.map_or([0.0, 0.0], |r| [r.x, r.y]).to_vec()));
+		let normal = jacobian.transpose() * &jacobian;
+		let gradient = jacobian.transpose() * r;
+		let mut improved = false;
+		
+		while lambda < 1e10 {
+			let damped = &normal + DMatrix::from_diagonal(&normal.diagonal()) * lambda;
+			let step = match damped.cholesky() {
+				Some(cholesky) => -cholesky.solve(&gradient),
+				None => { lambda *= 10.0; continue },
+			};
+			
+			let candidate = apply(&poses, step.as_slice());
+			match cost(&residuals(&candidate)) {
+				Some(cost) if cost < current => {
+					improved = (current - cost) / current.max(1e-12) > 1e-10;
+					poses = candidate;
+					current = cost;
+					lambda = (lambda / 10.0).max(1e-9);
+					break;
+				},
+				_ => lambda *= 10.0,
+			}
+		}
+		
+		if !improved {
+			break;
+		}
+	}
+	
+	poses
+}
+
+fn rms(errors: &[Option<Vector2<f64>>]) -> f64 {
+	let errors = errors.iter().flatten().collect::<Vec<_>>();
+	
+	(errors.iter().map(|r| r.norm_squared()).sum::<f64>() / errors.len().max(1) as f64).sqrt()
+}
+
+/// Hand-eye calibration of both cameras of a recording, compared with the transforms the headset reports.
+pub struct HandEyeReport {
+	pub serial: String,
+	pub cameras: Vec<Result<HandEye, String>>,
+	/// `head_from_camera` of the lighthouse config.
+	pub lighthouse: Vec<Option<Isometry3<f64>>>,
+	/// `Prop_CameraToHeadTransforms_Matrix34_Array`, if the recording has it.
+	pub property: Option<Vec<Isometry3<f64>>>,
+}
+
+impl HandEyeReport {
+	pub fn new(recording: &Recording, detections: &BoardDetections, board: &Board) -> HandEyeReport {
+		let cameras = (0..2).map(|camera| {
+			let model = &detections.rig.cameras[camera];
+			let views = recording.frames.iter().zip(&detections.detections).enumerate().filter_map(|(frame, (recorded, detection))| {
+				let world_from_head = recorded.pose?;
+				let detection = detection[camera].as_ref()?;
+				let object = detection.ids.iter().map(|&id| Point3::from(board.object_point(id))).collect::<Vec<_>>();
+				let pixels = detection.pixels.iter().map(|&pixel| Vector2::from(pixel)).collect::<Vec<_>>();
+				let camera_from_board = solve_pnp(model, &object, &pixels)?;
+				
+				Some(HandEyeView { frame, world_from_head, camera_from_board, object, pixels })
+			}).collect::<Vec<_>>();
+			
+			HandEye::solve(model, camera, &views).ok_or_else(|| {
+				format!("{} views with a tracked pose and the board, need at least {} with {} deg of rotation between them",
+				        views.len(), MIN_HAND_EYE_VIEWS, MIN_PAIR_ROTATION)
+			})
+		}).collect();
+		
+		HandEyeReport {
+			serial: recording.serial().to_string(),
+			cameras,
+			lighthouse: (0..2).map(|camera| detections.rig.head_from_camera.get(camera).copied()).collect(),
+			property: recording.cameras_to_head.clone(),
+		}
+	}
+	
+	/// Whether a camera disagrees with the transforms the headset reports.
+	pub fn misaligned(&self) -> bool {
+		self.cameras.iter().flatten().any(|hand_eye| {
+			self.references(hand_eye.camera).iter().any(|(_, reference)| {
+				let diff = PoseDiff::new(reference, &hand_eye.head_from_camera);
+				diff.translation.norm() > MISALIGNMENT_POSITION || diff.angle > MISALIGNMENT_ROTATION
+			})
+		})
+	}
+	
+	fn references(&self, camera: usize) -> Vec<(&'static str, Isometry3<f64>)> {
+		let mut references = vec![];
+		
+		if let Some(Some(lighthouse)) = self.lighthouse.get(camera) {
+			references.push(("lighthouse extrinsics", *lighthouse));
+		}
+		if let Some(property) = self.property.as_ref().and_then(|property| property.get(camera)) {
+			references.push(("Prop_CameraToHeadTransforms_Matrix34_Array", *property));
+		}
+		
+		references
+	}
+}
+
+impl Display for HandEyeReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Headset {}:", self.serial)?;
+		
+		for (camera, hand_eye) in self.cameras.iter().enumerate() {
+			writeln!(f)?;
+			
+			let hand_eye = match hand_eye {
+				Ok(hand_eye) => hand_eye,
+				Err(err) => { writeln!(f, "Camera {}: {}", camera, err)?; continue },
+			};
+			
+			let t = hand_eye.head_from_camera.translation.vector * 1000.0;
+			let (roll, pitch, yaw) = hand_eye.head_from_camera.rotation.euler_angles();
+			
+			writeln!(f, "Camera {}: {} views, {} motion pairs", camera, hand_eye.views, hand_eye.pairs)?;
+			writeln!(f, "  Head from camera: position {:+.3} {:+.3} {:+.3} mm, roll {:.4} deg, pitch {:.4} deg, yaw {:.4} deg",
+			         t.x, t.y, t.z, roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees())?;
+			writeln!(f, "  Closed form to refined: {}", PoseDiff::new(&hand_eye.linear, &hand_eye.head_from_camera))?;
+			let board = hand_eye.world_from_board.translation.vector;
+			writeln!(f, "  Board in standing space: position {:+.3} {:+.3} {:+.3} m", board.x, board.y, board.z)?;
+			writeln!(f, "  Reprojection RMS {:.3} px, median AX = XB rotation residual {:.4} deg", hand_eye.rms, hand_eye.rotation_residual)?;
+			
+			for (name, reference) in self.references(camera) {
+				writeln!(f, "  Versus {}: {}", name, PoseDiff::new(&reference, &hand_eye.head_from_camera))?;
+			}
+			
+			for (frame, rms) in &hand_eye.residuals {
+				writeln!(f, "  Frame {}: RMS {:.3} px", frame, rms)?;
+			}
+		}
+		
+		if self.property.is_none() {
+			writeln!(f)?;
+			writeln!(f, "Prop_CameraToHeadTransforms_Matrix34_Array was not recorded")?;
+		}
+		
+		let verdict = if self.cameras.iter().all(Result::is_err) {
+			"inconclusive"
+		} else if self.misaligned() {
+			"camera to head MISALIGNED"
+		} else {
+			"camera to head consistent"
+		};
+		
+		writeln!(f)?;
+		writeln!(f, "Verdict: {}", verdict)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn camera() -> FisheyeCamera {
+		FisheyeCamera {
+			focal: Vector2::new(280.0, 280.0),
+			center: Vector2::new(480.0, 480.0),
+			coeffs: [0.05, -0.01, 0.002, 0.0],
+			width: 960,
+			height: 960,
+		}
+	}
+	
+	/// Board views of a known camera to head transform from head poses turned in every direction.
+	fn views(head_from_camera: &Isometry3<f64>) -> Vec<HandEyeView> {
+		let object = (0..5).flat_map(|row| (0..7).map(move |column| Point3::new(column as f64 * 0.04, row as f64 * 0.04, 0.0))).collect::<Vec<_>>();
+		let world_from_board = *head_from_camera * Isometry3::translation(-0.12, -0.08, 0.6);
+		
+		[
+			(0.0, 0.0, 0.0), (0.15, 0.05, -0.1), (-0.1, 0.2, 0.05), (0.05, -0.15, 0.2), (-0.2, -0.05, -0.15), (0.1, 0.15, 0.15),
+		].iter().enumerate().map(|(frame, &(roll, pitch, yaw))| {
+			let world_from_head = Isometry3::from_parts(Translation3::new(roll * 0.2, 1.6 + pitch * 0.1, yaw * 0.3), UnitQuaternion::from_euler_angles(roll, pitch, yaw));
+			let camera_from_board = (world_from_head * head_from_camera).inverse() * world_from_board;
+			let pixels = object.iter().map(|point| camera().project(&(camera_from_board * point).coords).unwrap()).collect();
+			
+			HandEyeView { frame, world_from_head, camera_from_board, object: object.clone(), pixels }
+		}).collect()
+	}
+	
+	fn truth() -> Isometry3<f64> {
+		Isometry3::from_parts(Translation3::new(-0.065, 0.01, -0.08), UnitQuaternion::from_euler_angles(3.1, 0.02, -0.01))
+	}
+	
+	#[test]
+	fn recovers_a_known_camera_to_head() {
+		let truth = truth();
+		let mut views = views(&truth);
+		
+		// Board poses a little off, as from a noisy PnP, which the refinement on the pixels should undo
+		for (index, view) in views.iter_mut().enumerate() {
+			let sign = if index % 2 == 0 { 1.0 } else { -1.0 };
+			view.camera_from_board = apply_pose(&view.camera_from_board, &[0.002 * sign, -0.001, 0.001 * sign, 0.001, -0.002 * sign, 0.003]);
+		}
+		
+		let hand_eye = HandEye::solve(&camera(), 0, &views).unwrap();
+		assert_eq!(hand_eye.pairs, 15);
+		
+		let linear = PoseDiff::new(&truth, &hand_eye.linear);
+		assert!(linear.translation.norm() < 0.02 && linear.angle < 1.0, "closed form off by {}", linear);
+		
+		let refined = PoseDiff::new(&truth, &hand_eye.head_from_camera);
+		assert!(refined.translation.norm() < 1e-4 && refined.angle < 1e-3, "refined off by {}", refined);
+		assert!(hand_eye.rms < 1e-3, "rms {}", hand_eye.rms);
+	}
+	
+	#[test]
+	fn exact_motions_give_an_exact_closed_form() {
+		let truth = truth();
+		let hand_eye = HandEye::solve(&camera(), 0, &views(&truth)).unwrap();
+		
+		let linear = PoseDiff::new(&truth, &hand_eye.linear);
+		assert!(linear.translation.norm() < 1e-9 && linear.angle < 1e-6, "closed form off by {}", linear);
+		assert!(hand_eye.rotation_residual < 1e-6);
+	}
+	
+	#[test]
+	fn needs_enough_views() {
+		let views = views(&truth());
+		assert!(HandEye::solve(&camera(), 0, &views[..MIN_HAND_EYE_VIEWS - 1]).is_none());
+	}
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::Path;
+use nalgebra::Isometry3;
+use serde::{Serialize, Deserialize};
+
+use super::{DumpError, isometry_from_matrix34, gl_from_cv};
+use crate::openvr_load::TrackingUniverseOrigin;
+
+/// `manifest.json` next to the frames of a dump or recording. Older ones don't have it, their poses are standing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+	/// `seated`, `standing` or `raw`, the space of the frame header poses.
+	pub tracking_space: String,
+	/// `Prop_CurrentUniverseId_Uint64`, if it could be read.
+	pub universe_id: Option<u64>,
+	pub seated_zero_pose_to_standing: [[f32; 4]; 3],
+	pub raw_zero_pose_to_standing: [[f32; 4]; 3],
+	/// `Prop_CameraToHeadTransforms_Matrix34_Array`, if it could be read.
+	#[serde(default)]
+	pub cameras_to_head: Option<Vec<[[f32; 4]; 3]>>,
+}
+
+impl Manifest {
+	/// Reads `manifest.json` in `folder`, `None` if there is none.
+	pub fn load(folder: &Path) -> Result<Option<Manifest>, DumpError> {
+		let path = folder.join("manifest.json");
+		
+		if !path.is_file() {
+			return Ok(None);
+		}
+		
+		let manifest = fs::read_to_string(&path).map_err(|err| DumpError::Io(path.clone(), err))?;
+		let manifest = serde_json::from_str::<Manifest>(&manifest).map_err(|err| DumpError::Invalid(path.clone(), err.to_string()))?;
+		
+		if manifest.space().is_none() {
+			return Err(DumpError::Invalid(path, format!("unknown tracking space {}", manifest.tracking_space)));
+		}
+		
+		Ok(Some(manifest))
+	}
+	
+	pub fn space(&self) -> Option<TrackingUniverseOrigin> {
+		TrackingUniverseOrigin::parse(&self.tracking_space)
+	}
+	
+	/// Transform from the space of the frame poses to standing, as it was when they were captured.
+	pub fn standing_from_tracking(&self) -> Isometry3<f64> {
+		match self.space() {
+			Some(TrackingUniverseOrigin::Seated) => isometry_from_matrix34(&self.seated_zero_pose_to_standing),
+			Some(TrackingUniverseOrigin::RawAndUncalibrated) => isometry_from_matrix34(&self.raw_zero_pose_to_standing),
+			Some(TrackingUniverseOrigin::Standing) | None => Isometry3::identity(),
+		}
+	}
+	
+	/// `cameras_to_head` as camera (computer vision convention) to head transforms.
+	pub fn cameras_to_head(&self) -> Option<Vec<Isometry3<f64>>> {
+		self.cameras_to_head.as_ref().map(|transforms| transforms.iter().map(|m| isometry_from_matrix34(m) * gl_from_cv()).collect())
+	}
+}
@@ -0,0 +1,37 @@
+mod error;
+mod config;
+mod camera_model;
+mod validate;
+mod projection;
+mod rectify;
+mod dump;
+mod quality;
+mod bundle;
+mod patch;
+mod compare;
+mod pose;
+mod recording;
+mod manifest;
+mod frame_header;
+mod calibrate;
+mod handeye;
+mod tags;
+
+pub use error::*;
+pub use config::*;
+pub use camera_model::*;
+pub use validate::*;
+pub use projection::*;
+pub use rectify::*;
+pub use dump::*;
+pub use quality::*;
+pub use bundle::*;
+pub use patch::*;
+pub use compare::*;
+pub use pose::*;
+pub use recording::*;
+pub use manifest::*;
+pub use frame_header::*;
+pub use calibrate::*;
+pub use handeye::*;
+pub use tags::*;
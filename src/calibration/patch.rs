@@ -0,0 +1,293 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use serde_json::Value;
+
+use super::{ConfigError, LighthouseConfig, TrackedCameraConfig};
+
+const BACKUP_PREFIX: &str = "config.backup-";
+
+/// A single field that differs between two JSON documents, addressed like `tracked_cameras[0].extrinsics.plus_x[1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldChange {
+	Added(String, Value),
+	Removed(String, Value),
+	Changed(String, Value, Value),
+}
+
+impl FieldChange {
+	pub fn path(&self) -> &str {
+		match self {
+			FieldChange::Added(path, _) | FieldChange::Removed(path, _) | FieldChange::Changed(path, _, _) => path,
+		}
+	}
+}
+
+impl Display for FieldChange {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		match self {
+			FieldChange::Added(path, value) => write!(f, "+ {} = {}", path, value),
+			FieldChange::Removed(path, value) => write!(f, "- {} = {}", path, value),
+			FieldChange::Changed(path, old, new) => write!(f, "~ {}: {} -> {}", path, old, new),
+		}
+	}
+}
+
+/// Lists every leaf field that was added, removed or changed from `old` to `new`.
+pub fn diff_json(old: &Value, new: &Value) -> Vec<FieldChange> {
+	let mut changes = vec![];
+	diff_at(String::new(), old, new, &mut changes);
+	changes
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+	let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+	
+	match (old, new) {
+		(Value::Object(old), Value::Object(new)) => {
+			for (key, old_value) in old {
+				match new.get(key) {
+					Some(new_value) => diff_at(join(key), old_value, new_value, changes),
+					None => changes.push(FieldChange::Removed(join(key), old_value.clone())),
+				}
+			}
+			
+			for (key, new_value) in new {
+				if !old.contains_key(key) {
+					changes.push(FieldChange::Added(join(key), new_value.clone()));
+				}
+			}
+		},
+		(Value::Array(old), Value::Array(new)) => {
+			for index in 0..old.len().max(new.len()) {
+				let item = format!("{}[{}]", path, index);
+				
+				match (old.get(index), new.get(index)) {
+					(Some(old_value), Some(new_value)) => diff_at(item, old_value, new_value, changes),
+					(Some(old_value), None) => changes.push(FieldChange::Removed(item, old_value.clone())),
+					(None, Some(new_value)) => changes.push(FieldChange::Added(item, new_value.clone())),
+					(None, None) => {},
+				}
+			}
+		},
+		(old, new) if old != new => changes.push(FieldChange::Changed(path, old.clone(), new.clone())),
+		_ => {},
+	}
+}
+
+/// Recursively copies the fields of `source` into `target`, leaving fields `source` doesn't have alone.
+fn merge(target: &mut Value, source: &Value) {
+	match (target, source) {
+		(Value::Object(target), Value::Object(source)) => {
+			for (key, value) in source {
+				match target.get_mut(key) {
+					Some(existing) => merge(existing, value),
+					None => { target.insert(key.clone(), value.clone()); },
+				}
+			}
+		},
+		(target, source) => *target = source.clone(),
+	}
+}
+
+/// A lighthouse config with corrected tracked cameras, ready to be written over the original.
+pub struct ConfigPatch {
+	pub path: PathBuf,
+	pub original: String,
+	pub patched: String,
+	pub changes: Vec<FieldChange>,
+}
+
+impl ConfigPatch {
+	/// Patches the config at `path` with `cameras`, every field the cameras don't have is kept as it is.
+	pub fn new(path: &Path, cameras: &[TrackedCameraConfig]) -> Result<ConfigPatch, ConfigError> {
+		let original = fs::read_to_string(path)?;
+		let old: Value = serde_json::from_str(&original)?;
+		let mut new = old.clone();
+		
+		let tracked_cameras = match new.get_mut("tracked_cameras").and_then(Value::as_array_mut) {
+			Some(tracked_cameras) if tracked_cameras.len() >= cameras.len() => tracked_cameras,
+			_ => return Err(ConfigError::NoCameras),
+		};
+		
+		for (target, camera) in tracked_cameras.iter_mut().zip(cameras) {
+			merge(target, &serde_json::to_value(camera)?);
+		}
+		
+		let patched = serde_json::to_string_pretty(&new)?;
+		
+		// The patched config has to parse back to exactly the cameras we put in
+		let parsed = LighthouseConfig::parse(&patched)?;
+		for (index, camera) in cameras.iter().enumerate() {
+			if serde_json::to_value(&parsed.tracked_cameras[index])? != serde_json::to_value(camera)? {
+				return Err(ConfigError::Invalid(format!("tracked_cameras[{}] doesn't read back as written", index)));
+			}
+		}
+		
+		Ok(ConfigPatch { path: path.to_path_buf(), original, changes: diff_json(&old, &new), patched })
+	}
+	
+	/// Backs the original config up and writes the patched one, returns the backup path.
+	pub fn apply(&self) -> Result<PathBuf, ConfigError> {
+		if fs::read_to_string(&self.path)? != self.original {
+			return Err(ConfigError::Invalid("config changed since the patch was made".into()));
+		}
+		
+		let backup = backup(&self.path)?;
+		write_validated(&self.path, &self.patched)?;
+		
+		Ok(backup)
+	}
+}
+
+/// Copies `config` to a timestamped backup next to it.
+pub fn backup(config: &Path) -> Result<PathBuf, ConfigError> {
+	let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+	let mut backup = config.with_file_name(format!("{}{}.json", BACKUP_PREFIX, timestamp));
+	
+	for attempt in 1.. {
+		if !backup.exists() {
+			break;
+		}
+		
+		backup = config.with_file_name(format!("{}{}-{}.json", BACKUP_PREFIX, timestamp, attempt));
+	}
+	
+	fs::copy(config, &backup)?;
+	Ok(backup)
+}
+
+/// Backups of `config`, oldest first by the timestamp in their name, copying files around changes their modification time.
+pub fn backups(config: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+	let directory = config.parent().unwrap_or_else(|| Path::new("."));
+	let mut backups = fs::read_dir(directory)?
+	                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+	                    .filter_map(|path| Some((backup_order(path.file_name()?.to_str()?)?, path)))
+	                    .collect::<Vec<_>>();
+	
+	backups.sort();
+	Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Timestamp and attempt number from the name `backup` gave a file, `None` for other files.
+fn backup_order(name: &str) -> Option<(u64, u32)> {
+	let stamp = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".json")?;
+	
+	Some(match stamp.split_once('-') {
+		Some((timestamp, attempt)) => (timestamp.parse().ok()?, attempt.parse().ok()?),
+		None => (stamp.parse().ok()?, 0),
+	})
+}
+
+/// Restores `backup` over `config`, after backing up the current config too. Returns that new backup.
+pub fn rollback(config: &Path, backup_path: &Path) -> Result<PathBuf, ConfigError> {
+	let restored = fs::read_to_string(backup_path)?;
+	LighthouseConfig::parse(&restored)?;
+	
+	let current = backup(config)?;
+	write_validated(config, &restored)?;
+	
+	Ok(current)
+}
+
+/// Writes through a temporary file, which has to parse back as a config before replacing `path`.
+fn write_validated(path: &Path, text: &str) -> Result<(), ConfigError> {
+	let temporary = path.with_extension("json.tmp");
+	fs::write(&temporary, text)?;
+	
+	let written = fs::read_to_string(&temporary)?;
+	if written != text {
+		return Err(ConfigError::Invalid("temporary file doesn't read back as written".into()));
+	}
+	
+	LighthouseConfig::parse(&written)?;
+	fs::rename(&temporary, path)?;
+	
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+	
+	/// A config with an unnamed camera and a field we don't know about, in a fresh folder under the temp dir.
+	fn config(test: &str) -> PathBuf {
+		let directory = std::env::temp_dir().join(format!("vrcamdump-{}-{}", test, std::process::id()));
+		let _ = fs::remove_dir_all(&directory);
+		fs::create_dir_all(&directory).unwrap();
+		
+		let config = json!({
+			"device_serial_number": "LHR-00000000",
+			"tracked_cameras": [{
+				"extrinsics": { "plus_x": [1.0, 0.0, 0.0], "plus_z": [0.0, 0.0, 1.0], "position": [-0.05, 0.0, 0.0] },
+				"intrinsics": {
+					"center_x": 320.0, "center_y": 240.0, "focal_x": 300.0, "focal_y": 300.0, "width": 640, "height": 480,
+					"distort": { "coeffs": [0.1, 0.01, 0.0, 0.0], "type": "DISTORT_FTHETA" },
+				},
+				"serial": 1,
+			}],
+		});
+		
+		let path = directory.join("config.json");
+		fs::write(&path, serde_json::to_string_pretty(&config).unwrap()).unwrap();
+		path
+	}
+	
+	#[test]
+	fn applies_backs_up_and_rolls_back() {
+		let path = config("patch");
+		let original = fs::read(&path).unwrap();
+		
+		let mut cameras = LighthouseConfig::parse(&fs::read_to_string(&path).unwrap()).unwrap().tracked_cameras;
+		cameras[0].intrinsics.focal_x = 301.0;
+		let patch = ConfigPatch::new(&path, &cameras).unwrap();
+		
+		// Only the focal length changed, the unnamed camera doesn't get an empty name
+		assert_eq!(patch.changes, [FieldChange::Changed("tracked_cameras[0].intrinsics.focal_x".into(), json!(300.0), json!(301.0))]);
+		
+		// Making the patch without applying it, like answering no, leaves the config alone
+		assert_eq!(fs::read(&path).unwrap(), original);
+		assert!(backups(&path).unwrap().is_empty());
+		
+		let backup = patch.apply().unwrap();
+		assert_eq!(backups(&path).unwrap(), std::slice::from_ref(&backup));
+		assert_eq!(fs::read(&backup).unwrap(), original);
+		assert_eq!(fs::read_to_string(&path).unwrap(), patch.patched);
+		
+		let replaced = rollback(&path, &backup).unwrap();
+		assert_eq!(fs::read(&path).unwrap(), original);
+		assert_eq!(fs::read_to_string(&replaced).unwrap(), patch.patched);
+		assert_eq!(backups(&path).unwrap().len(), 2);
+		
+		fs::remove_dir_all(path.parent().unwrap()).unwrap();
+	}
+	
+	#[test]
+	fn backups_are_ordered_by_their_name() {
+		let mut names = vec![
+			"config.backup-1700000010.json",
+			"config.backup-1700000000-2.json",
+			"config.backup-999999999.json",
+			"config.backup-1700000000.json",
+			"config.backup-1700000000-10.json",
+		];
+		names.sort_by_key(|name| backup_order(name));
+		
+		assert_eq!(names, [
+			"config.backup-999999999.json",
+			"config.backup-1700000000.json",
+			"config.backup-1700000000-2.json",
+			"config.backup-1700000000-10.json",
+			"config.backup-1700000010.json",
+		]);
+	}
+	
+	#[test]
+	fn other_files_are_not_backups() {
+		assert_eq!(backup_order("config.json"), None);
+		assert_eq!(backup_order("config.backup-1700000000.json.tmp"), None);
+		assert_eq!(backup_order("config.backup-copy.json"), None);
+	}
+}
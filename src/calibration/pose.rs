@@ -0,0 +1,131 @@
+use nalgebra::{Isometry3, Matrix3, Matrix6, Point3, Rotation3, Translation3, UnitQuaternion, Vector2, Vector3, Vector6};
+
+use super::FisheyeCamera;
+use crate::vision::Homography;
+
+pub const PNP_ITERATIONS: usize = 30;
+
+/// Applies a rotation vector and translation offset on the right side of `pose`.
+pub fn apply_pose(pose: &Isometry3<f64>, delta: &[f64]) -> Isometry3<f64> {
+	pose * Isometry3::from_parts(Translation3::new(delta[3], delta[4], delta[5]),
+	                             UnitQuaternion::from_scaled_axis(Vector3::new(delta[0], delta[1], delta[2])))
+}
+
+/// Pose of an object in camera space from at least four of its points and their pixels.
+///
+/// Starts from the homography of the plane that fits the points best, so the points should be roughly coplanar, then
+/// minimizes the reprojection error.
+pub fn solve_pnp(camera: &FisheyeCamera, object: &[Point3<f64>], pixels: &[Vector2<f64>]) -> Option<Isometry3<f64>> {
+	if object.len() < 4 || object.len() != pixels.len() {
+		return None;
+	}
+	
+	let plane_from_object = fit_plane(object);
+	let (plane, normalized): (Vec<_>, Vec<_>) = object.iter().zip(pixels).filter_map(|(point, pixel)| {
+		let ray = camera.unproject(pixel).filter(|ray| ray.z > 0.05)?;
+		let point = plane_from_object * point;
+		
+		Some(([point.x, point.y], [ray.x / ray.z, ray.y / ray.z]))
+	}).unzip();
+	
+	let h = Homography::from_points(&plane, &normalized)?.0;
+	let scale = 2.0 / (h.column(0).norm() + h.column(1).norm());
+	let sign = if h[(2, 2)] < 0.0 { -scale } else { scale };
+	let (r1, r2, t) = (h.column(0) * sign, h.column(1) * sign, h.column(2) * sign);
+	let rotation = Matrix3::from_columns(&[r1, r2, r1.cross(&r2)]);
+	
+	let camera_from_plane = Isometry3::from_parts(Translation3::from(t), UnitQuaternion::from_matrix(&rotation));
+	
+	refine_pose(camera, &(camera_from_plane * plane_from_object), object, pixels)
+}
+
+/// Minimizes the reprojection error of `initial` with Levenberg-Marquardt.
+pub fn refine_pose(camera: &FisheyeCamera, initial: &Isometry3<f64>, object: &[Point3<f64>], pixels: &[Vector2<f64>]) -> Option<Isometry3<f64>> {
+	let cost = |pose: &Isometry3<f64>| -> Option<f64> {
+		reprojection_errors(camera, pose, object, pixels).into_iter().map(|error| error.map(|e| e.norm_squared())).sum()
+	};
+	
+	let mut pose = *initial;
+	let mut current = cost(&pose)?;
+	let mut lambda = 1e-3;
+	
+	for _ in 0..PNP_ITERATIONS {
+		let mut normal = Matrix6::zeros();
+		let mut gradient = Vector6::zeros();
+		
+		for (point, pixel) in object.iter().zip(pixels) {
+			let residual = |pose: &Isometry3<f64>| camera.project(&(pose * point).coords).map(|projected| projected - pixel);
+			let r = residual(&pose)?;
+			let mut jacobian = nalgebra::Matrix2x6::zeros();
+			
+			for i in 0..6 {
+				let mut delta = [0.0; 6];
+				delta[i] = 1e-6;
+				let plus = residual(&apply_pose(&pose, &delta))?;
+				delta[i] = -1e-6;
+				let minus = residual(&apply_pose(&pose, &delta))?;
+				
+				jacobian.set_column(i, &((plus - minus) / 2e-6));
+			}
+			
+			normal += jacobian.transpose() * jacobian;
+			gradient += jacobian.transpose() * r;
+		}
+		
+		let mut improved = false;
+		
+		while lambda < 1e10 {
+			let damped = normal + Matrix6::from_diagonal(&normal.diagonal()) * lambda;
+			let step = match damped.cholesky() {
+				Some(cholesky) => -cholesky.solve(&gradient),
+				None => { lambda *= 10.0; continue },
+			};
+			
+			let candidate = apply_pose(&pose, step.as_slice());
+			match cost(&candidate) {
+				Some(cost) if cost < current => {
+					improved = (current - cost) / current.max(1e-12) > 1e-10;
+					pose = candidate;
+					current = cost;
+					lambda = (lambda / 10.0).max(1e-9);
+					break;
+				},
+				_ => lambda *= 10.0,
+			}
+		}
+		
+		if !improved {
+			break;
+		}
+	}
+	
+	Some(pose)
+}
+
+/// Projected minus measured pixel of every point, `None` for points that don't project.
+pub fn reprojection_errors(camera: &FisheyeCamera, camera_from_object: &Isometry3<f64>, object: &[Point3<f64>], pixels: &[Vector2<f64>]) -> Vec<Option<Vector2<f64>>> {
+	object.iter().zip(pixels).map(|(point, pixel)| {
+		let local = camera_from_object * point;
+		
+		if local.z <= 0.0 {
+			return None;
+		}
+		
+		camera.project(&local.coords).map(|projected| projected - pixel)
+	}).collect()
+}
+
+/// Frame with the points' best fit plane as Z = 0, centered on them.
+fn fit_plane(points: &[Point3<f64>]) -> Isometry3<f64> {
+	let centroid = points.iter().map(|point| point.coords).sum::<Vector3<f64>>() / points.len() as f64;
+	let covariance = points.iter().map(|point| (point.coords - centroid) * (point.coords - centroid).transpose()).sum::<Matrix3<f64>>();
+	let eigen = covariance.symmetric_eigen();
+	
+	let mut order = [0, 1, 2];
+	order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+	let x = eigen.eigenvectors.column(order[0]).into_owned();
+	let y = eigen.eigenvectors.column(order[1]).into_owned();
+	let object_from_plane = Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[x, y, x.cross(&y)]));
+	
+	Isometry3::from_parts(Translation3::from(centroid), UnitQuaternion::from_rotation_matrix(&object_from_plane)).inverse()
+}
@@ -0,0 +1,288 @@
+use std::fmt::{self, Display, Formatter};
+use nalgebra::Vector2;
+
+use super::{LighthouseConfig, FisheyeCamera, PinholeCamera, FRAME_TYPES, PIXEL_TOLERANCE, frame_layout};
+use crate::openvr_load::TrackedDeviceIndex;
+use crate::system::CameraProperties;
+use crate::tracked_camera::{TrackedCamera, FrameType, FrameLayout, Frustum, Fov, Intrinsics, Z_NEAR, Z_FAR};
+
+/// Part of an undistorted image that has source pixels in the distorted one.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidRegion {
+	/// Bounding box of all valid pixels: min x, min y, max x, max y.
+	pub bounds: [u32; 4],
+	/// Largest fully valid rectangle with the image aspect ratio, centered on the principal point.
+	pub inner: [u32; 4],
+	/// Fraction of the image that is valid.
+	pub fraction: f64,
+}
+
+impl ValidRegion {
+	pub fn new(distorted: &FisheyeCamera, undistorted: &PinholeCamera) -> ValidRegion {
+		let width = undistorted.width as usize;
+		let height = undistorted.height as usize;
+		// Integral image of valid pixels, (width + 1) x (height + 1)
+		let mut integral = vec![0u32; (width + 1) * (height + 1)];
+		let mut bounds = [u32::MAX, u32::MAX, 0, 0];
+		let mut count = 0;
+		
+		for y in 0..height {
+			let mut row = 0;
+			
+			for x in 0..width {
+				let ray = undistorted.unproject(&Vector2::new(x as f64 + 0.5, y as f64 + 0.5));
+				let valid = distorted.project(&ray).is_some_and(|pixel| distorted.contains(&pixel));
+				
+				if valid {
+					row += 1;
+					count += 1;
+					bounds = [bounds[0].min(x as u32), bounds[1].min(y as u32), bounds[2].max(x as u32), bounds[3].max(y as u32)];
+				}
+				
+				integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+			}
+		}
+		
+		let valid_in = |x0: usize, y0: usize, x1: usize, y1: usize| {
+			integral[y1 * (width + 1) + x1] + integral[y0 * (width + 1) + x0]
+				- integral[y0 * (width + 1) + x1] - integral[y1 * (width + 1) + x0]
+		};
+		
+		let center = undistorted.center;
+		let max_half = Vector2::new(center.x.min(width as f64 - center.x), center.y.min(height as f64 - center.y));
+		let rect = |scale: f64| {
+			let half = max_half * scale;
+			[
+				(center.x - half.x).max(0.0) as usize,
+				(center.y - half.y).max(0.0) as usize,
+				((center.x + half.x) as usize).min(width),
+				((center.y + half.y) as usize).min(height),
+			]
+		};
+		
+		let fits = |scale: f64| {
+			let [x0, y0, x1, y1] = rect(scale);
+			valid_in(x0, y0, x1, y1) as usize == (x1 - x0) * (y1 - y0)
+		};
+		
+		// The search never reaches the upper end, which a fully valid image needs.
+		let (mut low, mut high) = if fits(1.0) { (1.0, 1.0) } else { (0.0, 1.0) };
+		while high - low > 1e-9 {
+			let mid = (low + high) / 2.0;
+			
+			if fits(mid) {
+				low = mid;
+			} else {
+				high = mid;
+			}
+		}
+		
+		let [x0, y0, x1, y1] = rect(low);
+		
+		if count == 0 {
+			bounds = [0; 4];
+		}
+		
+		ValidRegion {
+			bounds,
+			inner: [x0 as u32, y0 as u32, x1.saturating_sub(1) as u32, y1.saturating_sub(1) as u32],
+			fraction: count as f64 / (width * height).max(1) as f64,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct FrameProjection {
+	pub frame_type: FrameType,
+	pub camera_size: (u32, u32),
+	pub frustum: Frustum,
+	pub fov: Fov,
+	/// Intrinsics decomposed from the projection matrix.
+	pub equivalent: Intrinsics,
+	/// Intrinsics reported by `get_camera_intrinsics`.
+	pub intrinsics: Option<Intrinsics>,
+	pub valid_region: Option<ValidRegion>,
+}
+
+impl FrameProjection {
+	/// Focal length and principal point differences between `equivalent` and `intrinsics`.
+	pub fn delta(&self) -> Option<[f64; 4]> {
+		self.intrinsics.map(|intrinsics| [
+			(self.equivalent.focal_length[0] - intrinsics.focal_length[0]) as f64,
+			(self.equivalent.focal_length[1] - intrinsics.focal_length[1]) as f64,
+			(self.equivalent.center[0] - intrinsics.center[0]) as f64,
+			(self.equivalent.center[1] - intrinsics.center[1]) as f64,
+		])
+	}
+	
+	pub fn passed(&self) -> bool {
+		self.delta().is_some_and(|delta| delta.iter().all(|d| d.abs() <= PIXEL_TOLERANCE))
+	}
+}
+
+#[derive(Debug)]
+pub struct CameraProjections {
+	pub index: u32,
+	pub frames: Vec<FrameProjection>,
+	pub errors: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ProjectionReport {
+	pub layout: FrameLayout,
+	pub cameras: Vec<CameraProjections>,
+}
+
+impl ProjectionReport {
+	pub fn new(
+		tracked_camera: &TrackedCamera,
+		device: TrackedDeviceIndex,
+		properties: &CameraProperties,
+		config: Option<&LighthouseConfig>,
+	) -> ProjectionReport {
+		let (layout, distorted_size) = frame_layout(tracked_camera, device, properties);
+		
+		let cameras = (0..layout.camera_count()).map(|index| {
+			let mut errors = vec![];
+			let mut frames = vec![];
+			let camera_config = config.and_then(|config| config.tracked_cameras.get(index as usize));
+			
+			for &frame_type in FRAME_TYPES.iter() {
+				let camera_size = match tracked_camera.get_camera_frame_size(device, frame_type) {
+					Ok(frame_size) => frame_size.camera_size(layout),
+					Err(err) => { errors.push(format!("{:?} frame size: {}", frame_type, err)); continue; },
+				};
+				
+				let projection = match tracked_camera.get_camera_projection(device, index, frame_type, Z_NEAR, Z_FAR) {
+					Ok(projection) => projection,
+					Err(err) => { errors.push(format!("{:?} projection: {}", frame_type, err)); continue; },
+				};
+				
+				let intrinsics = tracked_camera.get_camera_intrinsics(device, index, frame_type)
+				                               .map_err(|err| errors.push(format!("{:?} intrinsics: {}", frame_type, err)))
+				                               .ok();
+				
+				let valid_region = match (frame_type, camera_config, intrinsics) {
+					(FrameType::MaximumUndistorted, Some(camera_config), Some(intrinsics)) => {
+						let (width, height) = distorted_size.camera_size(layout);
+						let distorted = FisheyeCamera::from_config(&camera_config.intrinsics, width, height);
+						let undistorted = PinholeCamera {
+							focal: Vector2::new(intrinsics.focal_length[0] as f64, intrinsics.focal_length[1] as f64),
+							center: Vector2::new(intrinsics.center[0] as f64, intrinsics.center[1] as f64),
+							width: camera_size.0,
+							height: camera_size.1,
+						};
+						
+						Some(ValidRegion::new(&distorted, &undistorted))
+					},
+					_ => None,
+				};
+				
+				let frustum = projection.frustum();
+				
+				frames.push(FrameProjection {
+					frame_type,
+					camera_size,
+					frustum,
+					fov: frustum.fov(),
+					equivalent: projection.intrinsics(camera_size.0, camera_size.1),
+					intrinsics,
+					valid_region,
+				});
+			}
+			
+			CameraProjections { index, frames, errors }
+		}).collect();
+		
+		ProjectionReport { layout, cameras }
+	}
+	
+	pub fn passed(&self) -> bool {
+		self.cameras.iter().all(|camera| camera.frames.iter().all(FrameProjection::passed))
+	}
+}
+
+impl Display for ProjectionReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Frame layout: {:?}", self.layout)?;
+		writeln!(f, "Projections use z_near = {}, z_far = {}", Z_NEAR, Z_FAR)?;
+		
+		for camera in &self.cameras {
+			for frame in &camera.frames {
+				let k = &frame.equivalent;
+				
+				writeln!(f)?;
+				writeln!(f, "Camera {} {:?} {}x{}: {}", camera.index, frame.frame_type, frame.camera_size.0, frame.camera_size.1,
+				         if frame.passed() { "PASS" } else { "FAIL" })?;
+				writeln!(f, "  Frustum tangents: left {:.4}, right {:.4}, bottom {:.4}, top {:.4}",
+				         frame.frustum.left, frame.frustum.right, frame.frustum.bottom, frame.frustum.top)?;
+				writeln!(f, "  FOV: horizontal {:.2} deg, vertical {:.2} deg, diagonal {:.2} deg",
+				         frame.fov.horizontal, frame.fov.vertical, frame.fov.diagonal)?;
+				writeln!(f, "  Projection K: focal {:.3} {:.3}, center {:.3} {:.3}",
+				         k.focal_length[0], k.focal_length[1], k.center[0], k.center[1])?;
+				
+				if let (Some(intrinsics), Some(delta)) = (frame.intrinsics, frame.delta()) {
+					writeln!(f, "  Intrinsics K: focal {:.3} {:.3}, center {:.3} {:.3}",
+					         intrinsics.focal_length[0], intrinsics.focal_length[1], intrinsics.center[0], intrinsics.center[1])?;
+					writeln!(f, "  Delta: focal {:+.3} {:+.3} px, center {:+.3} {:+.3} px", delta[0], delta[1], delta[2], delta[3])?;
+				}
+				
+				if let Some(region) = &frame.valid_region {
+					writeln!(f, "  Valid pixels: {:.1}%, bounds x {}..={} y {}..={}, inner rectangle x {}..={} y {}..={}",
+					         region.fraction * 100.0,
+					         region.bounds[0], region.bounds[2], region.bounds[1], region.bounds[3],
+					         region.inner[0], region.inner[2], region.inner[1], region.inner[3])?;
+				}
+			}
+			
+			for error in &camera.errors {
+				writeln!(f, "Camera {} error: {}", camera.index, error)?;
+			}
+		}
+		
+		writeln!(f)?;
+		writeln!(f, "Verdict: {}", if self.passed() { "PASS" } else { "FAIL" })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn equidistant(focal: f64) -> FisheyeCamera {
+		FisheyeCamera { focal: Vector2::new(focal, focal), center: Vector2::new(100.0, 100.0), coeffs: [0.0; 4], width: 200, height: 200 }
+	}
+	
+	fn pinhole(focal: f64) -> PinholeCamera {
+		PinholeCamera { focal: Vector2::new(focal, focal), center: Vector2::new(50.0, 50.0), width: 100, height: 100 }
+	}
+	
+	#[test]
+	fn narrow_view_is_fully_valid() {
+		// The pinhole corners are 35 deg off axis, well within the fisheye image.
+		let region = ValidRegion::new(&equidistant(100.0), &pinhole(70.0));
+		
+		assert_eq!(region.fraction, 1.0);
+		assert_eq!(region.bounds, [0, 0, 99, 99]);
+		assert_eq!(region.inner, [0, 0, 99, 99]);
+	}
+	
+	#[test]
+	fn wide_view_loses_the_corners() {
+		// The fisheye image ends at 1 rad off axis along the sides, the pinhole edges are at atan(50 / 20) = 1.19 rad.
+		let region = ValidRegion::new(&equidistant(100.0), &pinhole(20.0));
+		
+		assert!(region.fraction > 0.2 && region.fraction < 0.8, "{}", region.fraction);
+		
+		let [x0, y0, x1, y1] = region.inner;
+		assert!(x0 > 0 && y0 > 0 && x1 < 99 && y1 < 99);
+		// Centered on the principal point, up to the pixel the exclusive end loses.
+		assert!((x0 + x1).abs_diff(99) <= 1 && (y0 + y1).abs_diff(99) <= 1, "{:?}", region.inner);
+		
+		let camera = pinhole(20.0);
+		for [x, y] in [[x0, y0], [x1, y0], [x0, y1], [x1, y1]] {
+			let ray = camera.unproject(&Vector2::new(x as f64 + 0.5, y as f64 + 0.5));
+			assert!(equidistant(100.0).project(&ray).is_some_and(|pixel| equidistant(100.0).contains(&pixel)));
+		}
+	}
+}
@@ -0,0 +1,344 @@
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use image::GrayImage;
+use nalgebra::{Matrix3, Vector3};
+
+use super::{Dump, DumpError, StereoRig, Rectification, RECTIFIED_FOV};
+use crate::vision::{Feature, FeatureMatch, to_gray, detect_features, match_stereo};
+
+/// Largest median absolute vertical disparity of a good calibration, in rectified pixels.
+pub const VERTICAL_DISPARITY_TOLERANCE: f64 = 1.0;
+/// Fewer matches than this over all dumps of a headset are not enough for a verdict.
+pub const MIN_MATCHES: usize = 50;
+pub const MAX_FEATURES: usize = 2000;
+/// The distribution of vertical disparity is reported over a grid of this many cells per side.
+pub const DISTRIBUTION_GRID: usize = 4;
+const MIN_CELL_MATCHES: usize = 5;
+
+/// Vertical disparity of a single match.
+#[derive(Debug, Clone, Copy)]
+pub struct DisparitySample {
+	/// Left image position, normalized by the rectified focal length.
+	pub normalized: [f64; 2],
+	/// Left image position as a fraction of the image size.
+	pub relative: [f64; 2],
+	/// Vertical disparity in rectified pixels.
+	pub vertical: f64,
+	pub focal: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisparityStats {
+	pub count: usize,
+	pub mean: f64,
+	pub median: f64,
+	pub median_abs: f64,
+	pub rms: f64,
+	pub p90_abs: f64,
+}
+
+impl DisparityStats {
+	pub fn new(values: &[f64]) -> DisparityStats {
+		if values.is_empty() {
+			return DisparityStats::default();
+		}
+		
+		let count = values.len();
+		let abs = values.iter().map(|v| v.abs()).collect::<Vec<_>>();
+		
+		DisparityStats {
+			count,
+			mean: values.iter().sum::<f64>() / count as f64,
+			median: percentile(values, 0.5),
+			median_abs: percentile(&abs, 0.5),
+			rms: (values.iter().map(|v| v * v).sum::<f64>() / count as f64).sqrt(),
+			p90_abs: percentile(&abs, 0.9),
+		}
+	}
+}
+
+pub fn percentile(values: &[f64], fraction: f64) -> f64 {
+	let mut sorted = values.to_vec();
+	sorted.sort_by(|a, b| a.total_cmp(b));
+	
+	sorted[((sorted.len() - 1) as f64 * fraction).round() as usize]
+}
+
+/// Residual misalignment of the second camera explaining the vertical disparity.
+///
+/// Fits `dy / f = offset + roll * x + scale * y` with `x`, `y` normalized by the rectified focal length: a constant
+/// offset comes from a relative pitch, a slope along x from a relative roll and a slope along y from a vertical focal
+/// length mismatch.
+#[derive(Debug, Clone, Copy)]
+pub struct DisparityModel {
+	/// Degrees.
+	pub pitch: f64,
+	/// Degrees.
+	pub roll: f64,
+	/// Relative vertical scale difference.
+	pub scale: f64,
+	/// Samples within three robust standard deviations of the fit.
+	pub inliers: usize,
+}
+
+impl DisparityModel {
+	pub fn fit(samples: &[DisparitySample]) -> Option<DisparityModel> {
+		let residual = |samples: &[&DisparitySample], params: &Vector3<f64>| {
+			samples.iter().map(|s| (s.vertical / s.focal - params.dot(&Vector3::new(1.0, s.normalized[0], s.normalized[1]))) * s.focal)
+			       .collect::<Vec<_>>()
+		};
+		
+		let mut inliers = samples.iter().collect::<Vec<_>>();
+		let mut params = Vector3::zeros();
+		
+		for _ in 0..3 {
+			if inliers.len() < 3 {
+				return None;
+			}
+			
+			let mut normal = Matrix3::zeros();
+			let mut rhs = Vector3::zeros();
+			for sample in &inliers {
+				let row = Vector3::new(1.0, sample.normalized[0], sample.normalized[1]);
+				normal += row * row.transpose();
+				rhs += row * (sample.vertical / sample.focal);
+			}
+			
+			params = normal.try_inverse()? * rhs;
+			
+			let abs = residual(&inliers, &params).iter().map(|r| r.abs()).collect::<Vec<_>>();
+			let threshold = (3.0 * 1.4826 * percentile(&abs, 0.5)).max(0.5);
+			inliers = samples.iter().filter(|s| residual(&[s], &params)[0].abs() <= threshold).collect();
+		}
+		
+		Some(DisparityModel {
+			pitch: params[0].atan().to_degrees(),
+			roll: params[1].to_degrees(),
+			scale: params[2],
+			inliers: inliers.len(),
+		})
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct DisparitySummary {
+	pub stats: DisparityStats,
+	pub model: Option<DisparityModel>,
+	/// Median vertical disparity of each grid cell, row major, if it has enough matches.
+	pub grid: Vec<Option<f64>>,
+}
+
+impl DisparitySummary {
+	pub fn new(samples: &[DisparitySample]) -> DisparitySummary {
+		let mut cells = vec![vec![]; DISTRIBUTION_GRID * DISTRIBUTION_GRID];
+		for sample in samples {
+			let cell = |relative: f64| ((relative * DISTRIBUTION_GRID as f64) as usize).min(DISTRIBUTION_GRID - 1);
+			cells[cell(sample.relative[1]) * DISTRIBUTION_GRID + cell(sample.relative[0])].push(sample.vertical);
+		}
+		
+		DisparitySummary {
+			stats: DisparityStats::new(&samples.iter().map(|s| s.vertical).collect::<Vec<_>>()),
+			model: DisparityModel::fit(samples),
+			grid: cells.iter().map(|cell| if cell.len() >= MIN_CELL_MATCHES { Some(percentile(cell, 0.5)) } else { None }).collect(),
+		}
+	}
+	
+	pub fn verdict(&self) -> Verdict {
+		if self.stats.count < MIN_MATCHES {
+			Verdict::Inconclusive
+		} else if self.stats.median_abs <= VERTICAL_DISPARITY_TOLERANCE {
+			Verdict::Ok
+		} else {
+			Verdict::Drifted
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+	Ok,
+	Drifted,
+	Inconclusive,
+}
+
+impl Display for Verdict {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		f.pad(match self {
+			Verdict::Ok => "calibration OK",
+			Verdict::Drifted => "calibration drifted",
+			Verdict::Inconclusive => "inconclusive, not enough matches",
+		})
+	}
+}
+
+/// Features and stereo matches of the rectified images of a single dump.
+pub struct StereoFeatures {
+	pub rig: StereoRig,
+	pub rectification: Rectification,
+	pub images: [GrayImage; 2],
+	pub features: [Vec<Feature>; 2],
+	pub matches: Vec<FeatureMatch>,
+}
+
+impl StereoFeatures {
+	pub fn new(dump: &Dump) -> Result<StereoFeatures, DumpError> {
+		let images = dump.camera_images();
+		if images.len() != 2 {
+			return Err(DumpError::NotStereo);
+		}
+		
+		let rig = StereoRig::from_config(&dump.config, images[0].width(), images[0].height()).ok_or(DumpError::NotStereo)?;
+		let rectification = Rectification::new(&rig, RECTIFIED_FOV);
+		
+		let images = [to_gray(&rectification.remap(&rig, 0, &images[0])), to_gray(&rectification.remap(&rig, 1, &images[1]))];
+		let masks = [rectification.valid_mask(&rig, 0), rectification.valid_mask(&rig, 1)];
+		let features = [
+			detect_features(&images[0], Some(&masks[0]), MAX_FEATURES),
+			detect_features(&images[1], Some(&masks[1]), MAX_FEATURES),
+		];
+		let matches = match_stereo(&images[0], &images[1], &features[0], &features[1]);
+		
+		Ok(StereoFeatures { rig, rectification, images, features, matches })
+	}
+}
+
+/// Vertical disparity of the stereo matches of a single dump.
+pub struct DumpScore {
+	pub path: PathBuf,
+	pub serial: String,
+	pub samples: Vec<DisparitySample>,
+	pub summary: DisparitySummary,
+}
+
+impl DumpScore {
+	pub fn new(dump: &Dump) -> Result<DumpScore, DumpError> {
+		let stereo = StereoFeatures::new(dump)?;
+		let camera = &stereo.rectification.camera;
+		
+		let samples = stereo.matches.iter().map(|m| DisparitySample {
+			normalized: [(m.first[0] - camera.center.x) / camera.focal.x, (m.first[1] - camera.center.y) / camera.focal.y],
+			relative: [m.first[0] / camera.width as f64, m.first[1] / camera.height as f64],
+			vertical: m.vertical_disparity(),
+			focal: camera.focal.y,
+		}).collect::<Vec<_>>();
+		
+		Ok(DumpScore {
+			path: dump.path.clone(),
+			serial: dump.serial().to_string(),
+			summary: DisparitySummary::new(&samples),
+			samples,
+		})
+	}
+}
+
+/// All dumps of a single headset.
+pub struct HeadsetScore {
+	pub serial: String,
+	pub dumps: Vec<DumpScore>,
+	pub summary: DisparitySummary,
+}
+
+impl HeadsetScore {
+	pub fn verdict(&self) -> Verdict {
+		self.summary.verdict()
+	}
+}
+
+pub struct QualityReport {
+	pub headsets: Vec<HeadsetScore>,
+	pub errors: Vec<(PathBuf, DumpError)>,
+}
+
+impl QualityReport {
+	/// Scores every dump in the `dumps` folder, grouped by headset serial.
+	pub fn new(dumps: &Path) -> Result<QualityReport, DumpError> {
+		let mut headsets: Vec<HeadsetScore> = vec![];
+		let mut errors = vec![];
+		
+		for (path, dump) in Dump::load_all(dumps)? {
+			let score = match dump.and_then(|dump| DumpScore::new(&dump)) {
+				Ok(score) => score,
+				Err(err) => { errors.push((path, err)); continue; },
+			};
+			
+			match headsets.iter_mut().find(|headset| headset.serial == score.serial) {
+				Some(headset) => headset.dumps.push(score),
+				None => headsets.push(HeadsetScore { serial: score.serial.clone(), dumps: vec![score], summary: DisparitySummary::new(&[]) }),
+			}
+		}
+		
+		for headset in &mut headsets {
+			let samples = headset.dumps.iter().flat_map(|dump| dump.samples.iter().cloned()).collect::<Vec<_>>();
+			headset.summary = DisparitySummary::new(&samples);
+		}
+		
+		Ok(QualityReport { headsets, errors })
+	}
+}
+
+impl Display for DisparitySummary {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		let stats = &self.stats;
+		
+		writeln!(f, "  Vertical disparity over {} matches: median {:+.3} px, median |dy| {:.3} px, mean {:+.3} px, RMS {:.3} px, 90% |dy| {:.3} px",
+		         stats.count, stats.median, stats.median_abs, stats.mean, stats.rms, stats.p90_abs)?;
+		
+		if let Some(model) = &self.model {
+			writeln!(f, "  Residual misalignment ({} inliers): pitch {:+.4} deg, roll {:+.4} deg, vertical scale {:+.3}%",
+			         model.inliers, model.pitch, model.roll, model.scale * 100.0)?;
+		}
+		
+		writeln!(f, "  Median vertical disparity over the image, px:")?;
+		for row in self.grid.chunks(DISTRIBUTION_GRID) {
+			let cells = row.iter().map(|cell| match cell {
+				Some(median) => format!("{:+7.2}", median),
+				None => format!("{:>7}", "-"),
+			}).collect::<String>();
+			
+			writeln!(f, "   {}", cells)?;
+		}
+		
+		Ok(())
+	}
+}
+
+impl Display for QualityReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		for headset in &self.headsets {
+			writeln!(f, "Headset {}: {}", headset.serial, headset.verdict())?;
+			write!(f, "{}", headset.summary)?;
+			
+			for dump in &headset.dumps {
+				writeln!(f)?;
+				writeln!(f, " Dump {}: {}", dump.path.display(), dump.summary.verdict())?;
+				write!(f, "{}", dump.summary)?;
+			}
+			
+			writeln!(f)?;
+		}
+		
+		for (path, err) in &self.errors {
+			writeln!(f, "Unable to score {}: {}", path.display(), err)?;
+		}
+		
+		if self.headsets.is_empty() {
+			writeln!(f, "No dumps to score")?;
+		}
+		
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn percentile_tolerates_non_finite_values() {
+		let values = [3.0, f64::NAN, 1.0, 2.0, f64::INFINITY];
+		
+		assert_eq!(percentile(&values, 0.0), 1.0);
+		assert_eq!(percentile(&values, 0.5), 3.0);
+		assert!(percentile(&values, 1.0).is_nan());
+	}
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use image::RgbaImage;
+use nalgebra::Isometry3;
+
+use super::{LighthouseConfig, Dump, DumpError, Manifest, FrameHeader};
+use crate::tracked_camera::{FrameLayout, FrameSize};
+use crate::vision::split_frame;
+
+/// One frame of a recording, the image is only loaded when needed.
+#[derive(Debug, Clone)]
+pub struct RecordedFrame {
+	pub image: PathBuf,
+	/// Standing pose of the headset from the header, if it was valid. Poses recorded in another space are converted with
+	/// the transforms in `manifest.json`.
+	pub pose: Option<Isometry3<f64>>,
+	/// Seconds since the start of the recording when the frame was received, from `timestamps.txt`.
+	pub time: Option<f64>,
+}
+
+impl RecordedFrame {
+	pub fn load_image(&self) -> Result<RgbaImage, DumpError> {
+		Ok(image::open(&self.image).map_err(|err| DumpError::Image(self.image.clone(), err))?.to_rgba8())
+	}
+	
+	/// Per-camera images, with the layout guessed from the frame shape.
+	pub fn camera_images(&self) -> Result<Vec<RgbaImage>, DumpError> {
+		let image = self.load_image()?;
+		let layout = FrameLayout::new(None, &FrameSize { width: image.width(), height: image.height(), frame_buffer_size: 0 });
+		
+		Ok(split_frame(&image, layout))
+	}
+}
+
+/// A folder written by `record`, `recordings/<timestamp>` with `config.json`, `camera_properties.txt`, `manifest.json` and
+/// `<number>.png`/`<number>.json` per frame and `timestamps.txt`, or a folder of dumps of a single headset.
+pub struct Recording {
+	pub path: PathBuf,
+	/// The config the frames were captured with.
+	pub config_path: PathBuf,
+	pub config: LighthouseConfig,
+	/// `Prop_CameraToHeadTransforms_Matrix34_Array` when the frames were captured, if it was saved.
+	pub cameras_to_head: Option<Vec<Isometry3<f64>>>,
+	pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+	/// Loads the frame list. Dumps that fail to load, are of another headset than the first one or were captured in
+	/// another tracking universe are skipped, since their standing poses can't be related.
+	pub fn load(path: &Path) -> Result<Recording, DumpError> {
+		let config_path = path.join("config.json");
+		
+		if !config_path.is_file() {
+			return Recording::from_dumps(path);
+		}
+		
+		let config = fs::read_to_string(&config_path).map_err(|err| DumpError::Io(config_path.clone(), err))?;
+		let config = LighthouseConfig::parse(&config)?;
+		
+		let mut images = fs::read_dir(path).map_err(|err| DumpError::Io(path.to_path_buf(), err))?
+		                                   .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		                                   .filter(|image| is_frame(image))
+		                                   .collect::<Vec<_>>();
+		images.sort();
+		
+		let times = fs::read_to_string(path.join("timestamps.txt")).map(|times| parse_timestamps(&times)).unwrap_or_default();
+		let manifest = Manifest::load(path)?;
+		let standing_from_tracking = manifest.as_ref().map_or(Isometry3::identity(), |manifest| manifest.standing_from_tracking());
+		
+		let frames = images.into_iter().map(|image| {
+			let pose = FrameHeader::load(&image.with_extension("json"))?.and_then(|header| header.pose()).map(|pose| standing_from_tracking * pose);
+			let time = image.file_stem().and_then(|stem| times.get(&*stem.to_string_lossy())).copied();
+			
+			Ok(RecordedFrame { image, pose, time })
+		}).collect::<Result<Vec<_>, DumpError>>()?;
+		
+		if frames.is_empty() {
+			return Err(DumpError::NoDumps);
+		}
+		
+		let cameras_to_head = manifest.and_then(|manifest| manifest.cameras_to_head());
+		
+		Ok(Recording { path: path.to_path_buf(), config_path, config, cameras_to_head, frames })
+	}
+	
+	fn from_dumps(path: &Path) -> Result<Recording, DumpError> {
+		let dumps = Dump::load_all(path)?.into_iter().filter_map(|(_, dump)| dump.ok()).collect::<Vec<_>>();
+		let first = dumps.first().ok_or(DumpError::NoDumps)?;
+		
+		let universe = |dump: &Dump| dump.manifest.as_ref().and_then(|manifest| manifest.universe_id);
+		
+		let frames = dumps.iter()
+		                  .filter(|dump| dump.serial() == first.serial() && universe(dump) == universe(first))
+		                  .map(|dump| RecordedFrame { image: dump.path.join("camera.png"), pose: dump.pose, time: None })
+		                  .collect();
+		
+		Ok(Recording {
+			path: path.to_path_buf(),
+			config_path: first.path.join("config.json"),
+			config: first.config.clone(),
+			cameras_to_head: first.manifest.as_ref().and_then(|manifest| manifest.cameras_to_head()),
+			frames,
+		})
+	}
+	
+	pub fn serial(&self) -> &str {
+		self.config.serial.as_deref().unwrap_or("unknown")
+	}
+}
+
+/// Whether `path` is a `<number>.png` frame, other images in the folder were made from the frames.
+fn is_frame(path: &Path) -> bool {
+	let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+	
+	path.extension().is_some_and(|extension| extension == "png") && !stem.is_empty() && stem.bytes().all(|c| c.is_ascii_digit())
+}
+
+/// `<number> <frame sequence> <milliseconds>` lines to seconds by frame number.
+fn parse_timestamps(times: &str) -> HashMap<String, f64> {
+	times.lines().filter_map(|line| {
+		let fields = line.split_whitespace().collect::<Vec<_>>();
+		Some((fields.first()?.to_string(), fields.get(2)?.parse::<f64>().ok()? / 1000.0))
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn only_numbered_images_are_frames() {
+		assert!(is_frame(Path::new("recordings/1700000000/0000.png")));
+		assert!(is_frame(Path::new("0123.png")));
+		assert!(is_frame(Path::new("12345.png")));
+		
+		assert!(!is_frame(Path::new("recordings/1700000000/panorama.png")));
+		assert!(!is_frame(Path::new("0000.json")));
+		assert!(!is_frame(Path::new("0000_left.png")));
+		assert!(!is_frame(Path::new(".png")));
+	}
+}
@@ -0,0 +1,223 @@
+use std::fmt::{self, Display, Formatter};
+use nalgebra::{Isometry3, Matrix3, Matrix3x4, Rotation3, Vector2, Vector3};
+use image::{RgbaImage, Rgba};
+
+use super::{LighthouseConfig, FisheyeCamera, PinholeCamera};
+use crate::vision::{sample_bilinear, draw_horizontal_line};
+
+/// Horizontal field of view of the rectified images, in degrees.
+pub const RECTIFIED_FOV: f64 = 90.0;
+pub const EPIPOLAR_LINE_SPACING: u32 = 32;
+pub const EPIPOLAR_LINE_COLOR: Rgba<u8> = Rgba([0, 255, 0, 128]);
+
+/// Both cameras of the headset with their extrinsics.
+#[derive(Debug, Clone)]
+pub struct StereoRig {
+	pub cameras: [FisheyeCamera; 2],
+	pub head_from_camera: [Isometry3<f64>; 2],
+}
+
+impl StereoRig {
+	/// Builds the rig from config, for camera images of `width`x`height`.
+	pub fn from_config(config: &LighthouseConfig, width: u32, height: u32) -> Option<StereoRig> {
+		let first = config.tracked_cameras.first()?;
+		let second = config.tracked_cameras.get(1)?;
+		
+		Some(StereoRig {
+			cameras: [
+				FisheyeCamera::from_config(&first.intrinsics, width, height),
+				FisheyeCamera::from_config(&second.intrinsics, width, height),
+			],
+			head_from_camera: [config.head_from_camera(0)?, config.head_from_camera(1)?],
+		})
+	}
+	
+	pub fn second_from_first(&self) -> Isometry3<f64> {
+		self.head_from_camera[1].inverse() * self.head_from_camera[0]
+	}
+}
+
+/// Rotations that make both cameras share image rows, and the pinhole camera of the rectified images.
+///
+/// The rectified frame has +X along the baseline from the first camera to the second one, so the first camera is
+/// the left one and disparity `x_left - x_right` is positive.
+#[derive(Debug, Clone)]
+pub struct Rectification {
+	pub rotations: [Rotation3<f64>; 2],
+	pub camera: PinholeCamera,
+	pub baseline: f64,
+}
+
+impl Rectification {
+	pub fn new(rig: &StereoRig, fov: f64) -> Rectification {
+		let first_from_second = rig.second_from_first().inverse();
+		let second_rotation = first_from_second.rotation.to_rotation_matrix();
+		let baseline = first_from_second.translation.vector;
+		
+		let x = baseline.normalize();
+		let forward = (Vector3::z() + second_rotation * Vector3::z()) / 2.0;
+		let z = (forward - x * x.dot(&forward)).normalize();
+		let y = z.cross(&x);
+		let rect_from_first = Rotation3::from_matrix_unchecked(Matrix3::from_rows(&[x.transpose(), y.transpose(), z.transpose()]));
+		
+		let width = rig.cameras[0].width;
+		let height = rig.cameras[0].height;
+		let focal = width as f64 / 2.0 / (fov.to_radians() / 2.0).tan();
+		
+		Rectification {
+			rotations: [rect_from_first, rect_from_first * second_rotation],
+			camera: PinholeCamera {
+				focal: Vector2::new(focal, focal),
+				center: Vector2::new(width as f64 / 2.0, height as f64 / 2.0),
+				width,
+				height,
+			},
+			baseline: baseline.norm(),
+		}
+	}
+	
+	/// Projection matrix of a rectified camera, in the rectified frame of the first camera.
+	pub fn projection(&self, index: usize) -> Matrix3x4<f64> {
+		let mut extrinsics = Matrix3x4::identity();
+		if index == 1 {
+			extrinsics[(0, 3)] = -self.baseline;
+		}
+		
+		self.camera.matrix() * extrinsics
+	}
+	
+	/// Maps a rectified pixel back into the raw camera image.
+	pub fn unrectify_point(&self, rig: &StereoRig, index: usize, pixel: &Vector2<f64>) -> Option<Vector2<f64>> {
+		let ray = self.rotations[index].inverse() * self.camera.unproject(pixel);
+		
+		rig.cameras[index].project(&ray)
+	}
+	
+	/// Marks the rectified pixels that have a source pixel in the raw camera image, row major.
+	pub fn valid_mask(&self, rig: &StereoRig, index: usize) -> Vec<bool> {
+		let mut mask = Vec::with_capacity((self.camera.width * self.camera.height) as usize);
+		
+		for y in 0..self.camera.height {
+			for x in 0..self.camera.width {
+				let pixel = self.unrectify_point(rig, index, &Vector2::new(x as f64 + 0.5, y as f64 + 0.5));
+				mask.push(pixel.is_some_and(|pixel| rig.cameras[index].contains(&pixel)));
+			}
+		}
+		
+		mask
+	}
+	
+	/// Resamples a raw camera image into the rectified frame.
+	pub fn remap(&self, rig: &StereoRig, index: usize, image: &RgbaImage) -> RgbaImage {
+		RgbaImage::from_fn(self.camera.width, self.camera.height, |x, y| {
+			self.unrectify_point(rig, index, &Vector2::new(x as f64 + 0.5, y as f64 + 0.5))
+			    .and_then(|pixel| sample_bilinear(image, pixel.x, pixel.y))
+			    .unwrap_or(Rgba([0, 0, 0, 255]))
+		})
+	}
+}
+
+impl Display for Rectification {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Baseline: {:.6} m", self.baseline)?;
+		writeln!(f, "Rectified camera: {}x{}, focal {:.3}, center {:.3} {:.3}",
+		         self.camera.width, self.camera.height, self.camera.focal.x, self.camera.center.x, self.camera.center.y)?;
+		
+		for index in 0..2 {
+			let (roll, pitch, yaw) = self.rotations[index].euler_angles();
+			
+			writeln!(f)?;
+			writeln!(f, "Camera {} rotation (roll {:.4} deg, pitch {:.4} deg, yaw {:.4} deg):{}",
+			         index, roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees(), self.rotations[index].matrix())?;
+			writeln!(f, "Camera {} projection:{}", index, self.projection(index))?;
+		}
+		
+		Ok(())
+	}
+}
+
+/// A rectified image pair, the first one being the left camera.
+pub struct RectifiedPair {
+	pub rectification: Rectification,
+	pub images: [RgbaImage; 2],
+}
+
+impl RectifiedPair {
+	/// Rectifies the per-camera images of a frame, see `vision::split_frame`.
+	pub fn new(config: &LighthouseConfig, images: &[RgbaImage]) -> Option<RectifiedPair> {
+		let (first, second) = match images {
+			[first, second] => (first, second),
+			_ => return None,
+		};
+		
+		let rig = StereoRig::from_config(config, first.width(), first.height())?;
+		let rectification = Rectification::new(&rig, RECTIFIED_FOV);
+		let images = [rectification.remap(&rig, 0, first), rectification.remap(&rig, 1, second)];
+		
+		Some(RectifiedPair { rectification, images })
+	}
+	
+	/// Copies of the rectified images with horizontal epipolar lines drawn over them.
+	pub fn with_epipolar_lines(&self) -> [RgbaImage; 2] {
+		let mut images = self.images.clone();
+		
+		for image in images.iter_mut() {
+			for y in (EPIPOLAR_LINE_SPACING / 2..image.height()).step_by(EPIPOLAR_LINE_SPACING as usize) {
+				draw_horizontal_line(image, y, EPIPOLAR_LINE_COLOR);
+			}
+		}
+		
+		images
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nalgebra::{Point3, Translation3, UnitQuaternion};
+	
+	/// Cameras 13 cm apart looking forward, the second one slightly turned and higher so rectifying has work to do.
+	fn rig() -> StereoRig {
+		let camera = FisheyeCamera {
+			focal: Vector2::new(280.0, 280.0),
+			center: Vector2::new(480.0, 480.0),
+			coeffs: [0.05, -0.01, 0.002, 0.0],
+			width: 960,
+			height: 960,
+		};
+		let forward = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f64::consts::PI);
+		let turned = UnitQuaternion::from_euler_angles(0.01, -0.02, 0.03) * forward;
+		
+		StereoRig {
+			cameras: [camera, camera],
+			head_from_camera: [
+				Isometry3::from_parts(Translation3::new(-0.065, 0.0, -0.08), forward),
+				Isometry3::from_parts(Translation3::new(0.065, 0.004, -0.082), turned),
+			],
+		}
+	}
+	
+	#[test]
+	fn points_land_on_the_same_row_at_the_baseline_disparity() {
+		let rig = rig();
+		let rectification = Rectification::new(&rig, RECTIFIED_FOV);
+		let baseline = (rig.head_from_camera[1].translation.vector - rig.head_from_camera[0].translation.vector).norm();
+		assert!((rectification.baseline - baseline).abs() < 1e-12);
+		
+		for point in [Point3::new(0.0, 0.0, -2.0), Point3::new(0.4, 0.3, -1.5), Point3::new(-0.8, -0.5, -3.0)] {
+			let rectified = [0, 1].map(|index| rectification.rotations[index] * (rig.head_from_camera[index].inverse() * point).coords);
+			let pixels = rectified.map(|point| rectification.camera.project(&point).unwrap());
+			
+			assert!((pixels[0].y - pixels[1].y).abs() < 1e-9, "rows {} and {}", pixels[0].y, pixels[1].y);
+			
+			let disparity = rectification.camera.focal.x * baseline / rectified[0].z;
+			assert!((pixels[0].x - pixels[1].x - disparity).abs() < 1e-9, "disparity {} instead of {}", pixels[0].x - pixels[1].x, disparity);
+			
+			// The projection matrices take points in the rectified frame of the first camera to both images
+			for (index, pixel) in pixels.iter().enumerate() {
+				let projected = rectification.projection(index) * rectified[0].push(1.0);
+				assert!((projected.xy() / projected.z - pixel).norm() < 1e-9);
+			}
+		}
+	}
+}
@@ -0,0 +1,347 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use nalgebra::{Isometry3, Point3, Translation3, Vector2, Vector3};
+use serde::Deserialize;
+
+use super::{Recording, DumpError, ConfigError, ExtrinsicsConfig, StereoRig, PoseDiff, percentile, solve_pnp, refine_pose, reprojection_errors};
+use crate::vision::{Dictionary, TagDetection, detect_tags, to_gray};
+
+/// Header pose offsets searched for the latency, in seconds either way.
+pub const MAX_LATENCY: f64 = 0.2;
+pub const LATENCY_STEP: f64 = 0.001;
+/// Translation error that weighs as much as a degree of rotation error when matching poses, in meters.
+pub const LATENCY_TRANSLATION_SCALE: f64 = 0.01;
+
+/// Where the tags are, `tags.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TagMap {
+	/// Tag side in meters, black border included.
+	pub size: f64,
+	/// Dictionary file, relative to the map.
+	pub dictionary: PathBuf,
+	pub tags: Vec<TagPlacement>,
+}
+
+/// Tag pose in standing tracking space, +X to the right of the tag, +Z out of its face, like the camera extrinsics.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TagPlacement {
+	pub id: u32,
+	/// Overrides the map size for this tag.
+	#[serde(default)]
+	pub size: Option<f64>,
+	#[serde(flatten)]
+	pub pose: ExtrinsicsConfig,
+}
+
+impl TagMap {
+	pub fn load(path: &Path) -> Result<TagMap, ConfigError> {
+		let mut map: TagMap = serde_json::from_str(&fs::read_to_string(path)?)?;
+		
+		if map.dictionary.is_relative() {
+			map.dictionary = path.parent().unwrap_or_else(|| Path::new("")).join(&map.dictionary);
+		}
+		
+		Ok(map)
+	}
+	
+	/// Corners of a tag in standing space, clockwise from its top left when looking at it.
+	pub fn corners(&self, id: u32) -> Option<[Point3<f64>; 4]> {
+		let tag = self.tags.iter().find(|tag| tag.id == id)?;
+		let half = tag.size.unwrap_or(self.size) / 2.0;
+		let pose = tag.pose.isometry();
+		
+		Some([
+			pose * Point3::new(-half, half, 0.0),
+			pose * Point3::new(half, half, 0.0),
+			pose * Point3::new(half, -half, 0.0),
+			pose * Point3::new(-half, -half, 0.0),
+		])
+	}
+}
+
+/// Camera pose measured from the tags in one image, next to the one the frame header gives.
+#[derive(Debug, Clone)]
+pub struct TagPose {
+	pub frame: usize,
+	pub camera: usize,
+	pub tags: Vec<u32>,
+	pub world_from_camera: Isometry3<f64>,
+	pub rms: f64,
+	/// Header pose with the config extrinsics of the camera.
+	pub predicted: Option<Isometry3<f64>>,
+	pub time: Option<f64>,
+}
+
+impl TagPose {
+	pub fn error(&self) -> Option<PoseDiff> {
+		self.predicted.map(|predicted| PoseDiff::new(&predicted, &self.world_from_camera))
+	}
+}
+
+/// Header pose offset that matches the tag poses best.
+#[derive(Debug, Clone, Copy)]
+pub struct Latency {
+	/// How much the header poses lag the images, in seconds, negative if they lead.
+	pub offset: f64,
+	/// Mean combined error without and with the offset, see `LATENCY_TRANSLATION_SCALE`.
+	pub error: f64,
+	pub best_error: f64,
+	pub poses: usize,
+}
+
+pub struct TagReport {
+	pub serial: String,
+	pub poses: Vec<TagPose>,
+	/// Images where tags were found but the pose couldn't be solved, or that failed to load.
+	pub errors: Vec<String>,
+	pub latency: Option<Latency>,
+}
+
+impl TagReport {
+	pub fn new(recording: &Recording, map: &TagMap, dictionary: &Dictionary) -> Result<TagReport, DumpError> {
+		let mut rig = None;
+		let mut poses = vec![];
+		let mut errors = vec![];
+		
+		for (frame, recorded) in recording.frames.iter().enumerate() {
+			let images = match recorded.camera_images() {
+				Ok(images) => images,
+				Err(err) => { errors.push(format!("Frame {}: {}", frame, err)); continue },
+			};
+			
+			if rig.is_none() {
+				rig = Some(StereoRig::from_config(&recording.config, images[0].width(), images[0].height()).ok_or(DumpError::NotStereo)?);
+			}
+			let rig = rig.as_ref().unwrap();
+			
+			for (camera, image) in images.iter().enumerate().take(2) {
+				let detections = detect_tags(&to_gray(image), dictionary).into_iter().filter(|tag| map.corners(tag.id).is_some()).collect::<Vec<_>>();
+				if detections.is_empty() {
+					continue;
+				}
+				
+				let camera_from_world = match solve_tags(rig, camera, map, &detections) {
+					Some(pose) => pose,
+					None => { errors.push(format!("Frame {} camera {}: no pose from tags {:?}", frame, camera, detections.iter().map(|tag| tag.id).collect::<Vec<_>>())); continue },
+				};
+				
+				let (object, pixels) = tag_points(map, &detections);
+				let residuals = reprojection_errors(&rig.cameras[camera], &camera_from_world, &object, &pixels);
+				let residuals = residuals.iter().flatten().collect::<Vec<_>>();
+				
+				poses.push(TagPose {
+					frame,
+					camera,
+					tags: detections.iter().map(|tag| tag.id).collect(),
+					world_from_camera: camera_from_world.inverse(),
+					rms: (residuals.iter().map(|r| r.norm_squared()).sum::<f64>() / residuals.len().max(1) as f64).sqrt(),
+					predicted: recorded.pose.map(|world_from_head| world_from_head * rig.head_from_camera[camera]),
+					time: recorded.time,
+				});
+			}
+		}
+		
+		let latency = rig.as_ref().and_then(|rig| latency(recording, rig, &poses));
+		
+		Ok(TagReport { serial: recording.serial().to_string(), poses, errors, latency })
+	}
+}
+
+fn tag_points(map: &TagMap, detections: &[TagDetection]) -> (Vec<Point3<f64>>, Vec<Vector2<f64>>) {
+	detections.iter().filter_map(|tag| Some(map.corners(tag.id)?.iter().copied().zip(tag.corners.iter().map(|&pixel| Vector2::from(pixel))).collect::<Vec<_>>()))
+	          .flatten()
+	          .unzip()
+}
+
+/// Starts from the largest tag alone, then refines on the corners of all of them.
+fn solve_tags(rig: &StereoRig, camera: usize, map: &TagMap, detections: &[TagDetection]) -> Option<Isometry3<f64>> {
+	let area = |tag: &TagDetection| {
+		let c = &tag.corners;
+		((c[2][0] - c[0][0]) * (c[3][1] - c[1][1]) - (c[3][0] - c[1][0]) * (c[2][1] - c[0][1])).abs() / 2.0
+	};
+	let largest = detections.iter().max_by(|a, b| area(a).total_cmp(&area(b)))?;
+	let (object, pixels) = tag_points(map, std::slice::from_ref(largest));
+	let initial = solve_pnp(&rig.cameras[camera], &object, &pixels)?;
+	
+	let (object, pixels) = tag_points(map, detections);
+	refine_pose(&rig.cameras[camera], &initial, &object, &pixels)
+}
+
+/// Searches the header pose offset that fits the tag poses best, interpolating the header trajectory.
+fn latency(recording: &Recording, rig: &StereoRig, poses: &[TagPose]) -> Option<Latency> {
+	let mut trajectory = recording.frames.iter().filter_map(|frame| Some((frame.time?, frame.pose?))).collect::<Vec<_>>();
+	trajectory.sort_by(|a, b| a.0.total_cmp(&b.0));
+	
+	let head_at = |time: f64| -> Option<Isometry3<f64>> {
+		let next = trajectory.iter().position(|&(sample, _)| sample >= time)?;
+		if next == 0 {
+			return if (trajectory[0].0 - time).abs() < 1e-9 { Some(trajectory[0].1) } else { None };
+		}
+		
+		let ((t0, a), (t1, b)) = (trajectory[next - 1], trajectory[next]);
+		let s = (time - t0) / (t1 - t0).max(1e-9);
+		
+		Some(Isometry3::from_parts(Translation3::from(a.translation.vector.lerp(&b.translation.vector, s)), a.rotation.slerp(&b.rotation, s)))
+	};
+	
+	let error_at = |offset: f64| -> (f64, usize) {
+		let errors = poses.iter().filter_map(|pose| {
+			let predicted = head_at(pose.time? + offset)? * rig.head_from_camera[pose.camera];
+			let diff = PoseDiff::new(&predicted, &pose.world_from_camera);
+			
+			Some(diff.angle + diff.translation.norm() / LATENCY_TRANSLATION_SCALE)
+		}).collect::<Vec<_>>();
+		
+		(errors.iter().sum::<f64>() / errors.len().max(1) as f64, errors.len())
+	};
+	
+	let (error, count) = error_at(0.0);
+	if count < 3 {
+		return None;
+	}
+	
+	let steps = (MAX_LATENCY / LATENCY_STEP).round() as i32;
+	let (offset, best_error) = (-steps..=steps).map(|step| step as f64 * LATENCY_STEP)
+	                                          .map(|offset| (offset, error_at(offset)))
+	                                          .filter(|(_, (_, used))| *used * 5 >= count * 4)
+	                                          .map(|(offset, (error, _))| (offset, error))
+	                                          .min_by(|a, b| a.1.total_cmp(&b.1))?;
+	
+	Some(Latency { offset, error, best_error, poses: count })
+}
+
+impl Display for TagReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Headset {}: camera poses from tags in {} images", self.serial, self.poses.len())?;
+		writeln!(f)?;
+		
+		for pose in &self.poses {
+			let t = pose.world_from_camera.translation.vector;
+			write!(f, "Frame {} camera {}: tags {:?}, RMS {:.3} px, position {:+.4} {:+.4} {:+.4} m",
+			       pose.frame, pose.camera, pose.tags, pose.rms, t.x, t.y, t.z)?;
+			
+			match pose.error() {
+				Some(error) => writeln!(f, ", header {:.1} mm, {:.3} deg off", error.translation.norm() * 1000.0, error.angle)?,
+				None => writeln!(f, ", no valid header pose")?,
+			}
+		}
+		
+		for error in &self.errors {
+			writeln!(f, "{}", error)?;
+		}
+		
+		let errors = self.poses.iter().filter_map(TagPose::error).collect::<Vec<_>>();
+		if !errors.is_empty() {
+			let translations = errors.iter().map(|error| error.translation.norm() * 1000.0).collect::<Vec<_>>();
+			let angles = errors.iter().map(|error| error.angle).collect::<Vec<_>>();
+			let mean = errors.iter().map(|error| error.translation).sum::<Vector3<f64>>() / errors.len() as f64 * 1000.0;
+			
+			writeln!(f)?;
+			writeln!(f, "Header pose error over {} images:", errors.len())?;
+			writeln!(f, "  Translation: median {:.2} mm, 90th percentile {:.2} mm, mean offset {:+.2} {:+.2} {:+.2} mm",
+			         percentile(&translations, 0.5), percentile(&translations, 0.9), mean.x, mean.y, mean.z)?;
+			writeln!(f, "  Rotation: median {:.3} deg, 90th percentile {:.3} deg", percentile(&angles, 0.5), percentile(&angles, 0.9))?;
+		}
+		
+		writeln!(f)?;
+		match &self.latency {
+			Some(latency) => writeln!(f, "Latency: header poses {} the images by {:.0} ms, error {:.3} -> {:.3} over {} images",
+			                          if latency.offset >= 0.0 { "lag" } else { "lead" }, latency.offset.abs() * 1000.0,
+			                          latency.error, latency.best_error, latency.poses),
+			None => writeln!(f, "Latency: needs at least 3 tag poses in frames with timestamps and header poses"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use nalgebra::UnitQuaternion;
+	use crate::calibration::{FisheyeCamera, LighthouseConfig, RecordedFrame};
+	
+	fn rig() -> StereoRig {
+		let camera = FisheyeCamera {
+			focal: Vector2::new(280.0, 280.0),
+			center: Vector2::new(480.0, 480.0),
+			coeffs: [0.05, -0.01, 0.002, 0.0],
+			width: 960,
+			height: 960,
+		};
+		let forward = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f64::consts::PI);
+		
+		StereoRig {
+			cameras: [camera, camera],
+			head_from_camera: [
+				Isometry3::from_parts(Translation3::new(-0.065, 0.0, -0.08), forward),
+				Isometry3::from_parts(Translation3::new(0.065, 0.0, -0.08), forward),
+			],
+		}
+	}
+	
+	/// Moving at a constant speed and turning at a constant rate, which interpolating the samples gives back exactly.
+	fn head_at(time: f64) -> Isometry3<f64> {
+		Isometry3::from_parts(Translation3::new(0.3 * time, 1.6, -0.1 * time), UnitQuaternion::from_euler_angles(0.1 * time, 0.8 * time, 0.0))
+	}
+	
+	#[test]
+	fn tag_corners_match_the_detection_order() {
+		let map = TagMap {
+			size: 0.2,
+			dictionary: PathBuf::new(),
+			tags: vec![TagPlacement { id: 3, size: None, pose: ExtrinsicsConfig::from_isometry(&Isometry3::translation(0.0, 1.5, -2.0)) }],
+		};
+		
+		// Seen from the front, the camera looks down -Z of the standing space like the tag faces +Z
+		let camera_from_world = Isometry3::rotation(Vector3::x() * std::f64::consts::PI) * Isometry3::translation(0.0, -1.5, 0.0);
+		let corners = map.corners(3).unwrap();
+		let pixels = corners.iter().map(|corner| rig().cameras[0].project(&(camera_from_world * corner).coords).unwrap()).collect::<Vec<_>>();
+		
+		// Clockwise from the top left in the image
+		assert!(pixels[0].x < pixels[1].x && pixels[0].y < pixels[3].y);
+		assert!(pixels[2].x > pixels[3].x && pixels[2].y > pixels[1].y);
+		
+		let detection = TagDetection { id: 3, corners: [0, 1, 2, 3].map(|k| [pixels[k].x, pixels[k].y]) };
+		let solved = solve_tags(&rig(), 0, &map, &[detection]).unwrap();
+		assert!(PoseDiff::new(&camera_from_world, &solved).translation.norm() < 1e-6);
+	}
+	
+	#[test]
+	fn finds_a_known_latency() {
+		let rig = rig();
+		let lag = 0.05;
+		
+		// Header poses at 100 Hz, tag poses of images taken `lag` later than their header says
+		let frames = (0..=200).map(|sample| {
+			let time = sample as f64 * 0.01;
+			RecordedFrame { image: PathBuf::new(), pose: Some(head_at(time)), time: Some(time) }
+		}).collect();
+		let poses = (0..24).map(|index| {
+			let time = 0.3 + index as f64 * 0.05;
+			let camera = index % 2;
+			
+			TagPose {
+				frame: index,
+				camera,
+				tags: vec![0],
+				world_from_camera: head_at(time + lag) * rig.head_from_camera[camera],
+				rms: 0.0,
+				predicted: None,
+				time: Some(time),
+			}
+		}).collect::<Vec<_>>();
+		
+		let recording = Recording {
+			path: PathBuf::new(),
+			config_path: PathBuf::new(),
+			config: LighthouseConfig { serial: None, tracked_cameras: vec![], head: None },
+			cameras_to_head: None,
+			frames,
+		};
+		
+		let latency = latency(&recording, &rig, &poses).unwrap();
+		assert!((latency.offset - lag).abs() <= LATENCY_STEP / 2.0, "offset {}", latency.offset);
+		assert!(latency.best_error < 1e-6 && latency.error > 1.0, "{:?}", latency);
+		assert_eq!(latency.poses, 24);
+	}
+}
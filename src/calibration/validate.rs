@@ -0,0 +1,264 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{LighthouseConfig, TrackedCameraConfig};
+use crate::openvr_load::TrackedDeviceIndex;
+use crate::system::{CameraProperties, DistortionFunction};
+use crate::tracked_camera::{TrackedCamera, FrameType, FrameLayout, FrameSize};
+
+/// Maximum focal length and principal point difference, in pixels.
+pub const PIXEL_TOLERANCE: f64 = 0.5;
+/// Maximum difference between distortion coefficients. The properties are only single precision.
+pub const COEFFICIENT_TOLERANCE: f64 = 1e-4;
+
+pub const FRAME_TYPES: [FrameType; 3] = [FrameType::Distorted, FrameType::Undistorted, FrameType::MaximumUndistorted];
+
+#[derive(Debug)]
+pub struct FrameTypeIntrinsics {
+	pub frame_type: FrameType,
+	pub camera_size: (u32, u32),
+	pub focal: [f64; 2],
+	pub center: [f64; 2],
+}
+
+#[derive(Debug)]
+pub struct CameraValidation {
+	pub index: u32,
+	pub name: String,
+	/// Config intrinsics scaled to the Distorted frame resolution.
+	pub expected_focal: [f64; 2],
+	pub expected_center: [f64; 2],
+	pub measured: Vec<FrameTypeIntrinsics>,
+	pub focal_delta: Option<[f64; 2]>,
+	pub center_delta: Option<[f64; 2]>,
+	pub config_function: DistortionFunction,
+	pub reported_function: Option<DistortionFunction>,
+	pub coefficient_delta: Option<Vec<f64>>,
+	pub errors: Vec<String>,
+}
+
+impl CameraValidation {
+	/// Compares the config of a camera with what OpenVR reports for it, the Distorted frame being `camera_size` per camera.
+	pub fn new(
+		index: u32,
+		camera_config: &TrackedCameraConfig,
+		camera_size: (u32, u32),
+		measured: Vec<FrameTypeIntrinsics>,
+		reported_function: Option<DistortionFunction>,
+		reported_coefficients: Option<&[f32]>,
+		errors: Vec<String>,
+	) -> CameraValidation {
+		let intrinsics = &camera_config.intrinsics;
+		let scale = [camera_size.0 as f64 / intrinsics.width as f64, camera_size.1 as f64 / intrinsics.height as f64];
+		let expected_focal = [intrinsics.focal_x * scale[0], intrinsics.focal_y * scale[1]];
+		let expected_center = [intrinsics.center_x * scale[0], intrinsics.center_y * scale[1]];
+		
+		let distorted = measured.iter().find(|m| m.frame_type == FrameType::Distorted);
+		let focal_delta = distorted.map(|m| [m.focal[0] - expected_focal[0], m.focal[1] - expected_focal[1]]);
+		let center_delta = distorted.map(|m| [m.center[0] - expected_center[0], m.center[1] - expected_center[1]]);
+		
+		let coefficient_delta = reported_coefficients.map(|reported| {
+			intrinsics.distort.coeffs.iter()
+			          .zip(reported.iter())
+			          .map(|(expected, &reported)| reported as f64 - expected)
+			          .collect()
+		});
+		
+		CameraValidation {
+			index,
+			name: camera_config.name.clone(),
+			expected_focal,
+			expected_center,
+			measured,
+			focal_delta,
+			center_delta,
+			config_function: intrinsics.distort.function(),
+			reported_function,
+			coefficient_delta,
+			errors,
+		}
+	}
+	
+	pub fn passed(&self) -> bool {
+		let pixels_ok = |delta: &Option<[f64; 2]>| delta.is_some_and(|d| d.iter().all(|d| d.abs() <= PIXEL_TOLERANCE));
+		let coeffs_ok = self.coefficient_delta.as_ref().is_some_and(|d| d.iter().all(|d| d.abs() <= COEFFICIENT_TOLERANCE));
+		
+		pixels_ok(&self.focal_delta)
+			&& pixels_ok(&self.center_delta)
+			&& coeffs_ok
+			&& self.reported_function == Some(self.config_function)
+	}
+	
+	pub fn frame_type(&self, frame_type: FrameType) -> Option<&FrameTypeIntrinsics> {
+		self.measured.iter().find(|m| m.frame_type == frame_type)
+	}
+}
+
+#[derive(Debug)]
+pub struct ValidationReport {
+	pub layout: FrameLayout,
+	pub cameras: Vec<CameraValidation>,
+}
+
+impl ValidationReport {
+	pub fn new(
+		tracked_camera: &TrackedCamera,
+		device: TrackedDeviceIndex,
+		properties: &CameraProperties,
+		config: &LighthouseConfig,
+	) -> ValidationReport {
+		let (layout, distorted_size) = frame_layout(tracked_camera, device, properties);
+		
+		let cameras = config.tracked_cameras.iter().enumerate().map(|(index, camera_config)| {
+			let index = index as u32;
+			let mut errors = vec![];
+			let mut measured = vec![];
+			
+			for &frame_type in FRAME_TYPES.iter() {
+				let frame_size = match tracked_camera.get_camera_frame_size(device, frame_type) {
+					Ok(frame_size) => frame_size,
+					Err(err) => { errors.push(format!("{:?} frame size: {}", frame_type, err)); continue; },
+				};
+				
+				match tracked_camera.get_camera_intrinsics(device, index, frame_type) {
+					Ok(intrinsics) => measured.push(FrameTypeIntrinsics {
+						frame_type,
+						camera_size: frame_size.camera_size(layout),
+						focal: [intrinsics.focal_length[0] as f64, intrinsics.focal_length[1] as f64],
+						center: [intrinsics.center[0] as f64, intrinsics.center[1] as f64],
+					}),
+					Err(err) => errors.push(format!("{:?} intrinsics: {}", frame_type, err)),
+				}
+			}
+			
+			let reported_function = match &properties.distortion_functions {
+				Ok(functions) => functions.get(index as usize).copied(),
+				Err(err) => { errors.push(format!("Distortion function: {}", err)); None },
+			};
+			
+			let reported_coefficients = match &properties.distortion_coefficients {
+				Ok(coefficients) => coefficients.get(index as usize).map(|coefficients| &coefficients[..]),
+				Err(err) => { errors.push(format!("Distortion coefficients: {}", err)); None },
+			};
+			
+			CameraValidation::new(index, camera_config, distorted_size.camera_size(layout), measured,
+			                      reported_function, reported_coefficients, errors)
+		}).collect();
+		
+		ValidationReport { layout, cameras }
+	}
+	
+	pub fn passed(&self) -> bool {
+		self.cameras.iter().all(CameraValidation::passed)
+	}
+}
+
+/// Layout of the camera frame, together with the Distorted frame size it was derived from.
+pub fn frame_layout(tracked_camera: &TrackedCamera, device: TrackedDeviceIndex, properties: &CameraProperties) -> (FrameLayout, FrameSize) {
+	let distorted_size = tracked_camera.get_camera_frame_size(device, FrameType::Distorted).unwrap_or_default();
+	
+	(FrameLayout::new(properties.frame_layout.as_ref().ok().copied(), &distorted_size), distorted_size)
+}
+
+fn verdict(passed: bool) -> &'static str {
+	if passed { "PASS" } else { "FAIL" }
+}
+
+impl Display for ValidationReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Frame layout: {:?}", self.layout)?;
+		writeln!(f, "Tolerances: {} px, {} for distortion coefficients", PIXEL_TOLERANCE, COEFFICIENT_TOLERANCE)?;
+		
+		for camera in &self.cameras {
+			writeln!(f)?;
+			writeln!(f, "Camera {} ({}): {}", camera.index, camera.name, verdict(camera.passed()))?;
+			
+			for measured in &camera.measured {
+				writeln!(f, "  {:?} {}x{}: focal {:.3} {:.3}, center {:.3} {:.3}",
+				         measured.frame_type, measured.camera_size.0, measured.camera_size.1,
+				         measured.focal[0], measured.focal[1], measured.center[0], measured.center[1])?;
+			}
+			
+			writeln!(f, "  Config scaled to Distorted: focal {:.3} {:.3}, center {:.3} {:.3}",
+			         camera.expected_focal[0], camera.expected_focal[1], camera.expected_center[0], camera.expected_center[1])?;
+			
+			match camera.focal_delta {
+				Some(delta) => writeln!(f, "  Focal delta: {:+.3} {:+.3} px", delta[0], delta[1])?,
+				None => writeln!(f, "  Focal delta: N/A")?,
+			}
+			
+			match camera.center_delta {
+				Some(delta) => writeln!(f, "  Principal point delta: {:+.3} {:+.3} px", delta[0], delta[1])?,
+				None => writeln!(f, "  Principal point delta: N/A")?,
+			}
+			
+			writeln!(f, "  Distortion function: config {:?}, reported {:?}", camera.config_function, camera.reported_function)?;
+			
+			match &camera.coefficient_delta {
+				Some(delta) => writeln!(f, "  Distortion coefficient delta: {}", delta.iter().map(|d| format!("{:+.2e}", d)).collect::<Vec<_>>().join(" "))?,
+				None => writeln!(f, "  Distortion coefficient delta: N/A")?,
+			}
+			
+			if let (Some(distorted), Some(maximum)) = (camera.frame_type(FrameType::Distorted), camera.frame_type(FrameType::MaximumUndistorted)) {
+				let resolution_scale = maximum.camera_size.0 as f64 / distorted.camera_size.0 as f64;
+				let focal_scale = maximum.focal[0] / distorted.focal[0];
+				let half_fov = |m: &FrameTypeIntrinsics| (m.camera_size.0 as f64 / 2.0 / m.focal[0]).atan().to_degrees();
+				
+				writeln!(f, "  MaximumUndistorted vs Distorted: resolution x{:.3}, focal length x{:.3}.", resolution_scale, focal_scale)?;
+				writeln!(f, "    The optical center keeps x{:.3} of the sensor pixel density, the remaining x{:.3} of tangent extent", focal_scale, resolution_scale / focal_scale)?;
+				writeln!(f, "    holds the periphery stretched by the rectilinear projection, out to {:.1} deg from the center horizontally.", half_fov(maximum))?;
+			}
+			
+			for error in &camera.errors {
+				writeln!(f, "  Error: {}", error)?;
+			}
+		}
+		
+		writeln!(f)?;
+		writeln!(f, "Verdict: {}", verdict(self.passed()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+	
+	/// A 640x480 config camera, reported by OpenVR at twice the resolution with the principal point moved by `shift` pixels.
+	fn validate(shift: f64) -> CameraValidation {
+		let camera: TrackedCameraConfig = serde_json::from_value(json!({
+			"name": "left",
+			"extrinsics": { "plus_x": [1.0, 0.0, 0.0], "plus_z": [0.0, 0.0, 1.0], "position": [-0.05, 0.0, 0.0] },
+			"intrinsics": {
+				"center_x": 320.0, "center_y": 240.0, "focal_x": 300.0, "focal_y": 300.0, "width": 640, "height": 480,
+				"distort": { "coeffs": [0.1, 0.01, 0.0, 0.0], "type": "DISTORT_FTHETA" },
+			},
+		})).unwrap();
+		
+		let measured = FRAME_TYPES.iter().map(|&frame_type| FrameTypeIntrinsics {
+			frame_type,
+			camera_size: (1280, 960),
+			focal: [600.2, 599.7],
+			center: [640.3 + shift, 479.8],
+		}).collect();
+		
+		CameraValidation::new(0, &camera, (1280, 960), measured, Some(DistortionFunction::FTheta), Some(&[0.1, 0.01, 0.0, 0.0]), vec![])
+	}
+	
+	#[test]
+	fn passes_within_the_pixel_tolerance() {
+		let camera = validate(0.0);
+		
+		assert_eq!(camera.expected_focal, [600.0, 600.0]);
+		assert_eq!(camera.expected_center, [640.0, 480.0]);
+		assert!(camera.center_delta.unwrap().iter().all(|delta| delta.abs() < PIXEL_TOLERANCE));
+		assert!(camera.passed());
+	}
+	
+	#[test]
+	fn fails_on_a_shifted_principal_point() {
+		let camera = validate(PIXEL_TOLERANCE);
+		
+		assert!(camera.center_delta.unwrap()[0] > PIXEL_TOLERANCE);
+		assert!(!camera.passed());
+	}
+}
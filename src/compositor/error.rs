@@ -1,44 +1,42 @@
 use std::{error, fmt};
 use openvr_sys as sys;
 
+use crate::openvr_errors::COMPOSITOR_ERRORS;
+
 pub struct CompositorError {
-	pub code: sys::EVRScreenshotError,
-	pub name: String,
+	pub code: sys::EVRCompositorError,
+}
+
+impl CompositorError {
+	/// What the user can do about the common failures.
+	pub fn hint(&self) -> Option<&'static str> {
+		match self.code {
+			sys::EVRCompositorError_VRCompositorError_SharedTexturesNotSupported |
+			sys::EVRCompositorError_VRCompositorError_TextureIsOnWrongDevice => Some("Update the graphics driver, and run SteamVR on the same GPU"),
+			_ => None,
+		}
+	}
 }
 
 impl error::Error for CompositorError {}
 
 impl fmt::Debug for CompositorError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&format!("{}({})", self.name, self.code))
+		f.pad(&COMPOSITOR_ERRORS.symbol(self.code as i64))
 	}
 }
 
 impl fmt::Display for CompositorError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&self.name)
+		f.pad(&format!("{} ({})", COMPOSITOR_ERRORS.description(self.code as i64), COMPOSITOR_ERRORS.symbol(self.code as i64)))
 	}
 }
 
+#[cfg(windows)]
 pub fn check_err(code: sys::EVRCompositorError) -> Result<(), CompositorError> {
 	if code == sys::EVRCompositorError_VRCompositorError_None {
 		Ok(())
 	} else {
-		let name = match code {
-			sys::EVRCompositorError_VRCompositorError_RequestFailed => "VRCompositorError_RequestFailed",
-			sys::EVRCompositorError_VRCompositorError_IncompatibleVersion => "VRCompositorError_IncompatibleVersion",
-			sys::EVRCompositorError_VRCompositorError_DoNotHaveFocus => "VRCompositorError_DoNotHaveFocus",
-			sys::EVRCompositorError_VRCompositorError_InvalidTexture => "VRCompositorError_InvalidTexture",
-			sys::EVRCompositorError_VRCompositorError_IsNotSceneApplication => "VRCompositorError_IsNotSceneApplication",
-			sys::EVRCompositorError_VRCompositorError_TextureIsOnWrongDevice => "VRCompositorError_TextureIsOnWrongDevice",
-			sys::EVRCompositorError_VRCompositorError_TextureUsesUnsupportedFormat => "VRCompositorError_TextureUsesUnsupportedFormat",
-			sys::EVRCompositorError_VRCompositorError_SharedTexturesNotSupported => "VRCompositorError_SharedTexturesNotSupported",
-			sys::EVRCompositorError_VRCompositorError_IndexOutOfRange => "VRCompositorError_IndexOutOfRange",
-			sys::EVRCompositorError_VRCompositorError_AlreadySubmitted => "VRCompositorError_AlreadySubmitted",
-			sys::EVRCompositorError_VRCompositorError_InvalidBounds => "VRCompositorError_InvalidBounds",
-			_ => "VRCompositorError_UnknownError",
-		}.into();
-		
-		Err(CompositorError{ code, name })
+		Err(CompositorError{ code })
 	}
 }
@@ -1,30 +1,51 @@
-use std::marker::PhantomData;
+#[cfg(windows)]
 use std::ptr;
+#[cfg(windows)]
 use std::ops::DerefMut;
 use openvr_sys as sys;
+#[cfg(windows)]
 use winapi::um::d3d11::{ID3D11ShaderResourceView, ID3D11Device};
 
 mod error;
+// The mirror textures are only read through DirectX
+#[cfg(windows)]
 mod utils;
 
 pub use error::*;
+#[cfg(windows)]
 pub use utils::*;
-use crate::openvr_load::{load, InitError, Context};
+use crate::openvr_load::{InitError, Context};
+#[cfg(windows)]
 use crate::directx::D3DContext;
 
-pub type FnTable = &'static sys::VR_IVRCompositor_FnTable;
+/// Only valid while the session is, which the `Context` held next to it ensures.
+type FnTable = &'static sys::VR_IVRCompositor_FnTable;
 
-#[derive(Copy, Clone)]
-pub struct Compositor<'a>(FnTable, PhantomData<&'a Context>);
+/// Interface versions to try, the one `openvr_sys` was built with first.
+pub const VERSIONS: &[&[u8]] = &[
+	sys::IVRCompositor_Version,
+	// 022 only appended IsMotionSmoothingEnabled, IsMotionSmoothingSupported and IsCurrentSceneFocusAppLoading
+	b"IVRCompositor_021\0",
+];
 
-impl<'a> Compositor<'a> {
-	pub fn new(_context: &Context) -> Result<Compositor, InitError> {
-		let fn_tab: FnTable = unsafe { &*load(sys::IVRCompositor_Version)? };
+#[derive(Clone)]
+pub struct Compositor(#[cfg_attr(not(windows), allow(dead_code))] FnTable, #[allow(dead_code)] Context, &'static str);
+
+impl Compositor {
+	pub fn new(context: &Context) -> Result<Compositor, InitError> {
+		let (fn_tab, version) = context.load::<sys::VR_IVRCompositor_FnTable>(VERSIONS)?;
+		let fn_tab: FnTable = unsafe { &*(fn_tab as *const _) };
 		
-		Ok(Compositor(fn_tab, PhantomData))
+		Ok(Compositor(fn_tab, context.clone(), version))
+	}
+	
+	/// The interface version the runtime bound.
+	pub fn version(&self) -> &'static str {
+		self.2
 	}
 	
-	pub fn get_mirror_texture_d3d11<'c, 'd>(&'c self, eye: VREye, d3d: &'d D3DContext) -> Result<MirrorTexture<'c, 'd>, CompositorError> {
+	#[cfg(windows)]
+	pub fn get_mirror_texture_d3d11<'d>(&self, eye: VREye, d3d: &'d D3DContext) -> Result<MirrorTexture<'d>, CompositorError> {
 		let mut resource_view = ptr::null_mut();
 		
 		check_err(unsafe {
@@ -40,6 +61,7 @@ impl<'a> Compositor<'a> {
 		}
 	}
 	
+	#[cfg(windows)]
 	unsafe fn release_mirror_texture_d3d11(&self, resource_view: &mut ID3D11ShaderResourceView) {
 		self.0.ReleaseMirrorTextureD3D11.unwrap()(resource_view as *mut _ as *mut _)
 	}
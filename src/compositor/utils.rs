@@ -13,25 +13,25 @@ pub enum VREye {
 	Right,
 }
 
-impl Into<EVREye> for VREye {
-	fn into(self) -> u32 {
-		match self {
+impl From<VREye> for EVREye {
+	fn from(eye: VREye) -> Self {
+		match eye {
 			VREye::Left => EVREye_Eye_Left,
 			VREye::Right => EVREye_Eye_Right,
 		}
 	}
 }
 
-pub struct MirrorTexture<'c, 'd> {
-	compositor: Compositor<'c>,
+pub struct MirrorTexture<'d> {
+	compositor: Compositor,
 	d3d: &'d D3DContext,
 	resource_view: &'static mut ID3D11ShaderResourceView,
 	resource: &'static mut ID3D11Resource,
 	texture: &'static mut ID3D11Texture2D,
 }
 
-impl<'c, 'd> MirrorTexture<'c, 'd> {
-	pub unsafe fn new(compositor: Compositor<'c>, d3d: &'d D3DContext, resource_view: &'static mut ID3D11ShaderResourceView) -> MirrorTexture<'c, 'd> {
+impl<'d> MirrorTexture<'d> {
+	pub unsafe fn new(compositor: Compositor, d3d: &'d D3DContext, resource_view: &'static mut ID3D11ShaderResourceView) -> MirrorTexture<'d> {
 		let mut resource = ptr::null_mut();
 		resource_view.GetResource(&mut resource);
 		let resource = resource.as_mut().expect("GetResource failed");
@@ -104,7 +104,7 @@ impl<'c, 'd> MirrorTexture<'c, 'd> {
 	}
 }
 
-impl<'c, 'd> Drop for MirrorTexture<'c, 'd> {
+impl<'d> Drop for MirrorTexture<'d> {
 	fn drop(&mut self) {
 		unsafe {
 			self.texture.Release();
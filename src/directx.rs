@@ -68,7 +68,7 @@ pub struct D3DError(HRESULT);
 
 impl Display for D3DError {
 	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-		writeln!(f, "DirectX error: {}", self.0)
+		write!(f, "DirectX error: {:#010x}", self.0)
 	}
 }
 
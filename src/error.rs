@@ -0,0 +1,199 @@
+use std::{error, fmt, io};
+use std::num::{ParseFloatError, ParseIntError};
+use std::time::SystemTimeError;
+
+use crate::openvr_load::{InitError, TrackedDeviceIndex};
+use crate::tracked_camera::{TrackedCameraError, FrameError};
+use crate::system::TrackedPropertyError;
+use crate::settings::SettingsError;
+use crate::compositor::CompositorError;
+#[cfg(windows)]
+use crate::directx::D3DError;
+use crate::calibration::{ConfigError, DumpError};
+use crate::vision::BoardError;
+use crate::startup::WaitError;
+
+/// Any error of the program, what `main` reports.
+#[derive(Debug)]
+pub enum Error {
+	Init(InitError),
+	TrackedCamera(TrackedCameraError),
+	Frame(FrameError),
+	TrackedProperty(TrackedPropertyError),
+	Settings(SettingsError),
+	Compositor(CompositorError),
+	#[cfg(windows)]
+	D3D(D3DError),
+	Wait(WaitError),
+	Config(ConfigError),
+	Dump(DumpError),
+	Board(BoardError),
+	Io(io::Error),
+	Image(image::ImageError),
+	Json(serde_json::Error),
+	/// Bad command line arguments.
+	Usage(String),
+	Other(String),
+	/// What was being done, and to which tracked device, when `source` happened.
+	Context { operation: String, device: Option<TrackedDeviceIndex>, source: Box<Error> },
+}
+
+impl Error {
+	pub fn usage(text: impl Into<String>) -> Error {
+		Error::Usage(text.into())
+	}
+	
+	pub fn other(text: impl Into<String>) -> Error {
+		Error::Other(text.into())
+	}
+	
+	/// Process exit code. These don't change between versions, scripts may rely on them.
+	pub fn exit_code(&self) -> i32 {
+		match self {
+			Error::Other(_) => 1,
+			Error::Usage(_) => 2,
+			Error::Io(_) => 3,
+			Error::Image(_) => 4,
+			Error::Json(_) => 5,
+			Error::Config(_) => 6,
+			Error::Dump(_) => 7,
+			Error::Board(_) => 8,
+			Error::Init(_) => 10,
+			Error::TrackedCamera(_) => 11,
+			Error::Frame(FrameError::Camera(_)) => 11,
+			Error::TrackedProperty(_) => 12,
+			Error::Settings(_) => 13,
+			Error::Compositor(_) => 14,
+			#[cfg(windows)]
+			Error::D3D(_) => 15,
+			Error::Wait(WaitError::Init(_)) => 10,
+			Error::Wait(WaitError::TimedOut { .. }) => 16,
+			Error::Frame(_) => 17,
+			Error::Context { source, .. } => source.exit_code(),
+		}
+	}
+	
+	/// What the user can do about it, if we know.
+	pub fn hint(&self) -> Option<&'static str> {
+		match self {
+			Error::Init(err) => err.hint(),
+			Error::TrackedCamera(err) => err.hint(),
+			Error::Frame(err) => err.hint(),
+			Error::TrackedProperty(err) => err.hint(),
+			Error::Settings(err) => err.hint(),
+			Error::Compositor(err) => err.hint(),
+			#[cfg(windows)]
+			Error::D3D(_) => Some("Update the graphics driver, and check that SteamVR runs on the same GPU"),
+			Error::Wait(WaitError::Init(err)) => err.hint(),
+			Error::Wait(WaitError::TimedOut { .. }) => Some("Start SteamVR, connect the headset and enable the camera in the SteamVR settings"),
+			Error::Dump(DumpError::NoDumps) => Some("Take some dumps first with `vrcamdump.exe dump` or `vrcamdump.exe record`"),
+			Error::Dump(DumpError::NoPose) => Some("Keep the headset tracked by the base stations while capturing"),
+			Error::Context { source, .. } => source.hint(),
+			_ => None,
+		}
+	}
+}
+
+impl error::Error for Error {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		// Wrapped errors are displayed as they are, so the chain goes on with their own source
+		match self {
+			Error::Init(err) => err.source(),
+			Error::TrackedCamera(err) => err.source(),
+			Error::Frame(err) => err.source(),
+			Error::TrackedProperty(err) => err.source(),
+			Error::Settings(err) => err.source(),
+			Error::Compositor(err) => err.source(),
+			#[cfg(windows)]
+			Error::D3D(err) => err.source(),
+			Error::Wait(err) => err.source(),
+			Error::Config(err) => err.source(),
+			Error::Dump(err) => err.source(),
+			Error::Board(err) => err.source(),
+			Error::Io(err) => err.source(),
+			Error::Image(err) => err.source(),
+			Error::Json(err) => err.source(),
+			Error::Usage(_) | Error::Other(_) => None,
+			Error::Context { source, .. } => Some(source.as_ref()),
+		}
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Init(err) => err.fmt(f),
+			Error::TrackedCamera(err) => err.fmt(f),
+			Error::Frame(err) => err.fmt(f),
+			Error::TrackedProperty(err) => err.fmt(f),
+			Error::Settings(err) => err.fmt(f),
+			Error::Compositor(err) => err.fmt(f),
+			#[cfg(windows)]
+			Error::D3D(err) => err.fmt(f),
+			Error::Wait(err) => err.fmt(f),
+			Error::Config(err) => err.fmt(f),
+			Error::Dump(err) => err.fmt(f),
+			Error::Board(err) => err.fmt(f),
+			Error::Io(err) => err.fmt(f),
+			Error::Image(err) => err.fmt(f),
+			Error::Json(err) => err.fmt(f),
+			Error::Usage(text) | Error::Other(text) => f.pad(text),
+			Error::Context { operation, device: Some(device), .. } => write!(f, "Unable to {} on device {}", operation, device),
+			Error::Context { operation, device: None, .. } => write!(f, "Unable to {}", operation),
+		}
+	}
+}
+
+macro_rules! from_error {
+	($($(#[$attr:meta])* $variant:ident($error:ty)),* $(,)?) => {
+		$($(#[$attr])* impl From<$error> for Error {
+			fn from(err: $error) -> Self { Error::$variant(err) }
+		})*
+	};
+}
+
+from_error! {
+	Init(InitError),
+	TrackedCamera(TrackedCameraError),
+	Frame(FrameError),
+	TrackedProperty(TrackedPropertyError),
+	Settings(SettingsError),
+	Compositor(CompositorError),
+	#[cfg(windows)]
+	D3D(D3DError),
+	Wait(WaitError),
+	Config(ConfigError),
+	Dump(DumpError),
+	Board(BoardError),
+	Io(io::Error),
+	Image(image::ImageError),
+	Json(serde_json::Error),
+}
+
+impl From<ParseIntError> for Error {
+	fn from(err: ParseIntError) -> Self { Error::Usage(format!("Invalid number: {}", err)) }
+}
+
+impl From<ParseFloatError> for Error {
+	fn from(err: ParseFloatError) -> Self { Error::Usage(format!("Invalid number: {}", err)) }
+}
+
+impl From<SystemTimeError> for Error {
+	fn from(err: SystemTimeError) -> Self { Error::Other(format!("System clock is before 1970: {}", err)) }
+}
+
+/// Attaches what was being done to the error of a result.
+pub trait ResultExt<T> {
+	fn during(self, operation: &str) -> Result<T, Error>;
+	fn during_on(self, operation: &str, device: TrackedDeviceIndex) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
+	fn during(self, operation: &str) -> Result<T, Error> {
+		self.map_err(|err| Error::Context { operation: operation.to_string(), device: None, source: Box::new(err.into()) })
+	}
+	
+	fn during_on(self, operation: &str, device: TrackedDeviceIndex) -> Result<T, Error> {
+		self.map_err(|err| Error::Context { operation: operation.to_string(), device: Some(device), source: Box::new(err.into()) })
+	}
+}
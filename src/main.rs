@@ -1,28 +1,128 @@
-use std::{env, fmt, fs, thread};
-use std::error::Error;
-use std::fmt::{Display, Formatter};
+use std::{env, fs, io, process, thread};
+use std::io::Write;
+use std::error::Error as _;
 use std::time::{SystemTime, Duration, Instant};
-use image::{RgbaImage, DynamicImage, GenericImage};
+use image::DynamicImage;
 use openvr_sys::k_unTrackedDeviceIndex_Hmd as HMD;
 
 pub mod openvr_load;
+mod openvr_api;
+mod openvr_errors;
+mod error;
 mod compositor;
 mod tracked_camera;
 mod settings;
+#[cfg(windows)]
 mod directx;
 mod system;
+mod calibration;
+mod vision;
+mod reconstruction;
+mod startup;
 
+use error::{Error, ResultExt};
+#[cfg(windows)]
+use compositor::{VREye, MirrorTexture};
+use tracked_camera::{TrackedCamera, CameraTrackingSpace, VideoStream, Frame, FrameType, Capture, CaptureOptions, Z_NEAR, Z_FAR, release_errors};
 use system::System;
-use compositor::{Compositor, VREye};
-use tracked_camera::{TrackedCamera, FrameType};
 use settings::Settings;
-use openvr_load::{ApplicationType, Context};
+use openvr_load::{ApplicationType, Context, Interfaces, TrackingUniverseOrigin};
+use openvr_errors::ERROR_TABLES;
+#[cfg(windows)]
 use directx::D3DContext;
+use startup::{wait_for_steamvr, SystemClock, WaitOptions};
+use calibration::{LighthouseConfig, ValidationReport, ProjectionReport, RectifiedPair, QualityReport, Dump, DumpError, BundleProblem, BundleAdjustment,
+                  ConfigPatch, ConfigDiff, Recording, Manifest, FrameHeader, BoardDetections, BoardCalibration, HandEyeReport, TagMap, TagReport,
+                  diff_json, frame_layout};
+use vision::{split_frame, Board, Dictionary};
+use reconstruction::{StereoDepth, Fusion, Panorama, FUSION_VOXEL, PANORAMA_WIDTH};
+#[cfg(windows)]
 use winreg::RegKey;
+#[cfg(windows)]
 use winreg::enums::HKEY_LOCAL_MACHINE;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// How long to wait for a new camera frame before giving up.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        
+        let mut source = err.source();
+        while let Some(cause) = source {
+            eprintln!("  Caused by: {}", cause);
+            source = cause.source();
+        }
+        
+        if let Some(hint) = err.hint() {
+            eprintln!("Hint: {}", hint);
+        }
+        
+        process::exit(err.exit_code());
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let wait = args.iter().any(|arg| arg == "--wait");
+    let mut args = args.into_iter().filter(|arg| arg != "--wait").collect::<Vec<_>>();
+    let space = match args.iter().position(|arg| arg == "--tracking-space") {
+        Some(index) if index + 1 < args.len() => {
+            let name = args.remove(index + 1);
+            args.remove(index);
+            Some(TrackingUniverseOrigin::parse(&name).ok_or_else(|| Error::usage(format!("Unknown tracking space {}, use seated, standing or raw", name)))?)
+        },
+        Some(_) => return Err(Error::usage("--tracking-space needs seated, standing or raw")),
+        None => None,
+    };
+    
+    match args.first().map(String::as_str) {
+        None | Some("dump") => dump(wait, space),
+        Some("record") => record(args.get(1).cloned(), args.get(2).cloned(), wait, space),
+        Some("capture") => capture(args.get(1).cloned(), wait, space),
+        Some("score") => score(args.get(1).map(PathBuf::from)),
+        Some("refine") => refine(
+            args.iter().skip(1).find(|arg| !arg.starts_with("--")).map(PathBuf::from),
+            args.iter().any(|arg| arg == "--intrinsics"),
+        ),
+        Some("apply") => apply(
+            args.iter().skip(1).find(|arg| !arg.starts_with("--")).map(PathBuf::from),
+            args.iter().skip(1).filter(|arg| !arg.starts_with("--")).nth(1).map(PathBuf::from),
+            args.iter().any(|arg| arg == "--yes"),
+        ),
+        Some("rollback") => rollback(
+            args.iter().skip(1).find(|arg| !arg.starts_with("--")).cloned(),
+            args.iter().skip(1).filter(|arg| !arg.starts_with("--")).nth(1).cloned(),
+            args.iter().any(|arg| arg == "--yes"),
+        ),
+        Some("calibrate") => calibrate(args.get(1).map(PathBuf::from), args.get(2).cloned()),
+        Some("handeye") => hand_eye(args.get(1).map(PathBuf::from), args.get(2).cloned()),
+        Some("tags") => tags(args.get(1).map(PathBuf::from), args.get(2).map(PathBuf::from)),
+        Some("depth") => depth(
+            args.iter().skip(1).find(|arg| !arg.starts_with("--")).map(PathBuf::from),
+            args.iter().any(|arg| arg == "--tracking"),
+        ),
+        Some("fuse") => fuse(args.get(1).map(PathBuf::from), args.get(2).cloned()),
+        Some("panorama") => panorama(args.get(1).map(PathBuf::from), args.get(2).cloned()),
+        Some("diff") => diff(args.get(1).map(PathBuf::from), args.get(2).map(PathBuf::from)),
+        Some("error") => explain_error(args.get(1).cloned()),
+        Some(command) => Err(Error::usage(format!("Unknown command {}, use dump, record [count] [interval ms], capture [count] (all three take [--wait] [--tracking-space <space>]), \
+                                                    score [dumps folder], \
+                                                    refine [dumps folder] [--intrinsics], calibrate <recording | dumps folder> <board>, \
+                                                    handeye <recording | dumps folder> <board>, \
+                                                    tags <recording | dumps folder> <tag map json>, \
+                                                    depth <dump folder> [--tracking], \
+                                                    fuse [recording | dumps folder] [voxel m], \
+                                                    panorama [recording | dumps folder] [width], \
+                                                    apply <refined json> [config.json] [--yes], \
+                                                    rollback <serial number | config.json> [backup] [--yes], \
+                                                    diff <config.json | dump folder> <config.json | dump folder> \
+                                                    or error <code | symbol>", command))),
+    }
+}
+
+fn dump(wait: bool, space: Option<TrackingUniverseOrigin>) -> Result<(), Error> {
     let curtime = SystemTime::now()
                              .duration_since(SystemTime::UNIX_EPOCH)?
                              .as_secs();
@@ -30,39 +130,381 @@ fn main() -> Result<(), Box<dyn Error>> {
                           .join("dumps")
                           .join(format!("{}", curtime));
     
+    #[cfg(windows)]
     println!("Initializing DirectX...");
     
-    let d3d = D3DContext::new()?;
+    #[cfg(windows)]
+    let d3d = D3DContext::new().during("initialize DirectX")?;
+    
+    let Interfaces { system, tracked_camera, compositor, settings } = init_openvr(wait)?;
+    
+    let _tracking_space = prepare_camera(&tracked_camera, &settings, space)?;
+    
+    println!("Searching for calibration data...");
+    
+    let serial_number = system.string_tracked_device_property(HMD, openvr_sys::ETrackedDeviceProperty_Prop_SerialNumber_String)
+                              .during_on("read the serial number", HMD)?;
+    let config_path = lighthouse_config_path(&serial_number);
+    
+    let config = fs::read_to_string(config_path).unwrap_or_else(|err| {
+        eprintln!("Unable to read config! {}", err);
+        "N/A".into()
+    });
+    
+    println!("Fetching intrinsics...");
+    
+    let intrinsics = (
+        tracked_camera.get_camera_intrinsics(HMD, 0, FrameType::Distorted),
+        tracked_camera.get_camera_intrinsics(HMD, 1, FrameType::Distorted),
+        tracked_camera.get_camera_projection(HMD, 0, FrameType::Distorted, Z_NEAR, Z_FAR),
+        tracked_camera.get_camera_projection(HMD, 1, FrameType::Distorted, Z_NEAR, Z_FAR),
+        tracked_camera.get_camera_intrinsics(HMD, 0, FrameType::Undistorted),
+        tracked_camera.get_camera_intrinsics(HMD, 1, FrameType::Undistorted),
+        tracked_camera.get_camera_projection(HMD, 0, FrameType::Undistorted, Z_NEAR, Z_FAR),
+        tracked_camera.get_camera_projection(HMD, 1, FrameType::Undistorted, Z_NEAR, Z_FAR),
+        tracked_camera.get_camera_intrinsics(HMD, 0, FrameType::MaximumUndistorted),
+        tracked_camera.get_camera_intrinsics(HMD, 1, FrameType::MaximumUndistorted),
+        tracked_camera.get_camera_projection(HMD, 0, FrameType::MaximumUndistorted, Z_NEAR, Z_FAR),
+        tracked_camera.get_camera_projection(HMD, 1, FrameType::MaximumUndistorted, Z_NEAR, Z_FAR),
+    );
+    
+    println!("Fetching device properties...");
+    
+    let camera_properties = system.camera_properties(HMD);
+    
+    let properties = system.all_tracked_device_properties(HMD)
+                           .into_iter()
+                           .map(|(info, value)| match value {
+                               Ok(value) => format!("{} = {:?}\n", info.name, value),
+                               Err(err) => format!("{} = <{}>\n", info.name, err),
+                           })
+                           .collect::<String>();
+    
+    println!("Validating intrinsics against config...");
+    
+    let lighthouse_config = LighthouseConfig::parse(&config);
+    let validation = match &lighthouse_config {
+        Ok(lighthouse_config) => {
+            let report = ValidationReport::new(&tracked_camera, HMD, &camera_properties, lighthouse_config);
+            println!("Intrinsics validation: {}", if report.passed() { "PASS" } else { "FAIL" });
+            report.to_string()
+        },
+        Err(err) => {
+            eprintln!("Unable to validate intrinsics! {}", err);
+            format!("N/A: {}", err)
+        },
+    };
+    
+    println!("Decomposing projections...");
+    
+    let projections = ProjectionReport::new(&tracked_camera, HMD, &camera_properties, lighthouse_config.as_ref().ok());
+    println!("Projection check: {}", if projections.passed() { "PASS" } else { "FAIL" });
+    
+    // The mirror textures can only be read through DirectX, elsewhere the dump has no mirror image
+    #[cfg(windows)]
+    let mut mirror = {
+        println!("Initializing Mirror Textures...");
+        
+        [compositor.get_mirror_texture_d3d11(VREye::Left, &d3d).during("get the left mirror texture")?,
+         compositor.get_mirror_texture_d3d11(VREye::Right, &d3d).during("get the right mirror texture")?]
+    };
+    #[cfg(not(windows))]
+    drop(compositor);
+    
+    println!("Spin up sleep...");
+    
+    thread::sleep(Duration::from_secs(1));
+    
+    println!("Fetching camera frame...");
+    
+    let frame = VideoStream::acquire(&tracked_camera, HMD).during_on("acquire the camera video stream", HMD)?
+                            .next_frame(FrameType::Distorted, FRAME_TIMEOUT)
+                            .during_on("read a camera frame", HMD)?;
+    let camera_image = frame.to_image().ok_or(Error::other("Failed to parse camera frame"))?;
+    
+    println!("Rectifying camera images...");
+    
+    let (layout, _) = frame_layout(&tracked_camera, HMD, &camera_properties);
+    let rectified = lighthouse_config.as_ref()
+                                     .ok()
+                                     .and_then(|config| RectifiedPair::new(config, &split_frame(&camera_image, layout)));
+    
+    if rectified.is_none() {
+        eprintln!("Unable to rectify camera images!");
+    }
+    
+    #[cfg(windows)]
+    let mirror_image = Some(capture_mirror(&mut mirror)?);
+    #[cfg(not(windows))]
+    let mirror_image: Option<DynamicImage> = None;
+    
+    println!("Saving results to {}...", destination.as_os_str().to_string_lossy());
+    
+    fs::create_dir_all(&destination)?;
+    
+    fs::write(destination.join("config.json"), config)?;
+    camera_image.save(destination.join("camera.png"))?;
+    if let Some(mirror_image) = mirror_image {
+        mirror_image.save(destination.join("mirror.png"))?;
+    }
+    fs::write(destination.join("frame.json"), serde_json::to_string_pretty(&FrameHeader::from(&frame.header))?)?;
+    fs::write(destination.join("intrinsics.txt"), format!("{:#?}", intrinsics))?;
+    fs::write(destination.join("camera_properties.txt"), format!("{:#?}", camera_properties))?;
+    fs::write(destination.join("properties.txt"), properties)?;
+    fs::write(destination.join("validation.txt"), validation)?;
+    fs::write(destination.join("projection.txt"), projections.to_string())?;
+    write_manifest(&system, &tracked_camera, &destination)?;
+    write_release_errors(&destination)?;
+    
+    if let Some(rectified) = rectified {
+        let [left, right] = rectified.with_epipolar_lines();
+        left.save(destination.join("rectified_left.png"))?;
+        right.save(destination.join("rectified_right.png"))?;
+        fs::write(destination.join("rectification.txt"), rectified.rectification.to_string())?;
+    }
+    
+    println!("\nDone!");
+    
+    Ok(())
+}
+
+/// Both eyes of the compositor mirror side by side.
+#[cfg(windows)]
+fn capture_mirror(mirror: &mut [MirrorTexture; 2]) -> Result<DynamicImage, Error> {
+    use image::GenericImage;
+    
+    println!("Fetching mirror image...");
+    
+    unsafe {
+        let left_image = mirror[0].capture();
+        let right_image = mirror[1].capture();
+        let mut mirror_image = DynamicImage::new_rgb8(
+            left_image.width() + right_image.width(),
+            left_image.height().max(right_image.height())
+        );
     
+        mirror_image.copy_from(&left_image, 0, 0)?;
+        mirror_image.copy_from(&right_image, left_image.width(), 0)?;
+    
+        Ok(mirror_image)
+    }
+}
+
+/// With `wait` it waits for SteamVR to start and find the headset and its camera instead of failing right away.
+fn init_openvr(wait: bool) -> Result<Interfaces, Error> {
     println!("Initializing OpenVR...");
     
-    let context = Context::new(ApplicationType::Other)?;
-    let system = System::new(&context)?;
-    let compositor = Compositor::new(&context)?;
-    let tracked_camera = TrackedCamera::new(&context)?;
-    let settings = Settings::new(&context)?;
+    let (context, interfaces) = if wait {
+        wait_for_steamvr(&SystemClock::new(), &WaitOptions::default(), |progress| println!("{}", progress))?
+    } else {
+        let context = Context::new(ApplicationType::Other).during("initialize OpenVR")?;
+        let interfaces = context.interfaces().during("load the OpenVR interfaces")?;
+        (context, interfaces)
+    };
+    
+    println!("OpenVR interfaces: {}", context.capabilities());
     
+    Ok(interfaces)
+}
+
+/// Enables the camera and Room View, and puts the frame poses in `space` if one is given until the returned guard is
+/// dropped.
+fn prepare_camera(tracked_camera: &TrackedCamera, settings: &Settings, space: Option<TrackingUniverseOrigin>) -> Result<Option<CameraTrackingSpace>, Error> {
     if !tracked_camera.has_camera(HMD) {
-        return Err(StrError::new("No camera in HMD"));
+        return Err(Error::other("No camera in HMD"));
     }
     
-    if !settings.get_bool("camera", "enableCamera")? {
+    if !settings.get_bool("camera", "enableCamera").during("read the camera settings")? {
         println!("Camera is not enabled, enabling...");
-        settings.set_bool("camera", "enableCamera", true)?;
+        settings.set_bool("camera", "enableCamera", true).during("enable the camera")?;
     }
     
-    if settings.get_i32("camera", "roomView")? != 1 {
+    if settings.get_i32("camera", "roomView").during("read the camera settings")? != 1 {
         println!("Room view is not set to 2D, setting...");
-        settings.set_i32("camera", "roomView", 1)?;
+        settings.set_i32("camera", "roomView", 1).during("set Room View to 2D")?;
     }
     
-    if settings.get_i32("camera", "roomViewStyle")? != 4 {
+    if settings.get_i32("camera", "roomViewStyle").during("read the camera settings")? != 4 {
         println!("Room view style is not set to opaque, setting...");
-        settings.set_i32("camera", "roomViewStyle", 4)?;
+        settings.set_i32("camera", "roomViewStyle", 4).during("set the Room View style")?;
     }
     
-    println!("Searching for calibration data...");
+    match space {
+        Some(space) => match tracked_camera.select_camera_tracking_space(space) {
+            Some(selected) => Ok(Some(selected)),
+            None => Err(Error::other(format!("{} can't select the camera tracking space, update SteamVR", tracked_camera.version()))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn record(count: Option<String>, interval: Option<String>, wait: bool, space: Option<TrackingUniverseOrigin>) -> Result<(), Error> {
+    let count = count.map_or(Ok(100), |count| count.parse::<usize>())?;
+    let interval = Duration::from_millis(interval.map_or(Ok(500), |interval| interval.parse::<u64>())?);
+    let curtime = SystemTime::now()
+                             .duration_since(SystemTime::UNIX_EPOCH)?
+                             .as_secs();
+    let destination = env::current_dir()?
+                          .join("recordings")
+                          .join(format!("{}", curtime));
+    
+    let Interfaces { system, tracked_camera, settings, .. } = init_openvr(wait)?;
+    
+    let _tracking_space = prepare_camera(&tracked_camera, &settings, space)?;
+    prepare_recording(&system, &tracked_camera, &destination)?;
+    
+    println!("Recording {} frames every {} ms to {}...", count, interval.as_millis(), destination.as_os_str().to_string_lossy());
+    
+    let mut timestamps = String::new();
+    let start = Instant::now();
+    
+    {
+        let mut stream = VideoStream::acquire(&tracked_camera, HMD).during_on("acquire the camera video stream", HMD)?;
+        
+        for (recorded, frame) in stream.frames(FrameType::Distorted, FRAME_TIMEOUT).take(count).enumerate() {
+            let frame = frame.during_on("read a camera frame", HMD)?;
+            save_recorded_frame(&destination, recorded, &frame, start, &mut timestamps)?;
+            
+            print!("\rRecorded {}/{}", recorded + 1, count);
+            io::stdout().flush()?;
+            
+            thread::sleep(interval);
+        }
+    }
+    
+    write_release_errors(&destination)?;
+    
+    println!("\nDone!");
+    
+    Ok(())
+}
+
+/// Lets SteamVR stream to a ring buffer of the last `count` frames until Enter is pressed, then saves them like `record`.
+fn capture(count: Option<String>, wait: bool, space: Option<TrackingUniverseOrigin>) -> Result<(), Error> {
+    let count = count.map_or(Ok(30), |count| count.parse::<usize>())?;
+    if count == 0 {
+        return Err(Error::usage("capture needs to keep at least 1 frame"));
+    }
+    
+    let curtime = SystemTime::now()
+                             .duration_since(SystemTime::UNIX_EPOCH)?
+                             .as_secs();
+    let destination = env::current_dir()?
+                          .join("recordings")
+                          .join(format!("{}", curtime));
+    
+    let Interfaces { system, tracked_camera, settings, .. } = init_openvr(wait)?;
+    
+    let _tracking_space = prepare_camera(&tracked_camera, &settings, space)?;
+    
+    let stream = VideoStream::acquire(&tracked_camera, HMD).during_on("acquire the camera video stream", HMD)?;
+    let capture = Capture::start(stream, CaptureOptions { capacity: count, ..CaptureOptions::default() });
+    
+    println!("Keeping the last {} camera frames, press Enter to save them...", count);
+    io::stdin().read_line(&mut String::new())?;
+    
+    let frames = capture.snapshot();
+    let stats = capture.stats();
+    let last_error = capture.last_error();
+    drop(capture);
+    
+    let start = match frames.first() {
+        Some(frame) => frame.received,
+        None => return match last_error {
+            Some(err) => Err(err).during_on("read a camera frame", HMD),
+            None => Err(Error::other("The camera sent no frames")),
+        },
+    };
+    
+    prepare_recording(&system, &tracked_camera, &destination)?;
+    
+    println!("Saving {} frames to {}...", frames.len(), destination.as_os_str().to_string_lossy());
+    
+    let mut timestamps = String::new();
+    for (index, frame) in frames.iter().enumerate() {
+        save_recorded_frame(&destination, index, frame, start, &mut timestamps)?;
+    }
+    
+    write_release_errors(&destination)?;
+    
+    println!("Captured {} frames, {} were missed between polls and {} dropped while the buffer was being read",
+             stats.captured, stats.missed, stats.dropped);
+    
+    println!("\nDone!");
+    
+    Ok(())
+}
+
+/// Creates the recording folder with the lighthouse config and camera properties the frames go with.
+fn prepare_recording(system: &System, tracked_camera: &TrackedCamera, destination: &Path) -> Result<(), Error> {
+    let serial_number = system.string_tracked_device_property(HMD, openvr_sys::ETrackedDeviceProperty_Prop_SerialNumber_String)
+                              .during_on("read the serial number", HMD)?;
+    let config = fs::read_to_string(lighthouse_config_path(&serial_number))?;
+    
+    fs::create_dir_all(destination)?;
+    fs::write(destination.join("config.json"), config)?;
+    fs::write(destination.join("camera_properties.txt"), format!("{:#?}", system.camera_properties(HMD)))?;
+    write_manifest(system, tracked_camera, destination)?;
+    
+    Ok(())
+}
+
+/// Saves `manifest.json` with the space of the frame poses and where it is in the tracking universe, so poses of
+/// different sessions can be brought into one frame, and the camera to head transforms the frames go with.
+fn write_manifest(system: &System, tracked_camera: &TrackedCamera, destination: &Path) -> Result<(), Error> {
+    let manifest = Manifest {
+        // Interfaces without the tracking space always use standing
+        tracking_space: tracked_camera.camera_tracking_space().unwrap_or(TrackingUniverseOrigin::Standing).name().to_string(),
+        universe_id: system.uint64_tracked_device_property(HMD, openvr_sys::ETrackedDeviceProperty_Prop_CurrentUniverseId_Uint64).ok(),
+        seated_zero_pose_to_standing: system.seated_zero_pose_to_standing(),
+        raw_zero_pose_to_standing: system.raw_zero_pose_to_standing(),
+        cameras_to_head: system.array_tracked_device_property(HMD, openvr_sys::ETrackedDeviceProperty_Prop_CameraToHeadTransforms_Matrix34_Array).ok(),
+    };
+    
+    fs::write(destination.join("manifest.json"), serde_json::to_string_pretty(&manifest)?)?;
+    
+    Ok(())
+}
+
+/// Saves `<index>.png` and `<index>.json`, and `timestamps.txt` with the frame appended.
+fn save_recorded_frame(destination: &Path, index: usize, frame: &Frame, start: Instant, timestamps: &mut String) -> Result<(), Error> {
+    *timestamps += &format!("{:04} {} {:.3}\n", index, frame.sequence(), frame.received.duration_since(start).as_secs_f64() * 1000.0);
+    
+    let camera_image = frame.to_image().ok_or(Error::other("Failed to parse camera frame"))?;
+    
+    camera_image.save(destination.join(format!("{:04}.png", index)))?;
+    fs::write(destination.join(format!("{:04}.json", index)), serde_json::to_string_pretty(&FrameHeader::from(&frame.header))?)?;
     
+    fs::write(destination.join("timestamps.txt"), &*timestamps)?;
+    
+    Ok(())
+}
+
+/// Saves the camera streams that failed to release next to the output, they explain a camera that stays busy afterwards.
+fn write_release_errors(destination: &Path) -> Result<(), Error> {
+    let errors = release_errors();
+    if !errors.is_empty() {
+        let lines: String = errors.iter()
+                                  .map(|(device, handle, err)| format!("device {} handle {:#x}: {:?}\n", device, handle, err))
+                                  .collect();
+        fs::write(destination.join("stream_release_errors.txt"), lines)?;
+    }
+    
+    Ok(())
+}
+
+fn lighthouse_config_path(serial_number: &str) -> PathBuf {
+    let mut config_path = steam_config_folder();
+    config_path.push("lighthouse");
+    config_path.push(serial_number.to_lowercase());
+    config_path.push("config.json");
+    println!("Using config location: {}", config_path.to_string_lossy());
+    
+    config_path
+}
+
+#[cfg(windows)]
+fn steam_config_folder() -> PathBuf {
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     let steam_location = hklm.open_subkey("SOFTWARE\\Valve\\Steam")
                              .or(hklm.open_subkey("SOFTWARE\\Wow6432Node\\Valve\\Steam"))
@@ -74,130 +516,360 @@ fn main() -> Result<(), Box<dyn Error>> {
                              });
     println!("Using steam location: {}", steam_location);
     
-    let serial_number = system.string_tracked_device_property(HMD, openvr_sys::ETrackedDeviceProperty_Prop_SerialNumber_String)?;
-    let mut config_path = PathBuf::new();
-    config_path.push(steam_location);
-    config_path.push("config");
-    config_path.push("lighthouse");
-    config_path.push(serial_number.to_lowercase());
-    config_path.push("config.json");
-    println!("Using config location: {}", config_path.to_string_lossy());
-    
-    let config = fs::read_to_string(config_path).unwrap_or_else(|err| {
-        eprintln!("Unable to read config! {}", err);
-        "N/A".into()
-    });
-    
-    println!("Fetching intrinsics...");
+    PathBuf::from(steam_location).join("config")
+}
+
+/// The Steam config folder SteamVR registered in `openvrpaths.vrpath`, `~/.steam/steam/config` if it has none.
+#[cfg(not(windows))]
+fn steam_config_folder() -> PathBuf {
+    openvr_api::config_folders().into_iter().next().unwrap_or_else(|| {
+        let fallback = PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".steam/steam/config");
+        eprintln!("Unable to find steam config path!\nFallback to: {}", fallback.to_string_lossy());
+        fallback
+    })
+}
+
+fn score(dumps: Option<PathBuf>) -> Result<(), Error> {
+    let dumps = match dumps {
+        Some(dumps) => dumps,
+        None => env::current_dir()?.join("dumps"),
+    };
     
-    let intrinsics = (
-        tracked_camera.get_camera_intrinsics(HMD, 0, FrameType::Distorted),
-        tracked_camera.get_camera_intrinsics(HMD, 1, FrameType::Distorted),
-        tracked_camera.get_camera_projection(HMD, 0, FrameType::Distorted, 0.01, 100.01),
-        tracked_camera.get_camera_projection(HMD, 1, FrameType::Distorted, 0.01, 100.01),
-        tracked_camera.get_camera_intrinsics(HMD, 0, FrameType::Undistorted),
-        tracked_camera.get_camera_intrinsics(HMD, 1, FrameType::Undistorted),
-        tracked_camera.get_camera_projection(HMD, 0, FrameType::Undistorted, 0.01, 100.01),
-        tracked_camera.get_camera_projection(HMD, 1, FrameType::Undistorted, 0.01, 100.01),
-        tracked_camera.get_camera_intrinsics(HMD, 0, FrameType::MaximumUndistorted),
-        tracked_camera.get_camera_intrinsics(HMD, 1, FrameType::MaximumUndistorted),
-        tracked_camera.get_camera_projection(HMD, 0, FrameType::MaximumUndistorted, 0.01, 100.01),
-        tracked_camera.get_camera_projection(HMD, 1, FrameType::MaximumUndistorted, 0.01, 100.01),
-    );
+    println!("Scoring dumps in {}...", dumps.to_string_lossy());
     
-    println!("Initializing Mirror Textures...");
+    let report = QualityReport::new(&dumps)?;
     
-    let mut left_eye = compositor.get_mirror_texture_d3d11(VREye::Left, &d3d)?;
-    let mut right_eye = compositor.get_mirror_texture_d3d11(VREye::Right, &d3d)?;
+    print!("\n{}", report);
+    fs::write(dumps.join("quality.txt"), report.to_string())?;
     
-    println!("Spin up sleep...");
+    Ok(())
+}
+
+fn refine(dumps: Option<PathBuf>, intrinsics: bool) -> Result<(), Error> {
+    let dumps = match dumps {
+        Some(dumps) => dumps,
+        None => env::current_dir()?.join("dumps"),
+    };
     
-    thread::sleep(Duration::from_secs(1));
+    println!("Refining calibration from dumps in {}...", dumps.to_string_lossy());
+    
+    let mut headsets: Vec<Vec<Dump>> = vec![];
+    for (path, dump) in Dump::load_all(&dumps)? {
+        match dump {
+            Ok(dump) if dump.pose.is_none() => eprintln!("Skipping {}: {}", path.to_string_lossy(), DumpError::NoPose),
+            Ok(dump) => match headsets.iter_mut().find(|headset| headset[0].serial() == dump.serial()) {
+                Some(headset) => headset.push(dump),
+                None => headsets.push(vec![dump]),
+            },
+            Err(err) => eprintln!("Skipping {}: {}", path.to_string_lossy(), err),
+        }
+    }
     
-    print!("Fetching camera frame...");
+    let mut report = String::new();
     
-    let frame_size;
-    let mut buffer;
-    let header;
-    unsafe {
-        let start = Instant::now();
-        let service = tracked_camera.acquire_video_streaming_service(HMD)?;
-        frame_size = tracked_camera.get_camera_frame_size(HMD, FrameType::Distorted)?;
-        buffer = vec![0u8; frame_size.frame_buffer_size as usize];
+    for headset in headsets {
+        let serial = headset[0].serial().to_string();
+        println!("Refining {} from {} dumps...", serial, headset.len());
         
-        loop {
-            match tracked_camera.get_video_stream_frame_buffer(service, FrameType::Distorted, &mut buffer) {
-                Ok(result) => {
-                    header = result;
-                    break;
-                },
-                Err(err) => {
-                    print!(".");
-    
-                    if start.elapsed().as_secs() > 5 {
-                        println!();
-                        return Err(err.into());
-                    }
-                }
-            };
-            
-            thread::sleep(Duration::from_secs(1));
-        }
+        let problem = match BundleProblem::new(&headset) {
+            Ok(problem) => problem,
+            Err(err) => {
+                eprintln!("Unable to refine {}! {}", serial, err);
+                report += &format!("Headset {}: {}\n\n", serial, err);
+                continue;
+            },
+        };
         
-        println!();
+        let adjustment = BundleAdjustment::solve(&problem, intrinsics);
+        let cameras = adjustment.corrected_cameras(&headset[0].config);
         
-        // tracked_camera.release_video_streaming_service(service)?; // Just doesn't work ¯\_(ツ)_/¯
+        report += &format!("Headset {}:\n{}\n", serial, adjustment);
+        let refined = serde_json::json!({ "device_serial_number": headset[0].config.serial, "tracked_cameras": cameras });
+        fs::write(dumps.join(format!("refined_{}.json", serial)), serde_json::to_string_pretty(&refined)?)?;
+    }
+    
+    print!("\n{}", report);
+    fs::write(dumps.join("refinement.txt"), report)?;
+    
+    Ok(())
+}
+
+fn calibrate(recording: Option<PathBuf>, board: Option<String>) -> Result<(), Error> {
+    let (recording, board) = match (recording, board) {
+        (Some(recording), Some(board)) => (recording, board),
+        _ => return Err(Error::usage("Usage: calibrate <recording | dumps folder> <board>")),
     };
+    let board = Board::parse(&board)?;
     
-    for i in (3..buffer.len()).step_by(4) {
-        buffer[i] = 255;
+    println!("Detecting the board in {}...", recording.to_string_lossy());
+    
+    let recording = Recording::load(&recording)?;
+    let detections = BoardDetections::new(&recording, &board)?;
+    
+    for (frame, detection) in detections.detections.iter().enumerate() {
+        let corners = |camera: usize| detection[camera].as_ref().map_or(0, |detection| detection.ids.len());
+        println!("  Frame {}: {} and {} corners", frame, corners(0), corners(1));
     }
     
-    let camera_image = RgbaImage::from_raw(frame_size.width, frame_size.height, buffer)
-        .ok_or(StrError::new("Failed to parse camera frame"))?;
+    println!("Calibrating {}...", recording.serial());
     
-    println!("Fetching mirror image...");
+    let calibration = BoardCalibration::solve(&detections, &board).ok_or(Error::other("The board was not found in any frame"))?;
+    let cameras = calibration.corrected_cameras(&recording.config);
+    let patch = ConfigPatch::new(&recording.config_path, &cameras)?;
+    let diff = ConfigDiff::new(&patch.original, &patch.patched)?;
     
-    let mirror_image= unsafe {
-        let left_image = left_eye.capture();
-        let right_image = right_eye.capture();
-        let mut mirror_image = DynamicImage::new_rgb8(
-            left_image.width() + right_image.width(),
-            left_image.height().max(right_image.height())
-        );
+    let report = format!("{}\nCompared to the lighthouse calibration:\n{}", calibration, diff);
+    print!("\n{}", report);
     
-        mirror_image.copy_from(&left_image, 0, 0)?;
-        mirror_image.copy_from(&right_image, left_image.width(), 0)?;
+    let calibrated = serde_json::json!({ "device_serial_number": recording.config.serial, "tracked_cameras": cameras });
+    fs::write(recording.path.join("calibration.txt"), report)?;
+    fs::write(recording.path.join(format!("calibrated_{}.json", recording.serial())), serde_json::to_string_pretty(&calibrated)?)?;
     
-        mirror_image
+    Ok(())
+}
+
+fn hand_eye(recording: Option<PathBuf>, board: Option<String>) -> Result<(), Error> {
+    let (recording, board) = match (recording, board) {
+        (Some(recording), Some(board)) => (recording, board),
+        _ => return Err(Error::usage("Usage: handeye <recording | dumps folder> <board>")),
     };
+    let board = Board::parse(&board)?;
     
-    println!("Saving results to {}...", destination.as_os_str().to_string_lossy());
+    println!("Detecting the board in {}...", recording.to_string_lossy());
     
-    fs::create_dir_all(&destination)?;
+    let recording = Recording::load(&recording)?;
+    let detections = BoardDetections::new(&recording, &board)?;
     
-    fs::write(destination.join("config.json"), config)?;
-    camera_image.save(destination.join("camera.png"))?;
-    mirror_image.save(destination.join("mirror.png"))?;
-    fs::write(destination.join("frame.txt"), format!("{:#?}", header))?;
-    fs::write(destination.join("intrinsics.txt"), format!("{:#?}", intrinsics))?;
+    println!("Solving camera to head transforms of {}...", recording.serial());
     
-    println!("\nDone!");
+    let report = HandEyeReport::new(&recording, &detections, &board);
+    
+    print!("\n{}", report);
+    fs::write(recording.path.join("handeye.txt"), report.to_string())?;
     
     Ok(())
 }
 
+fn tags(recording: Option<PathBuf>, map_path: Option<PathBuf>) -> Result<(), Error> {
+    let (recording, map_path) = match (recording, map_path) {
+        (Some(recording), Some(map_path)) => (recording, map_path),
+        _ => return Err(Error::usage("Usage: tags <recording | dumps folder> <tag map json>")),
+    };
+    let map = TagMap::load(&map_path)?;
+    let dictionary = Dictionary::load(&map.dictionary)?;
+    
+    println!("Detecting tags in {}...", recording.to_string_lossy());
+    
+    let recording = Recording::load(&recording)?;
+    let report = TagReport::new(&recording, &map, &dictionary)?;
+    
+    print!("\n{}", report);
+    fs::write(recording.path.join("tags.txt"), report.to_string())?;
+    
+    Ok(())
+}
+
+fn depth(dump: Option<PathBuf>, tracking: bool) -> Result<(), Error> {
+    let dump = dump.ok_or(Error::usage("Usage: depth <dump folder> [--tracking]"))?;
+    let dump = Dump::load(&dump)?;
+    
+    println!("Matching camera images of {}...", dump.path.to_string_lossy());
+    
+    let depth = StereoDepth::new(&dump.config, &dump.camera_images()).ok_or(DumpError::NotStereo)?;
+    let mut cloud = depth.point_cloud();
+    
+    if tracking {
+        cloud.transform(&dump.pose.ok_or(DumpError::NoPose)?);
+    }
+    
+    print!("\n{}", depth);
+    println!("Saving {} points in {} space...", cloud.len(), if tracking { "tracking" } else { "head" });
+    
+    depth.disparity.to_image().save(dump.path.join("disparity.png"))?;
+    depth.depth_image().save(dump.path.join("depth.png"))?;
+    cloud.write_ply(&dump.path.join(if tracking { "points_tracking.ply" } else { "points.ply" }))?;
+    
+    Ok(())
+}
+
+fn fuse(recording: Option<PathBuf>, voxel: Option<String>) -> Result<(), Error> {
+    let recording = match recording {
+        Some(recording) => recording,
+        None => env::current_dir()?.join("dumps"),
+    };
+    let voxel = voxel.map_or(Ok(FUSION_VOXEL), |voxel| voxel.parse::<f64>())?;
+    if !(voxel.is_finite() && voxel > 0.0) {
+        return Err(Error::usage(format!("Voxel size {} m has to be a positive number", voxel)));
+    }
+    
+    println!("Fusing stereo depth of {}...", recording.to_string_lossy());
+    
+    let recording = Recording::load(&recording)?;
+    let fusion = Fusion::new(&recording, voxel);
+    let cloud = fusion.cloud.to_point_cloud(1);
+    
+    print!("\n{}", fusion);
+    println!("Saving {} points...", cloud.len());
+    
+    fs::write(recording.path.join("fusion.txt"), fusion.to_string())?;
+    cloud.write_ply(&recording.path.join("fused.ply"))?;
+    
+    Ok(())
+}
+
+fn panorama(recording: Option<PathBuf>, width: Option<String>) -> Result<(), Error> {
+    let recording = match recording {
+        Some(recording) => recording,
+        None => env::current_dir()?.join("dumps"),
+    };
+    let width = width.map_or(Ok(PANORAMA_WIDTH), |width| width.parse::<u32>())?;
+    if width < 2 {
+        return Err(Error::usage("The panorama has to be at least 2 pixels wide, it's half as high"));
+    }
+    
+    println!("Stitching {}...", recording.to_string_lossy());
+    
+    let recording = Recording::load(&recording)?;
+    let panorama = Panorama::new(&recording, width);
+    
+    print!("\n{}", panorama);
+    
+    // Next to the recording rather than in it, where it could be taken for one of its frames
+    let folder = recording.path.canonicalize()?;
+    let destination = match folder.file_name() {
+        Some(name) => folder.with_file_name(format!("{}_panorama", name.to_string_lossy())),
+        None => folder.join("panorama"),
+    };
+    
+    fs::write(destination.with_extension("txt"), panorama.to_string())?;
+    panorama.image.save(destination.with_extension("png"))?;
+    println!("Saved to {}.png", destination.to_string_lossy());
+    
+    Ok(())
+}
 
+fn apply(refined: Option<PathBuf>, config_path: Option<PathBuf>, yes: bool) -> Result<(), Error> {
+    let refined = refined.ok_or(Error::usage("Usage: apply <refined json> [config.json] [--yes]"))?;
+    let refined = LighthouseConfig::parse(&fs::read_to_string(refined)?)?;
+    let config_path = match (config_path, &refined.serial) {
+        (Some(config_path), _) => config_path,
+        (None, Some(serial_number)) => lighthouse_config_path(serial_number),
+        (None, None) => return Err(Error::other("Refined calibration has no serial number, pass the config.json path")),
+    };
+    
+    let patch = ConfigPatch::new(&config_path, &refined.tracked_cameras)?;
+    
+    if patch.changes.is_empty() {
+        println!("Config already has this calibration, nothing to do.");
+        return Ok(());
+    }
+    
+    println!("Changes to {}:", config_path.to_string_lossy());
+    for change in &patch.changes {
+        println!("  {}", change);
+    }
+    
+    if !yes && !confirm("Write the patched config?")? {
+        println!("Nothing written.");
+        return Ok(());
+    }
+    
+    let backup = patch.apply()?;
+    println!("Patched config written, original saved to {}", backup.to_string_lossy());
+    println!("Undo with: vrcamdump rollback {} {}", config_path.to_string_lossy(), backup.to_string_lossy());
+    
+    Ok(())
+}
 
-#[derive(Debug)]
-struct StrError(String);
+fn rollback(target: Option<String>, backup: Option<String>, yes: bool) -> Result<(), Error> {
+    let target = target.ok_or(Error::usage("Usage: rollback <serial number | config.json> [backup] [--yes]"))?;
+    let config_path = if target.ends_with(".json") { PathBuf::from(target) } else { lighthouse_config_path(&target) };
+    let backups = calibration::backups(&config_path)?;
+    
+    let backup = match backup {
+        Some(backup) => match backup.parse::<usize>() {
+            Ok(number) if (1..=backups.len()).contains(&number) => backups[number - 1].clone(),
+            Ok(_) if backups.is_empty() => return Err(Error::usage(format!("{} has no backups", config_path.to_string_lossy()))),
+            Ok(number) => return Err(Error::usage(format!("There's no backup {}, pick 1 to {}", number, backups.len()))),
+            Err(_) => PathBuf::from(backup),
+        },
+        None => {
+            println!("Backups of {}:", config_path.to_string_lossy());
+            for (number, backup) in backups.iter().enumerate() {
+                println!("  {}: {}", number + 1, backup.to_string_lossy());
+            }
+            
+            if backups.is_empty() {
+                println!("  none");
+            } else {
+                println!("Restore one with: vrcamdump rollback {} <number>", config_path.to_string_lossy());
+            }
+            
+            return Ok(());
+        },
+    };
+    
+    let current: serde_json::Value = serde_json::from_str(&fs::read_to_string(&config_path)?)?;
+    let restored: serde_json::Value = serde_json::from_str(&fs::read_to_string(&backup)?)?;
+    
+    println!("Changes to {}:", config_path.to_string_lossy());
+    for change in diff_json(&current, &restored) {
+        println!("  {}", change);
+    }
+    
+    if !yes && !confirm("Restore this backup?")? {
+        println!("Nothing written.");
+        return Ok(());
+    }
+    
+    let saved = calibration::rollback(&config_path, &backup)?;
+    println!("Restored {}, the replaced config was saved to {}", backup.to_string_lossy(), saved.to_string_lossy());
+    
+    Ok(())
+}
 
-impl StrError {
-    fn new(text: impl Into<String>) -> Box<StrError> { Box::new(StrError(text.into())) }
+fn diff(old: Option<PathBuf>, new: Option<PathBuf>) -> Result<(), Error> {
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return Err(Error::usage("Usage: diff <config.json | dump folder> <config.json | dump folder>")),
+    };
+    
+    let read = |path: &PathBuf| if path.is_dir() { fs::read_to_string(path.join("config.json")) } else { fs::read_to_string(path) };
+    let diff = ConfigDiff::new(&read(&old)?, &read(&new)?)?;
+    
+    println!("Comparing {} to {}", old.to_string_lossy(), new.to_string_lossy());
+    print!("{}", diff);
+    
+    Ok(())
 }
 
-impl Display for StrError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> { self.0.fmt(f) }
+fn explain_error(code: Option<String>) -> Result<(), Error> {
+    let code = code.ok_or_else(|| Error::usage("Usage: error <code | symbol>"))?;
+    
+    let found = ERROR_TABLES.iter()
+                            .filter_map(|table| match code.parse::<i64>() {
+                                Ok(value) => table.find(value),
+                                Err(_) => table.find_symbol(&code),
+                            }.map(|error| (table, error)))
+                            .collect::<Vec<_>>();
+    
+    if found.is_empty() {
+        return Err(Error::usage(format!("{} is not a known OpenVR error code or symbol", code)));
+    }
+    
+    for (table, error) in found {
+        println!("{} {} {}: {}", table.name, error.code, error.symbol, error.description);
+    }
+    
+    Ok(())
 }
 
-impl Error for StrError {}
+fn confirm(question: &str) -> Result<bool, Error> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
@@ -0,0 +1,353 @@
+use std::{env, fs};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use libloading::{library_filename, Library};
+use serde::Deserialize;
+use openvr_sys as sys;
+
+use crate::openvr_load::{ApplicationType, InitError};
+use crate::openvr_errors::{INIT_ERRORS, runtime_string};
+
+/// Environment variable with the path of the `openvr_api` library to use instead of searching for one.
+pub const OPENVR_API_PATH: &str = "OPENVR_API_PATH";
+
+#[cfg(windows)]
+const RUNTIME_BIN: &str = "bin/win64";
+#[cfg(not(windows))]
+const RUNTIME_BIN: &str = "bin/linux64";
+
+enum RuntimePath {
+	/// `VR_GetRuntimePath`, exported since OpenVR 1.16
+	Buffer(unsafe extern "C" fn(*mut c_char, u32, *mut u32) -> bool),
+	/// `VR_RuntimePath` of older versions
+	Static(unsafe extern "C" fn() -> *const c_char),
+}
+
+/// The entry points of an `openvr_api` library loaded at runtime.
+pub struct OpenVrApi {
+	path: PathBuf,
+	init_internal: unsafe extern "C" fn(*mut sys::EVRInitError, sys::EVRApplicationType) -> u32,
+	shutdown_internal: unsafe extern "C" fn(),
+	is_hmd_present: unsafe extern "C" fn() -> bool,
+	is_runtime_installed: unsafe extern "C" fn() -> bool,
+	runtime_path: RuntimePath,
+	is_interface_version_valid: unsafe extern "C" fn(*const c_char) -> bool,
+	get_generic_interface: unsafe extern "C" fn(*const c_char, *mut sys::EVRInitError) -> isize,
+	init_error_as_symbol: Option<unsafe extern "C" fn(sys::EVRInitError) -> *const c_char>,
+	init_error_as_description: Option<unsafe extern "C" fn(sys::EVRInitError) -> *const c_char>,
+	// The function pointers above point into it
+	_library: Library,
+}
+
+impl OpenVrApi {
+	/// Loads `openvr_api` from `path` or `OPENVR_API_PATH` if given, else from the library search path and the SteamVR
+	/// runtime folders.
+	pub fn load(path: Option<&Path>) -> Result<Self, InitError> {
+		let candidates = match path.map(PathBuf::from).or_else(|| env::var_os(OPENVR_API_PATH).map(PathBuf::from)) {
+			Some(path) => vec![path],
+			None => default_candidates(),
+		};
+		
+		let mut failures = Vec::new();
+		for candidate in candidates {
+			match OpenVrApi::open(&candidate) {
+				Ok(api) => return Ok(api),
+				Err(err) => failures.push(format!("{}: {}", candidate.to_string_lossy(), err)),
+			}
+		}
+		
+		Err(InitError::Library(failures))
+	}
+	
+	fn open(path: &Path) -> Result<Self, libloading::Error> {
+		unsafe {
+			let library = Library::new(path)?;
+			
+			let runtime_path = match library.get(b"VR_GetRuntimePath\0") {
+				Ok(symbol) => RuntimePath::Buffer(*symbol),
+				Err(_) => RuntimePath::Static(*library.get(b"VR_RuntimePath\0")?),
+			};
+			
+			Ok(OpenVrApi {
+				path: path.to_owned(),
+				init_internal: *library.get(b"VR_InitInternal\0")?,
+				shutdown_internal: *library.get(b"VR_ShutdownInternal\0")?,
+				is_hmd_present: *library.get(b"VR_IsHmdPresent\0")?,
+				is_runtime_installed: *library.get(b"VR_IsRuntimeInstalled\0")?,
+				runtime_path,
+				is_interface_version_valid: *library.get(b"VR_IsInterfaceVersionValid\0")?,
+				get_generic_interface: *library.get(b"VR_GetGenericInterface\0")?,
+				init_error_as_symbol: library.get(b"VR_GetVRInitErrorAsSymbol\0").ok().map(|symbol| *symbol),
+				init_error_as_description: library.get(b"VR_GetVRInitErrorAsEnglishDescription\0").ok().map(|symbol| *symbol),
+				_library: library,
+			})
+		}
+	}
+	
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+	
+	pub fn is_runtime_installed(&self) -> bool {
+		unsafe { (self.is_runtime_installed)() }
+	}
+	
+	pub fn runtime_path(&self) -> Option<PathBuf> {
+		let path = unsafe {
+			match self.runtime_path {
+				RuntimePath::Buffer(get_runtime_path) => {
+					let mut size = 0;
+					get_runtime_path(std::ptr::null_mut(), 0, &mut size);
+					
+					let mut buffer = vec![0u8; size.max(1) as usize];
+					if !get_runtime_path(buffer.as_mut_ptr() as *mut c_char, buffer.len() as u32, &mut size) {
+						return None;
+					}
+					
+					let end = buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len());
+					String::from_utf8(buffer[..end].to_vec()).ok()?
+				},
+				RuntimePath::Static(runtime_path) => {
+					let path = runtime_path();
+					if path.is_null() {
+						return None;
+					}
+					
+					CStr::from_ptr(path).to_str().ok()?.to_owned()
+				},
+			}
+		};
+		
+		if path.is_empty() { None } else { Some(PathBuf::from(path)) }
+	}
+	
+	pub fn is_hmd_present(&self) -> bool {
+		unsafe { (self.is_hmd_present)() }
+	}
+	
+	/// Checks what `VR_InitInternal` would otherwise fail on less clearly, or hang on while it starts SteamVR.
+	pub fn precheck(&self, ty: ApplicationType) -> Result<(), InitError> {
+		if !self.is_runtime_installed() {
+			return Err(self.init_error(sys::EVRInitError_VRInitError_Init_InstallationNotFound));
+		}
+		
+		if self.runtime_path().is_none() {
+			return Err(self.init_error(sys::EVRInitError_VRInitError_Init_PathRegistryNotFound));
+		}
+		
+		if ty != ApplicationType::Utility && !self.is_hmd_present() {
+			return Err(self.init_error(sys::EVRInitError_VRInitError_Init_HmdNotFound));
+		}
+		
+		Ok(())
+	}
+	
+	/// `code` as an error, with the symbol and description this library has for it remembered for displaying it.
+	pub fn init_error(&self, code: sys::EVRInitError) -> InitError {
+		let name = |as_name: Option<unsafe extern "C" fn(sys::EVRInitError) -> *const c_char>| {
+			as_name.and_then(|as_name| unsafe { runtime_string(as_name(code)) })
+		};
+		
+		INIT_ERRORS.remember(code as i64, name(self.init_error_as_symbol), name(self.init_error_as_description));
+		
+		InitError::OpenVR(code)
+	}
+	
+	pub unsafe fn init_internal(&self, error: &mut sys::EVRInitError, ty: ApplicationType) -> u32 {
+		(self.init_internal)(error, ty as sys::EVRApplicationType)
+	}
+	
+	pub unsafe fn shutdown_internal(&self) {
+		(self.shutdown_internal)()
+	}
+	
+	pub unsafe fn is_interface_version_valid(&self, version: &[u8]) -> bool {
+		(self.is_interface_version_valid)(version.as_ptr() as *const c_char)
+	}
+	
+	pub unsafe fn get_generic_interface(&self, version: &[u8], error: &mut sys::EVRInitError) -> isize {
+		(self.get_generic_interface)(version.as_ptr() as *const c_char, error)
+	}
+}
+
+#[derive(Deserialize)]
+struct VrPaths {
+	#[serde(default)]
+	runtime: Vec<PathBuf>,
+	#[cfg(not(windows))]
+	#[serde(default)]
+	config: Vec<PathBuf>,
+}
+
+fn default_candidates() -> Vec<PathBuf> {
+	let mut candidates = vec![PathBuf::from(library_filename("openvr_api"))];
+	
+	candidates.extend(runtime_folders().into_iter()
+	                                   .map(|runtime| runtime.join(RUNTIME_BIN).join(library_filename("openvr_api"))));
+	
+	candidates
+}
+
+/// The SteamVR runtimes registered in `openvrpaths.vrpath`, the same file `VR_RuntimePath` reads.
+fn runtime_folders() -> Vec<PathBuf> {
+	vr_paths().map(|paths| paths.runtime).unwrap_or_default()
+}
+
+/// The Steam config folders registered in `openvrpaths.vrpath`, where the lighthouse configs are.
+#[cfg(not(windows))]
+pub fn config_folders() -> Vec<PathBuf> {
+	vr_paths().map(|paths| paths.config).unwrap_or_default()
+}
+
+fn vr_paths() -> Option<VrPaths> {
+	let config = if cfg!(windows) {
+		env::var_os("LOCALAPPDATA").map(PathBuf::from)
+	} else {
+		env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+		                              .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+	};
+	
+	config.map(|config| config.join("openvr").join("openvrpaths.vrpath"))
+	      .and_then(|path| fs::read_to_string(path).ok())
+	      .and_then(|text| serde_json::from_str::<VrPaths>(text.trim_start_matches('\u{feff}')).ok())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use std::process::Command;
+	use super::*;
+	
+	/// `openvr_api` with everything this module loads, `-D` flags take parts away.
+	const STUB: &str = r#"
+#include <stdbool.h>
+#include <stdint.h>
+#include <string.h>
+
+static char table[64 * sizeof(void *)];
+
+uint32_t VR_InitInternal(int *error, int type) { *error = 0; return 1; }
+void VR_ShutdownInternal(void) {}
+bool VR_IsHmdPresent(void) {
+#ifdef NO_HMD
+	return false;
+#else
+	return true;
+#endif
+}
+bool VR_IsRuntimeInstalled(void) {
+#ifdef NO_RUNTIME
+	return false;
+#else
+	return true;
+#endif
+}
+#ifdef STATIC_RUNTIME_PATH
+const char *VR_RuntimePath(void) { return "/stub/SteamVR"; }
+#else
+bool VR_GetRuntimePath(char *buffer, uint32_t size, uint32_t *required) {
+	const char *path = "/stub/SteamVR";
+	*required = strlen(path) + 1;
+	if (size < *required) return false;
+	strcpy(buffer, path);
+	return true;
+}
+#endif
+bool VR_IsInterfaceVersionValid(const char *version) { return true; }
+#ifndef NO_GENERIC_INTERFACE
+intptr_t VR_GetGenericInterface(const char *version, int *error) { *error = 0; return (intptr_t)table; }
+#endif
+"#;
+	
+	/// Builds the stub with `defines` into its own folder, so the tests can run in parallel.
+	fn build_stub(name: &str, defines: &[&str]) -> PathBuf {
+		let folder = env::temp_dir().join(format!("vrcamdump_stub_{}_{}", std::process::id(), name));
+		fs::create_dir_all(&folder).unwrap();
+		
+		let source = folder.join("stub.c");
+		fs::write(&source, STUB).unwrap();
+		
+		let library = folder.join(library_filename("openvr_api"));
+		let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+		                     .args(["-shared", "-fPIC", "-o"])
+		                     .arg(&library)
+		                     .arg(&source)
+		                     .args(defines.iter().map(|define| format!("-D{}", define)))
+		                     .status()
+		                     .expect("A C compiler is needed to build the stub openvr_api");
+		assert!(status.success(), "Unable to build the stub openvr_api");
+		
+		library
+	}
+	
+	fn init_error(result: Result<(), InitError>) -> Option<sys::EVRInitError> {
+		match result {
+			Err(InitError::OpenVR(code)) => Some(code),
+			Err(err) => panic!("Unexpected error {}", err),
+			Ok(()) => None,
+		}
+	}
+	
+	#[test]
+	fn loads_the_stub() {
+		let library = build_stub("full", &[]);
+		let api = OpenVrApi::load(Some(&library)).unwrap();
+		
+		assert_eq!(api.path(), library.as_path());
+		assert!(api.is_runtime_installed());
+		assert!(api.is_hmd_present());
+		assert_eq!(api.runtime_path(), Some(PathBuf::from("/stub/SteamVR")));
+		assert_eq!(init_error(api.precheck(ApplicationType::Scene)), None);
+		
+		let mut error = sys::EVRInitError_VRInitError_Unknown;
+		assert_ne!(unsafe { api.get_generic_interface(b"FnTable:IVRSystem_019\0", &mut error) }, 0);
+		assert_eq!(error, sys::EVRInitError_VRInitError_None);
+	}
+	
+	#[test]
+	fn falls_back_to_the_static_runtime_path() {
+		let api = OpenVrApi::load(Some(&build_stub("static", &["STATIC_RUNTIME_PATH"]))).unwrap();
+		
+		assert_eq!(api.runtime_path(), Some(PathBuf::from("/stub/SteamVR")));
+	}
+	
+	#[test]
+	fn precheck_reports_what_is_missing() {
+		let api = OpenVrApi::load(Some(&build_stub("no_hmd", &["NO_HMD"]))).unwrap();
+		assert_eq!(init_error(api.precheck(ApplicationType::Scene)), Some(sys::EVRInitError_VRInitError_Init_HmdNotFound));
+		// Utility applications don't need a headset
+		assert_eq!(init_error(api.precheck(ApplicationType::Utility)), None);
+		
+		let api = OpenVrApi::load(Some(&build_stub("no_runtime", &["NO_RUNTIME"]))).unwrap();
+		assert_eq!(init_error(api.precheck(ApplicationType::Utility)), Some(sys::EVRInitError_VRInitError_Init_InstallationNotFound));
+	}
+	
+	#[test]
+	fn missing_symbol_is_a_library_error() {
+		let library = build_stub("missing_symbol", &["NO_GENERIC_INTERFACE"]);
+		
+		match OpenVrApi::load(Some(&library)) {
+			Err(InitError::Library(failures)) => {
+				assert_eq!(failures.len(), 1);
+				assert!(failures[0].starts_with(&*library.to_string_lossy()), "{}", failures[0]);
+				assert!(failures[0].contains("VR_GetGenericInterface"), "{}", failures[0]);
+			},
+			Err(err) => panic!("Unexpected error {}", err),
+			Ok(_) => panic!("Loaded a library without VR_GetGenericInterface"),
+		}
+	}
+	
+	#[test]
+	fn missing_library_is_a_library_error() {
+		let library = env::temp_dir().join("vrcamdump_no_such_folder").join(library_filename("openvr_api"));
+		
+		match OpenVrApi::load(Some(&library)) {
+			Err(InitError::Library(failures)) => {
+				assert_eq!(failures.len(), 1);
+				assert!(failures[0].starts_with(&*library.to_string_lossy()), "{}", failures[0]);
+			},
+			Err(err) => panic!("Unexpected error {}", err),
+			Ok(_) => panic!("Loaded a library that doesn't exist"),
+		}
+	}
+}
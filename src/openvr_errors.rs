@@ -0,0 +1,507 @@
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+use openvr_sys as sys;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorCode {
+	pub code: i64,
+	pub symbol: &'static str,
+	pub description: &'static str,
+}
+
+/// The values of one OpenVR error enum. Codes `openvr_sys` has constants for are taken from them, the newer ones are
+/// written out so codes from a newer runtime still get a readable name. What the runtime itself calls a code is asked
+/// for when the error happens and takes precedence over the table.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorTable {
+	pub name: &'static str,
+	pub codes: &'static [ErrorCode],
+}
+
+/// What the runtime called a code of a table.
+#[derive(Clone)]
+struct RuntimeName {
+	table: &'static str,
+	code: i64,
+	symbol: Option<String>,
+	description: Option<String>,
+}
+
+/// Names the runtime gave, kept since it may be shut down by the time the error is displayed.
+static RUNTIME_NAMES: Mutex<Vec<RuntimeName>> = Mutex::new(Vec::new());
+
+impl ErrorTable {
+	pub fn find(&self, code: i64) -> Option<&'static ErrorCode> {
+		self.codes.iter().find(|error| error.code == code)
+	}
+	
+	pub fn find_symbol(&self, symbol: &str) -> Option<&'static ErrorCode> {
+		self.codes.iter().find(|error| error.symbol == symbol)
+	}
+	
+	/// Remembers what the runtime calls `code`, empty names are ignored.
+	pub fn remember(&self, code: i64, symbol: Option<String>, description: Option<String>) {
+		let symbol = symbol.filter(|symbol| !symbol.is_empty());
+		let description = description.filter(|description| !description.is_empty());
+		
+		if symbol.is_none() && description.is_none() {
+			return;
+		}
+		
+		let mut names = RUNTIME_NAMES.lock().unwrap();
+		names.retain(|name| (name.table, name.code) != (self.name, code));
+		names.push(RuntimeName { table: self.name, code, symbol, description });
+	}
+	
+	fn runtime_name(&self, code: i64) -> Option<RuntimeName> {
+		RUNTIME_NAMES.lock()
+		             .unwrap()
+		             .iter()
+		             .find(|name| (name.table, name.code) == (self.name, code))
+		             .cloned()
+	}
+	
+	pub fn symbol(&self, code: i64) -> Cow<'static, str> {
+		if let Some(symbol) = self.runtime_name(code).and_then(|name| name.symbol) {
+			return symbol.into();
+		}
+		
+		match self.find(code) {
+			Some(error) => error.symbol.into(),
+			None => format!("{}({})", self.name, code).into(),
+		}
+	}
+	
+	pub fn description(&self, code: i64) -> Cow<'static, str> {
+		if let Some(description) = self.runtime_name(code).and_then(|name| name.description) {
+			return description.into();
+		}
+		
+		match self.find(code) {
+			Some(error) => error.description.into(),
+			None => "Error code unknown to this build, it may come from a newer SteamVR".into(),
+		}
+	}
+}
+
+/// A name returned by the runtime, `None` for null or invalid UTF-8.
+///
+/// # Safety
+///
+/// `name` has to be null or point to a NUL-terminated string.
+pub unsafe fn runtime_string(name: *const c_char) -> Option<String> {
+	if name.is_null() {
+		return None;
+	}
+	
+	CStr::from_ptr(name).to_str().ok().map(str::to_owned)
+}
+
+macro_rules! error_codes {
+	($($table:ident: $name:ident { $($symbol:ident = $code:expr => $description:literal,)* })*) => {
+		$(
+			#[doc = concat!("Every `", stringify!($name), "` value listed in `openvr.h`.")]
+			pub const $table: ErrorTable = ErrorTable {
+				name: stringify!($name),
+				codes: &[$(ErrorCode { code: $code as i64, symbol: stringify!($symbol), description: $description },)*],
+			};
+		)*
+		
+		pub const ERROR_TABLES: &[ErrorTable] = &[$($table,)*];
+		
+		/// Where the code of every entry of `ERROR_TABLES` came from, to check the `sys` constants against the symbols.
+		#[cfg(test)]
+		const CODE_SOURCES: &[&[&str]] = &[$(&[$(stringify!($code),)*],)*];
+	};
+}
+
+error_codes! {
+	INIT_ERRORS: EVRInitError {
+		VRInitError_None = sys::EVRInitError_VRInitError_None => "No error",
+		VRInitError_Unknown = sys::EVRInitError_VRInitError_Unknown => "Unknown error",
+		VRInitError_Init_InstallationNotFound = sys::EVRInitError_VRInitError_Init_InstallationNotFound => "SteamVR installation not found",
+		VRInitError_Init_InstallationCorrupt = sys::EVRInitError_VRInitError_Init_InstallationCorrupt => "SteamVR installation is corrupt",
+		VRInitError_Init_VRClientDLLNotFound = sys::EVRInitError_VRInitError_Init_VRClientDLLNotFound => "The vrclient library was not found",
+		VRInitError_Init_FileNotFound = sys::EVRInitError_VRInitError_Init_FileNotFound => "A required file was not found",
+		VRInitError_Init_FactoryNotFound = sys::EVRInitError_VRInitError_Init_FactoryNotFound => "The vrclient factory was not found",
+		VRInitError_Init_InterfaceNotFound = sys::EVRInitError_VRInitError_Init_InterfaceNotFound => "The requested interface version is not supported by the installed SteamVR",
+		VRInitError_Init_InvalidInterface = sys::EVRInitError_VRInitError_Init_InvalidInterface => "The interface is invalid",
+		VRInitError_Init_UserConfigDirectoryInvalid = sys::EVRInitError_VRInitError_Init_UserConfigDirectoryInvalid => "The user config directory is invalid",
+		VRInitError_Init_HmdNotFound = sys::EVRInitError_VRInitError_Init_HmdNotFound => "No headset was found",
+		VRInitError_Init_NotInitialized = sys::EVRInitError_VRInitError_Init_NotInitialized => "OpenVR is not initialized",
+		VRInitError_Init_PathRegistryNotFound = sys::EVRInitError_VRInitError_Init_PathRegistryNotFound => "The OpenVR path registry was not found",
+		VRInitError_Init_NoConfigPath = sys::EVRInitError_VRInitError_Init_NoConfigPath => "No config path was found",
+		VRInitError_Init_NoLogPath = sys::EVRInitError_VRInitError_Init_NoLogPath => "No log path was found",
+		VRInitError_Init_PathRegistryNotWritable = sys::EVRInitError_VRInitError_Init_PathRegistryNotWritable => "The OpenVR path registry is not writable",
+		VRInitError_Init_AppInfoInitFailed = sys::EVRInitError_VRInitError_Init_AppInfoInitFailed => "Application info failed to initialize",
+		VRInitError_Init_Retry = sys::EVRInitError_VRInitError_Init_Retry => "Initialization should be retried",
+		VRInitError_Init_InitCanceledByUser = sys::EVRInitError_VRInitError_Init_InitCanceledByUser => "Initialization was canceled by the user",
+		VRInitError_Init_AnotherAppLaunching = sys::EVRInitError_VRInitError_Init_AnotherAppLaunching => "Another application is launching",
+		VRInitError_Init_SettingsInitFailed = sys::EVRInitError_VRInitError_Init_SettingsInitFailed => "Settings failed to initialize",
+		VRInitError_Init_ShuttingDown = sys::EVRInitError_VRInitError_Init_ShuttingDown => "SteamVR is shutting down",
+		VRInitError_Init_TooManyObjects = sys::EVRInitError_VRInitError_Init_TooManyObjects => "Too many objects",
+		VRInitError_Init_NoServerForBackgroundApp = sys::EVRInitError_VRInitError_Init_NoServerForBackgroundApp => "SteamVR is not running for a background application",
+		VRInitError_Init_NotSupportedWithCompositor = sys::EVRInitError_VRInitError_Init_NotSupportedWithCompositor => "Not supported with the compositor",
+		VRInitError_Init_NotAvailableToUtilityApps = sys::EVRInitError_VRInitError_Init_NotAvailableToUtilityApps => "Not available to utility applications",
+		VRInitError_Init_Internal = sys::EVRInitError_VRInitError_Init_Internal => "Internal error",
+		VRInitError_Init_HmdDriverIdIsNone = sys::EVRInitError_VRInitError_Init_HmdDriverIdIsNone => "The headset driver id is none",
+		VRInitError_Init_HmdNotFoundPresenceFailed = sys::EVRInitError_VRInitError_Init_HmdNotFoundPresenceFailed => "No headset was found, the presence check failed",
+		VRInitError_Init_VRMonitorNotFound = sys::EVRInitError_VRInitError_Init_VRMonitorNotFound => "vrmonitor was not found",
+		VRInitError_Init_VRMonitorStartupFailed = sys::EVRInitError_VRInitError_Init_VRMonitorStartupFailed => "vrmonitor failed to start",
+		VRInitError_Init_LowPowerWatchdogNotSupported = sys::EVRInitError_VRInitError_Init_LowPowerWatchdogNotSupported => "The low power watchdog is not supported",
+		VRInitError_Init_InvalidApplicationType = sys::EVRInitError_VRInitError_Init_InvalidApplicationType => "Invalid application type",
+		VRInitError_Init_NotAvailableToWatchdogApps = sys::EVRInitError_VRInitError_Init_NotAvailableToWatchdogApps => "Not available to watchdog applications",
+		VRInitError_Init_WatchdogDisabledInSettings = sys::EVRInitError_VRInitError_Init_WatchdogDisabledInSettings => "The watchdog is disabled in the settings",
+		VRInitError_Init_VRDashboardNotFound = sys::EVRInitError_VRInitError_Init_VRDashboardNotFound => "The dashboard was not found",
+		VRInitError_Init_VRDashboardStartupFailed = sys::EVRInitError_VRInitError_Init_VRDashboardStartupFailed => "The dashboard failed to start",
+		VRInitError_Init_VRHomeNotFound = sys::EVRInitError_VRInitError_Init_VRHomeNotFound => "SteamVR Home was not found",
+		VRInitError_Init_VRHomeStartupFailed = sys::EVRInitError_VRInitError_Init_VRHomeStartupFailed => "SteamVR Home failed to start",
+		VRInitError_Init_RebootingBusy = sys::EVRInitError_VRInitError_Init_RebootingBusy => "SteamVR is busy rebooting",
+		VRInitError_Init_FirmwareUpdateBusy = sys::EVRInitError_VRInitError_Init_FirmwareUpdateBusy => "SteamVR is busy updating firmware",
+		VRInitError_Init_FirmwareRecoveryBusy = sys::EVRInitError_VRInitError_Init_FirmwareRecoveryBusy => "SteamVR is busy recovering firmware",
+		VRInitError_Init_USBServiceBusy = sys::EVRInitError_VRInitError_Init_USBServiceBusy => "The USB service is busy",
+		VRInitError_Init_VRWebHelperStartupFailed = sys::EVRInitError_VRInitError_Init_VRWebHelperStartupFailed => "vrwebhelper failed to start",
+		VRInitError_Init_TrackerManagerInitFailed = sys::EVRInitError_VRInitError_Init_TrackerManagerInitFailed => "The tracker manager failed to initialize",
+		VRInitError_Init_AlreadyRunning = sys::EVRInitError_VRInitError_Init_AlreadyRunning => "SteamVR is already running",
+		VRInitError_Init_FailedForVrMonitor = sys::EVRInitError_VRInitError_Init_FailedForVrMonitor => "Initialization failed for vrmonitor",
+		VRInitError_Init_PropertyManagerInitFailed = 145 => "The property manager failed to initialize",
+		VRInitError_Init_WebServerFailed = 146 => "The web server failed to start",
+		VRInitError_Init_IllegalTypeTransition = 147 => "Illegal application type transition",
+		VRInitError_Init_MismatchedRuntimes = 148 => "Mismatched runtimes",
+		VRInitError_Init_InvalidProcessId = 149 => "Invalid process id",
+		VRInitError_Init_VRServiceStartupFailed = 150 => "vrservice failed to start",
+		VRInitError_Init_PrismNeedsNewDrivers = 151 => "The compositor needs newer graphics drivers",
+		VRInitError_Init_PrismStartupTimedOut = 152 => "The compositor timed out starting up",
+		VRInitError_Init_CouldNotStartPrism = 153 => "Could not start the compositor",
+		VRInitError_Init_PrismClientInitFailed = 154 => "The compositor client failed to initialize",
+		VRInitError_Init_PrismClientStartFailed = 155 => "The compositor client failed to start",
+		VRInitError_Init_PrismExitedUnexpectedly = 156 => "The compositor exited unexpectedly",
+		VRInitError_Init_BadLuid = 157 => "Bad graphics adapter LUID",
+		VRInitError_Init_NoServerForAppContainer = 158 => "No server for the app container",
+		VRInitError_Init_DuplicateBootstrapper = 159 => "Duplicate bootstrapper",
+		VRInitError_Init_VRDashboardServicePending = 160 => "The dashboard service is pending",
+		VRInitError_Init_VRDashboardServiceTimeout = 161 => "The dashboard service timed out",
+		VRInitError_Init_VRDashboardServiceStopped = 162 => "The dashboard service stopped",
+		VRInitError_Init_VRDashboardAlreadyStarted = 163 => "The dashboard is already started",
+		VRInitError_Init_VRDashboardCopyFailed = 164 => "The dashboard copy failed",
+		VRInitError_Init_VRDashboardTokenFailure = 165 => "The dashboard token failed",
+		VRInitError_Init_VRDashboardEnvironmentFailure = 166 => "The dashboard environment failed",
+		VRInitError_Init_VRDashboardPathFailure = 167 => "The dashboard path failed",
+		VRInitError_Driver_Failed = sys::EVRInitError_VRInitError_Driver_Failed => "The driver failed",
+		VRInitError_Driver_Unknown = sys::EVRInitError_VRInitError_Driver_Unknown => "Unknown driver error",
+		VRInitError_Driver_HmdUnknown = sys::EVRInitError_VRInitError_Driver_HmdUnknown => "Unknown headset",
+		VRInitError_Driver_NotLoaded = sys::EVRInitError_VRInitError_Driver_NotLoaded => "The driver is not loaded",
+		VRInitError_Driver_RuntimeOutOfDate = sys::EVRInitError_VRInitError_Driver_RuntimeOutOfDate => "SteamVR is older than the driver",
+		VRInitError_Driver_HmdInUse = sys::EVRInitError_VRInitError_Driver_HmdInUse => "The headset is in use by another application",
+		VRInitError_Driver_NotCalibrated = sys::EVRInitError_VRInitError_Driver_NotCalibrated => "The headset is not calibrated",
+		VRInitError_Driver_CalibrationInvalid = sys::EVRInitError_VRInitError_Driver_CalibrationInvalid => "The headset calibration is invalid",
+		VRInitError_Driver_HmdDisplayNotFound = sys::EVRInitError_VRInitError_Driver_HmdDisplayNotFound => "The headset display was not found",
+		VRInitError_Driver_TrackedDeviceInterfaceUnknown = sys::EVRInitError_VRInitError_Driver_TrackedDeviceInterfaceUnknown => "Unknown tracked device interface",
+		VRInitError_Driver_HmdDriverIdOutOfBounds = sys::EVRInitError_VRInitError_Driver_HmdDriverIdOutOfBounds => "The headset driver id is out of bounds",
+		VRInitError_Driver_HmdDisplayMirrored = sys::EVRInitError_VRInitError_Driver_HmdDisplayMirrored => "The headset display is mirrored",
+		VRInitError_Driver_HmdDisplayNotFoundLaptop = sys::EVRInitError_VRInitError_Driver_HmdDisplayNotFoundLaptop => "The headset display was not found on this laptop",
+		VRInitError_Driver_PeerDriverNotInstalled = 214 => "The peer driver is not installed",
+		VRInitError_Driver_WirelessHmdNotConnected = 215 => "The wireless headset is not connected",
+		VRInitError_IPC_ServerInitFailed = sys::EVRInitError_VRInitError_IPC_ServerInitFailed => "The IPC server failed to initialize",
+		VRInitError_IPC_ConnectFailed = sys::EVRInitError_VRInitError_IPC_ConnectFailed => "Could not connect to SteamVR",
+		VRInitError_IPC_SharedStateInitFailed = sys::EVRInitError_VRInitError_IPC_SharedStateInitFailed => "The IPC shared state failed to initialize",
+		VRInitError_IPC_CompositorInitFailed = sys::EVRInitError_VRInitError_IPC_CompositorInitFailed => "The IPC compositor failed to initialize",
+		VRInitError_IPC_MutexInitFailed = sys::EVRInitError_VRInitError_IPC_MutexInitFailed => "The IPC mutex failed to initialize",
+		VRInitError_IPC_Failed = sys::EVRInitError_VRInitError_IPC_Failed => "IPC failed",
+		VRInitError_IPC_CompositorConnectFailed = sys::EVRInitError_VRInitError_IPC_CompositorConnectFailed => "Could not connect to the compositor",
+		VRInitError_IPC_CompositorInvalidConnectResponse = sys::EVRInitError_VRInitError_IPC_CompositorInvalidConnectResponse => "The compositor sent an invalid connect response",
+		VRInitError_IPC_ConnectFailedAfterMultipleAttempts = sys::EVRInitError_VRInitError_IPC_ConnectFailedAfterMultipleAttempts => "Could not connect to SteamVR after multiple attempts",
+		VRInitError_IPC_ConnectFailedAfterTargetExited = 309 => "Could not connect to SteamVR, it exited",
+		VRInitError_IPC_NamespaceUnavailable = 310 => "The IPC namespace is unavailable",
+		VRInitError_Compositor_Failed = sys::EVRInitError_VRInitError_Compositor_Failed => "The compositor failed",
+		VRInitError_Compositor_D3D11HardwareRequired = sys::EVRInitError_VRInitError_Compositor_D3D11HardwareRequired => "The compositor requires Direct3D 11 hardware",
+		VRInitError_Compositor_FirmwareRequiresUpdate = sys::EVRInitError_VRInitError_Compositor_FirmwareRequiresUpdate => "The compositor requires a firmware update",
+		VRInitError_Compositor_OverlayInitFailed = sys::EVRInitError_VRInitError_Compositor_OverlayInitFailed => "The compositor overlays failed to initialize",
+		VRInitError_Compositor_ScreenshotsInitFailed = sys::EVRInitError_VRInitError_Compositor_ScreenshotsInitFailed => "The compositor screenshots failed to initialize",
+		VRInitError_Compositor_UnableToCreateDevice = sys::EVRInitError_VRInitError_Compositor_UnableToCreateDevice => "The compositor was unable to create a graphics device",
+		VRInitError_Compositor_SharedStateIsNull = sys::EVRInitError_VRInitError_Compositor_SharedStateIsNull => "The compositor shared state is null",
+		VRInitError_Compositor_NotificationManagerIsNull = sys::EVRInitError_VRInitError_Compositor_NotificationManagerIsNull => "The compositor notification manager is null",
+		VRInitError_Compositor_ResourceManagerClientIsNull = sys::EVRInitError_VRInitError_Compositor_ResourceManagerClientIsNull => "The compositor resource manager client is null",
+		VRInitError_Compositor_MessageOverlaySharedStateInitFailure = sys::EVRInitError_VRInitError_Compositor_MessageOverlaySharedStateInitFailure => "The compositor message overlay shared state failed to initialize",
+		VRInitError_Compositor_PropertiesInterfaceIsNull = sys::EVRInitError_VRInitError_Compositor_PropertiesInterfaceIsNull => "The compositor properties interface is null",
+		VRInitError_Compositor_CreateFullscreenWindowFailed = sys::EVRInitError_VRInitError_Compositor_CreateFullscreenWindowFailed => "The compositor failed to create the fullscreen window",
+		VRInitError_Compositor_SettingsInterfaceIsNull = sys::EVRInitError_VRInitError_Compositor_SettingsInterfaceIsNull => "The compositor settings interface is null",
+		VRInitError_Compositor_FailedToShowWindow = sys::EVRInitError_VRInitError_Compositor_FailedToShowWindow => "The compositor failed to show the window",
+		VRInitError_Compositor_DistortInterfaceIsNull = sys::EVRInitError_VRInitError_Compositor_DistortInterfaceIsNull => "The compositor distortion interface is null",
+		VRInitError_Compositor_DisplayFrequencyFailure = sys::EVRInitError_VRInitError_Compositor_DisplayFrequencyFailure => "The compositor failed to set the display frequency",
+		VRInitError_Compositor_RendererInitializationFailed = sys::EVRInitError_VRInitError_Compositor_RendererInitializationFailed => "The compositor renderer failed to initialize",
+		VRInitError_Compositor_DXGIFactoryInterfaceIsNull = sys::EVRInitError_VRInitError_Compositor_DXGIFactoryInterfaceIsNull => "The compositor DXGI factory interface is null",
+		VRInitError_Compositor_DXGIFactoryCreateFailed = sys::EVRInitError_VRInitError_Compositor_DXGIFactoryCreateFailed => "The compositor failed to create the DXGI factory",
+		VRInitError_Compositor_DXGIFactoryQueryFailed = sys::EVRInitError_VRInitError_Compositor_DXGIFactoryQueryFailed => "The compositor failed to query the DXGI factory",
+		VRInitError_Compositor_InvalidAdapterDesktop = sys::EVRInitError_VRInitError_Compositor_InvalidAdapterDesktop => "The compositor found an invalid desktop adapter",
+		VRInitError_Compositor_InvalidHmdAttachment = sys::EVRInitError_VRInitError_Compositor_InvalidHmdAttachment => "The headset is attached to an invalid adapter",
+		VRInitError_Compositor_InvalidOutputDesktop = sys::EVRInitError_VRInitError_Compositor_InvalidOutputDesktop => "The compositor found an invalid desktop output",
+		VRInitError_Compositor_InvalidDeviceProvided = sys::EVRInitError_VRInitError_Compositor_InvalidDeviceProvided => "The compositor was given an invalid device",
+		VRInitError_Compositor_D3D11RendererInitializationFailed = sys::EVRInitError_VRInitError_Compositor_D3D11RendererInitializationFailed => "The compositor Direct3D 11 renderer failed to initialize",
+		VRInitError_Compositor_FailedToFindDisplayMode = sys::EVRInitError_VRInitError_Compositor_FailedToFindDisplayMode => "The compositor failed to find a display mode",
+		VRInitError_Compositor_FailedToCreateSwapChain = sys::EVRInitError_VRInitError_Compositor_FailedToCreateSwapChain => "The compositor failed to create the swap chain",
+		VRInitError_Compositor_FailedToGetBackBuffer = sys::EVRInitError_VRInitError_Compositor_FailedToGetBackBuffer => "The compositor failed to get the back buffer",
+		VRInitError_Compositor_FailedToCreateRenderTarget = sys::EVRInitError_VRInitError_Compositor_FailedToCreateRenderTarget => "The compositor failed to create the render target",
+		VRInitError_Compositor_FailedToCreateDXGI2SwapChain = sys::EVRInitError_VRInitError_Compositor_FailedToCreateDXGI2SwapChain => "The compositor failed to create the DXGI 2 swap chain",
+		VRInitError_Compositor_FailedtoGetDXGI2BackBuffer = sys::EVRInitError_VRInitError_Compositor_FailedtoGetDXGI2BackBuffer => "The compositor failed to get the DXGI 2 back buffer",
+		VRInitError_Compositor_FailedToCreateDXGI2RenderTarget = sys::EVRInitError_VRInitError_Compositor_FailedToCreateDXGI2RenderTarget => "The compositor failed to create the DXGI 2 render target",
+		VRInitError_Compositor_FailedToGetDXGIDeviceInterface = sys::EVRInitError_VRInitError_Compositor_FailedToGetDXGIDeviceInterface => "The compositor failed to get the DXGI device interface",
+		VRInitError_Compositor_SelectDisplayMode = sys::EVRInitError_VRInitError_Compositor_SelectDisplayMode => "The compositor failed to select a display mode",
+		VRInitError_Compositor_FailedToCreateNvAPIRenderTargets = sys::EVRInitError_VRInitError_Compositor_FailedToCreateNvAPIRenderTargets => "The compositor failed to create the NvAPI render targets",
+		VRInitError_Compositor_NvAPISetDisplayMode = sys::EVRInitError_VRInitError_Compositor_NvAPISetDisplayMode => "The compositor failed to set the NvAPI display mode",
+		VRInitError_Compositor_FailedToCreateDirectModeDisplay = sys::EVRInitError_VRInitError_Compositor_FailedToCreateDirectModeDisplay => "The compositor failed to create the direct mode display",
+		VRInitError_Compositor_InvalidHmdPropertyContainer = sys::EVRInitError_VRInitError_Compositor_InvalidHmdPropertyContainer => "The compositor found an invalid headset property container",
+		VRInitError_Compositor_UpdateDisplayFrequency = sys::EVRInitError_VRInitError_Compositor_UpdateDisplayFrequency => "The compositor failed to update the display frequency",
+		VRInitError_Compositor_CreateRasterizerState = sys::EVRInitError_VRInitError_Compositor_CreateRasterizerState => "The compositor failed to create the rasterizer state",
+		VRInitError_Compositor_CreateWireframeRasterizerState = sys::EVRInitError_VRInitError_Compositor_CreateWireframeRasterizerState => "The compositor failed to create the wireframe rasterizer state",
+		VRInitError_Compositor_CreateSamplerState = sys::EVRInitError_VRInitError_Compositor_CreateSamplerState => "The compositor failed to create the sampler state",
+		VRInitError_Compositor_CreateClampToBorderSamplerState = sys::EVRInitError_VRInitError_Compositor_CreateClampToBorderSamplerState => "The compositor failed to create the clamp to border sampler state",
+		VRInitError_Compositor_CreateAnisoSamplerState = sys::EVRInitError_VRInitError_Compositor_CreateAnisoSamplerState => "The compositor failed to create the anisotropic sampler state",
+		VRInitError_Compositor_CreateOverlaySamplerState = sys::EVRInitError_VRInitError_Compositor_CreateOverlaySamplerState => "The compositor failed to create the overlay sampler state",
+		VRInitError_Compositor_CreatePanoramaSamplerState = sys::EVRInitError_VRInitError_Compositor_CreatePanoramaSamplerState => "The compositor failed to create the panorama sampler state",
+		VRInitError_Compositor_CreateFontSamplerState = sys::EVRInitError_VRInitError_Compositor_CreateFontSamplerState => "The compositor failed to create the font sampler state",
+		VRInitError_Compositor_CreateNoBlendState = sys::EVRInitError_VRInitError_Compositor_CreateNoBlendState => "The compositor failed to create the no blend state",
+		VRInitError_Compositor_CreateBlendState = sys::EVRInitError_VRInitError_Compositor_CreateBlendState => "The compositor failed to create the blend state",
+		VRInitError_Compositor_CreateAlphaBlendState = sys::EVRInitError_VRInitError_Compositor_CreateAlphaBlendState => "The compositor failed to create the alpha blend state",
+		VRInitError_Compositor_CreateBlendStateMaskR = sys::EVRInitError_VRInitError_Compositor_CreateBlendStateMaskR => "The compositor failed to create the red mask blend state",
+		VRInitError_Compositor_CreateBlendStateMaskG = sys::EVRInitError_VRInitError_Compositor_CreateBlendStateMaskG => "The compositor failed to create the green mask blend state",
+		VRInitError_Compositor_CreateBlendStateMaskB = sys::EVRInitError_VRInitError_Compositor_CreateBlendStateMaskB => "The compositor failed to create the blue mask blend state",
+		VRInitError_Compositor_CreateDepthStencilState = sys::EVRInitError_VRInitError_Compositor_CreateDepthStencilState => "The compositor failed to create the depth stencil state",
+		VRInitError_Compositor_CreateDepthStencilStateNoWrite = sys::EVRInitError_VRInitError_Compositor_CreateDepthStencilStateNoWrite => "The compositor failed to create the read only depth stencil state",
+		VRInitError_Compositor_CreateDepthStencilStateNoDepth = sys::EVRInitError_VRInitError_Compositor_CreateDepthStencilStateNoDepth => "The compositor failed to create the depthless depth stencil state",
+		VRInitError_Compositor_CreateFlushTexture = sys::EVRInitError_VRInitError_Compositor_CreateFlushTexture => "The compositor failed to create the flush texture",
+		VRInitError_Compositor_CreateDistortionSurfaces = sys::EVRInitError_VRInitError_Compositor_CreateDistortionSurfaces => "The compositor failed to create the distortion surfaces",
+		VRInitError_Compositor_CreateConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateConstantBuffer => "The compositor failed to create a constant buffer",
+		VRInitError_Compositor_CreateHmdPoseConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateHmdPoseConstantBuffer => "The compositor failed to create the headset pose constant buffer",
+		VRInitError_Compositor_CreateHmdPoseStagingConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateHmdPoseStagingConstantBuffer => "The compositor failed to create the headset pose staging constant buffer",
+		VRInitError_Compositor_CreateSharedFrameInfoConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateSharedFrameInfoConstantBuffer => "The compositor failed to create the shared frame info constant buffer",
+		VRInitError_Compositor_CreateOverlayConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateOverlayConstantBuffer => "The compositor failed to create the overlay constant buffer",
+		VRInitError_Compositor_CreateSceneTextureIndexConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateSceneTextureIndexConstantBuffer => "The compositor failed to create the scene texture index constant buffer",
+		VRInitError_Compositor_CreateReadableSceneTextureIndexConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateReadableSceneTextureIndexConstantBuffer => "The compositor failed to create the readable scene texture index constant buffer",
+		VRInitError_Compositor_CreateLayerGraphicsTextureIndexConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateLayerGraphicsTextureIndexConstantBuffer => "The compositor failed to create the layer graphics texture index constant buffer",
+		VRInitError_Compositor_CreateLayerComputeTextureIndexConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateLayerComputeTextureIndexConstantBuffer => "The compositor failed to create the layer compute texture index constant buffer",
+		VRInitError_Compositor_CreateLayerComputeSceneTextureIndexConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateLayerComputeSceneTextureIndexConstantBuffer => "The compositor failed to create the layer compute scene texture index constant buffer",
+		VRInitError_Compositor_CreateComputeHmdPoseConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateComputeHmdPoseConstantBuffer => "The compositor failed to create the compute headset pose constant buffer",
+		VRInitError_Compositor_CreateGeomConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreateGeomConstantBuffer => "The compositor failed to create the geometry constant buffer",
+		VRInitError_Compositor_CreatePanelMaskConstantBuffer = sys::EVRInitError_VRInitError_Compositor_CreatePanelMaskConstantBuffer => "The compositor failed to create the panel mask constant buffer",
+		VRInitError_Compositor_CreatePixelSimUBO = sys::EVRInitError_VRInitError_Compositor_CreatePixelSimUBO => "The compositor failed to create the pixel simulation buffer",
+		VRInitError_Compositor_CreateMSAARenderTextures = sys::EVRInitError_VRInitError_Compositor_CreateMSAARenderTextures => "The compositor failed to create the MSAA render textures",
+		VRInitError_Compositor_CreateResolveRenderTextures = sys::EVRInitError_VRInitError_Compositor_CreateResolveRenderTextures => "The compositor failed to create the resolve render textures",
+		VRInitError_Compositor_CreateComputeResolveRenderTextures = sys::EVRInitError_VRInitError_Compositor_CreateComputeResolveRenderTextures => "The compositor failed to create the compute resolve render textures",
+		VRInitError_Compositor_CreateDriverDirectModeResolveTextures = sys::EVRInitError_VRInitError_Compositor_CreateDriverDirectModeResolveTextures => "The compositor failed to create the driver direct mode resolve textures",
+		VRInitError_Compositor_OpenDriverDirectModeResolveTextures = sys::EVRInitError_VRInitError_Compositor_OpenDriverDirectModeResolveTextures => "The compositor failed to open the driver direct mode resolve textures",
+		VRInitError_Compositor_CreateFallbackSyncTexture = sys::EVRInitError_VRInitError_Compositor_CreateFallbackSyncTexture => "The compositor failed to create the fallback sync texture",
+		VRInitError_Compositor_ShareFallbackSyncTexture = sys::EVRInitError_VRInitError_Compositor_ShareFallbackSyncTexture => "The compositor failed to share the fallback sync texture",
+		VRInitError_Compositor_CreateOverlayIndexBuffer = sys::EVRInitError_VRInitError_Compositor_CreateOverlayIndexBuffer => "The compositor failed to create the overlay index buffer",
+		VRInitError_Compositor_CreateOverlayVertextBuffer = sys::EVRInitError_VRInitError_Compositor_CreateOverlayVertextBuffer => "The compositor failed to create the overlay vertex buffer",
+		VRInitError_Compositor_CreateTextVertexBuffer = sys::EVRInitError_VRInitError_Compositor_CreateTextVertexBuffer => "The compositor failed to create the text vertex buffer",
+		VRInitError_Compositor_CreateTextIndexBuffer = sys::EVRInitError_VRInitError_Compositor_CreateTextIndexBuffer => "The compositor failed to create the text index buffer",
+		VRInitError_Compositor_CreateMirrorTextures = sys::EVRInitError_VRInitError_Compositor_CreateMirrorTextures => "The compositor failed to create the mirror textures",
+		VRInitError_Compositor_CreateLastFrameRenderTexture = sys::EVRInitError_VRInitError_Compositor_CreateLastFrameRenderTexture => "The compositor failed to create the last frame render texture",
+		VRInitError_Compositor_CreateMirrorOverlay = 485 => "The compositor failed to create the mirror overlay",
+		VRInitError_Compositor_FailedToCreateVirtualDisplayBackbuffer = 486 => "The compositor failed to create the virtual display back buffer",
+		VRInitError_Compositor_DisplayModeNotSupported = 487 => "The display mode is not supported",
+		VRInitError_Compositor_CreateOverlayInvalidCall = 488 => "Invalid call creating a compositor overlay",
+		VRInitError_Compositor_CreateOverlayAlreadyInitialized = 489 => "The compositor overlay is already initialized",
+		VRInitError_Compositor_FailedToCreateMailbox = 490 => "The compositor failed to create the mailbox",
+		VRInitError_Compositor_WindowInterfaceIsNull = 491 => "The compositor window interface is null",
+		VRInitError_Compositor_SystemLayerCreateInstance = 492 => "The compositor system layer failed to create an instance",
+		VRInitError_Compositor_SystemLayerCreateSession = 493 => "The compositor system layer failed to create a session",
+		VRInitError_Compositor_CreateInverseDistortUVs = 494 => "The compositor failed to create the inverse distortion UVs",
+		VRInitError_Compositor_CreateBackbufferDepth = 495 => "The compositor failed to create the back buffer depth",
+		VRInitError_Compositor_CannotDRMLeaseDisplay = 496 => "The compositor cannot lease the display through DRM",
+		VRInitError_Compositor_CannotConnectToDisplayServer = 497 => "The compositor cannot connect to the display server",
+		VRInitError_Compositor_GnomeNoDRMLeasing = 498 => "GNOME does not support DRM leasing",
+		VRInitError_Compositor_FailedToInitializeEncoder = 499 => "The compositor failed to initialize the encoder",
+		VRInitError_Compositor_CreateBlurTexture = 500 => "The compositor failed to create the blur texture",
+		VRInitError_VendorSpecific_UnableToConnectToOculusRuntime = sys::EVRInitError_VRInitError_VendorSpecific_UnableToConnectToOculusRuntime => "Unable to connect to the Oculus runtime",
+		VRInitError_VendorSpecific_WindowsNotInDevMode = sys::EVRInitError_VRInitError_VendorSpecific_WindowsNotInDevMode => "Windows is not in developer mode",
+		VRInitError_VendorSpecific_OculusLinkNotEnabled = 1002 => "Oculus Link is not enabled",
+		VRInitError_VendorSpecific_HmdFound_CantOpenDevice = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_CantOpenDevice => "Headset found, but the device can't be opened",
+		VRInitError_VendorSpecific_HmdFound_UnableToRequestConfigStart = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UnableToRequestConfigStart => "Headset found, but the config request failed",
+		VRInitError_VendorSpecific_HmdFound_NoStoredConfig = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_NoStoredConfig => "Headset found, but it has no stored config",
+		VRInitError_VendorSpecific_HmdFound_ConfigTooBig = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_ConfigTooBig => "Headset found, but its config is too big",
+		VRInitError_VendorSpecific_HmdFound_ConfigTooSmall = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_ConfigTooSmall => "Headset found, but its config is too small",
+		VRInitError_VendorSpecific_HmdFound_UnableToInitZLib = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UnableToInitZLib => "Headset found, but zlib failed to initialize",
+		VRInitError_VendorSpecific_HmdFound_CantReadFirmwareVersion = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_CantReadFirmwareVersion => "Headset found, but its firmware version can't be read",
+		VRInitError_VendorSpecific_HmdFound_UnableToSendUserDataStart = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UnableToSendUserDataStart => "Headset found, but sending user data failed to start",
+		VRInitError_VendorSpecific_HmdFound_UnableToGetUserDataStart = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UnableToGetUserDataStart => "Headset found, but reading user data failed to start",
+		VRInitError_VendorSpecific_HmdFound_UnableToGetUserDataNext = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UnableToGetUserDataNext => "Headset found, but reading user data failed",
+		VRInitError_VendorSpecific_HmdFound_UserDataAddressRange = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UserDataAddressRange => "Headset found, but the user data address is out of range",
+		VRInitError_VendorSpecific_HmdFound_UserDataError = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_UserDataError => "Headset found, but its user data is invalid",
+		VRInitError_VendorSpecific_HmdFound_ConfigFailedSanityCheck = sys::EVRInitError_VRInitError_VendorSpecific_HmdFound_ConfigFailedSanityCheck => "Headset found, but its config failed the sanity check",
+		VRInitError_VendorSpecific_OculusRuntimeBadInstall = 1114 => "The Oculus runtime installation is broken",
+		VRInitError_VendorSpecific_HmdFound_UnexpectedConfiguration_1 = 1115 => "Headset found, but its configuration is unexpected",
+		VRInitError_Steam_SteamInstallationNotFound = sys::EVRInitError_VRInitError_Steam_SteamInstallationNotFound => "Steam installation not found",
+	}
+	
+	COMPOSITOR_ERRORS: EVRCompositorError {
+		VRCompositorError_None = sys::EVRCompositorError_VRCompositorError_None => "No error",
+		VRCompositorError_RequestFailed = sys::EVRCompositorError_VRCompositorError_RequestFailed => "The request failed",
+		VRCompositorError_IncompatibleVersion = sys::EVRCompositorError_VRCompositorError_IncompatibleVersion => "Incompatible compositor version",
+		VRCompositorError_DoNotHaveFocus = sys::EVRCompositorError_VRCompositorError_DoNotHaveFocus => "The application does not have focus",
+		VRCompositorError_InvalidTexture = sys::EVRCompositorError_VRCompositorError_InvalidTexture => "Invalid texture",
+		VRCompositorError_IsNotSceneApplication = sys::EVRCompositorError_VRCompositorError_IsNotSceneApplication => "The application is not the scene application",
+		VRCompositorError_TextureIsOnWrongDevice = sys::EVRCompositorError_VRCompositorError_TextureIsOnWrongDevice => "The texture is on the wrong graphics device",
+		VRCompositorError_TextureUsesUnsupportedFormat = sys::EVRCompositorError_VRCompositorError_TextureUsesUnsupportedFormat => "The texture uses an unsupported format",
+		VRCompositorError_SharedTexturesNotSupported = sys::EVRCompositorError_VRCompositorError_SharedTexturesNotSupported => "Shared textures are not supported",
+		VRCompositorError_IndexOutOfRange = sys::EVRCompositorError_VRCompositorError_IndexOutOfRange => "Index out of range",
+		VRCompositorError_AlreadySubmitted = sys::EVRCompositorError_VRCompositorError_AlreadySubmitted => "A frame was already submitted",
+		VRCompositorError_InvalidBounds = sys::EVRCompositorError_VRCompositorError_InvalidBounds => "Invalid texture bounds",
+		VRCompositorError_AlreadySet = 110 => "Already set",
+	}
+	
+	TRACKED_CAMERA_ERRORS: EVRTrackedCameraError {
+		VRTrackedCameraError_None = sys::EVRTrackedCameraError_VRTrackedCameraError_None => "No error",
+		VRTrackedCameraError_OperationFailed = sys::EVRTrackedCameraError_VRTrackedCameraError_OperationFailed => "The operation failed",
+		VRTrackedCameraError_InvalidHandle = sys::EVRTrackedCameraError_VRTrackedCameraError_InvalidHandle => "Invalid camera handle",
+		VRTrackedCameraError_InvalidFrameHeaderVersion = sys::EVRTrackedCameraError_VRTrackedCameraError_InvalidFrameHeaderVersion => "Invalid frame header version",
+		VRTrackedCameraError_OutOfHandles = sys::EVRTrackedCameraError_VRTrackedCameraError_OutOfHandles => "Out of camera handles",
+		VRTrackedCameraError_IPCFailure = sys::EVRTrackedCameraError_VRTrackedCameraError_IPCFailure => "IPC failure",
+		VRTrackedCameraError_NotSupportedForThisDevice = sys::EVRTrackedCameraError_VRTrackedCameraError_NotSupportedForThisDevice => "The device has no camera, or it is disabled",
+		VRTrackedCameraError_SharedMemoryFailure = sys::EVRTrackedCameraError_VRTrackedCameraError_SharedMemoryFailure => "Shared memory failure",
+		VRTrackedCameraError_FrameBufferingFailure = sys::EVRTrackedCameraError_VRTrackedCameraError_FrameBufferingFailure => "Frame buffering failure",
+		VRTrackedCameraError_StreamSetupFailure = sys::EVRTrackedCameraError_VRTrackedCameraError_StreamSetupFailure => "The video stream failed to set up",
+		VRTrackedCameraError_InvalidGLTextureId = sys::EVRTrackedCameraError_VRTrackedCameraError_InvalidGLTextureId => "Invalid OpenGL texture id",
+		VRTrackedCameraError_InvalidSharedTextureHandle = sys::EVRTrackedCameraError_VRTrackedCameraError_InvalidSharedTextureHandle => "Invalid shared texture handle",
+		VRTrackedCameraError_FailedToGetGLTextureId = sys::EVRTrackedCameraError_VRTrackedCameraError_FailedToGetGLTextureId => "Failed to get the OpenGL texture id",
+		VRTrackedCameraError_SharedTextureFailure = sys::EVRTrackedCameraError_VRTrackedCameraError_SharedTextureFailure => "Shared texture failure",
+		VRTrackedCameraError_NoFrameAvailable = sys::EVRTrackedCameraError_VRTrackedCameraError_NoFrameAvailable => "No frame is available",
+		VRTrackedCameraError_InvalidArgument = sys::EVRTrackedCameraError_VRTrackedCameraError_InvalidArgument => "Invalid argument",
+		VRTrackedCameraError_InvalidFrameBufferSize = sys::EVRTrackedCameraError_VRTrackedCameraError_InvalidFrameBufferSize => "Invalid frame buffer size",
+	}
+	
+	SETTINGS_ERRORS: EVRSettingsError {
+		VRSettingsError_None = sys::EVRSettingsError_VRSettingsError_None => "No error",
+		VRSettingsError_IPCFailed = sys::EVRSettingsError_VRSettingsError_IPCFailed => "IPC failed",
+		VRSettingsError_WriteFailed = sys::EVRSettingsError_VRSettingsError_WriteFailed => "Writing the settings failed",
+		VRSettingsError_ReadFailed = sys::EVRSettingsError_VRSettingsError_ReadFailed => "Reading the settings failed",
+		VRSettingsError_JsonParseFailed = sys::EVRSettingsError_VRSettingsError_JsonParseFailed => "The settings file is not valid JSON",
+		VRSettingsError_UnsetSettingHasNoDefault = sys::EVRSettingsError_VRSettingsError_UnsetSettingHasNoDefault => "The setting is unset and has no default",
+	}
+	
+	TRACKED_PROPERTY_ERRORS: ETrackedPropertyError {
+		TrackedProp_Success = sys::ETrackedPropertyError_TrackedProp_Success => "No error",
+		TrackedProp_WrongDataType = sys::ETrackedPropertyError_TrackedProp_WrongDataType => "The property has a different data type",
+		TrackedProp_WrongDeviceClass = sys::ETrackedPropertyError_TrackedProp_WrongDeviceClass => "The property doesn't apply to this device class",
+		TrackedProp_BufferTooSmall = sys::ETrackedPropertyError_TrackedProp_BufferTooSmall => "The buffer is too small",
+		TrackedProp_UnknownProperty = sys::ETrackedPropertyError_TrackedProp_UnknownProperty => "Unknown property",
+		TrackedProp_InvalidDevice = sys::ETrackedPropertyError_TrackedProp_InvalidDevice => "Invalid device index",
+		TrackedProp_CouldNotContactServer = sys::ETrackedPropertyError_TrackedProp_CouldNotContactServer => "Could not contact SteamVR",
+		TrackedProp_ValueNotProvidedByDevice = sys::ETrackedPropertyError_TrackedProp_ValueNotProvidedByDevice => "The device does not provide this property",
+		TrackedProp_StringExceedsMaximumLength = sys::ETrackedPropertyError_TrackedProp_StringExceedsMaximumLength => "The string exceeds the maximum length",
+		TrackedProp_NotYetAvailable = sys::ETrackedPropertyError_TrackedProp_NotYetAvailable => "The property is not available yet",
+		TrackedProp_PermissionDenied = sys::ETrackedPropertyError_TrackedProp_PermissionDenied => "Permission denied",
+		TrackedProp_InvalidOperation = sys::ETrackedPropertyError_TrackedProp_InvalidOperation => "Invalid operation",
+		TrackedProp_CannotWriteToWildcards = sys::ETrackedPropertyError_TrackedProp_CannotWriteToWildcards => "Cannot write to wildcard properties",
+		TrackedProp_IPCReadFailure = sys::ETrackedPropertyError_TrackedProp_IPCReadFailure => "IPC read failure",
+		TrackedProp_OutOfMemory = 14 => "Out of memory",
+		TrackedProp_InvalidContainer = 15 => "Invalid property container",
+	}
+	
+	OVERLAY_ERRORS: EVROverlayError {
+		VROverlayError_None = sys::EVROverlayError_VROverlayError_None => "No error",
+		VROverlayError_UnknownOverlay = sys::EVROverlayError_VROverlayError_UnknownOverlay => "Unknown overlay",
+		VROverlayError_InvalidHandle = sys::EVROverlayError_VROverlayError_InvalidHandle => "Invalid overlay handle",
+		VROverlayError_PermissionDenied = sys::EVROverlayError_VROverlayError_PermissionDenied => "Permission denied",
+		VROverlayError_OverlayLimitExceeded = sys::EVROverlayError_VROverlayError_OverlayLimitExceeded => "Overlay limit exceeded",
+		VROverlayError_WrongVisibilityType = sys::EVROverlayError_VROverlayError_WrongVisibilityType => "Wrong visibility type",
+		VROverlayError_KeyTooLong = sys::EVROverlayError_VROverlayError_KeyTooLong => "The overlay key is too long",
+		VROverlayError_NameTooLong = sys::EVROverlayError_VROverlayError_NameTooLong => "The overlay name is too long",
+		VROverlayError_KeyInUse = sys::EVROverlayError_VROverlayError_KeyInUse => "The overlay key is in use",
+		VROverlayError_WrongTransformType = sys::EVROverlayError_VROverlayError_WrongTransformType => "Wrong transform type",
+		VROverlayError_InvalidTrackedDevice = sys::EVROverlayError_VROverlayError_InvalidTrackedDevice => "Invalid tracked device",
+		VROverlayError_InvalidParameter = sys::EVROverlayError_VROverlayError_InvalidParameter => "Invalid parameter",
+		VROverlayError_ThumbnailCantBeDestroyed = sys::EVROverlayError_VROverlayError_ThumbnailCantBeDestroyed => "The thumbnail can't be destroyed",
+		VROverlayError_ArrayTooSmall = sys::EVROverlayError_VROverlayError_ArrayTooSmall => "The array is too small",
+		VROverlayError_RequestFailed = sys::EVROverlayError_VROverlayError_RequestFailed => "The request failed",
+		VROverlayError_InvalidTexture = sys::EVROverlayError_VROverlayError_InvalidTexture => "Invalid texture",
+		VROverlayError_UnableToLoadFile = sys::EVROverlayError_VROverlayError_UnableToLoadFile => "Unable to load the file",
+		VROverlayError_KeyboardAlreadyInUse = sys::EVROverlayError_VROverlayError_KeyboardAlreadyInUse => "The keyboard is already in use",
+		VROverlayError_NoNeighbor = sys::EVROverlayError_VROverlayError_NoNeighbor => "No neighbor",
+		VROverlayError_TooManyMaskPrimitives = sys::EVROverlayError_VROverlayError_TooManyMaskPrimitives => "Too many mask primitives",
+		VROverlayError_BadMaskPrimitive = sys::EVROverlayError_VROverlayError_BadMaskPrimitive => "Bad mask primitive",
+		VROverlayError_TextureAlreadyLocked = sys::EVROverlayError_VROverlayError_TextureAlreadyLocked => "The texture is already locked",
+		VROverlayError_TextureLockCapacityReached = sys::EVROverlayError_VROverlayError_TextureLockCapacityReached => "Texture lock capacity reached",
+		VROverlayError_TextureNotLocked = sys::EVROverlayError_VROverlayError_TextureNotLocked => "The texture is not locked",
+		VROverlayError_TimedOut = 34 => "Timed out",
+	}
+	
+	SCREENSHOT_ERRORS: EVRScreenshotError {
+		VRScreenshotError_None = sys::EVRScreenshotError_VRScreenshotError_None => "No error",
+		VRScreenshotError_RequestFailed = sys::EVRScreenshotError_VRScreenshotError_RequestFailed => "The request failed",
+		VRScreenshotError_IncompatibleVersion = sys::EVRScreenshotError_VRScreenshotError_IncompatibleVersion => "Incompatible screenshot version",
+		VRScreenshotError_NotFound = sys::EVRScreenshotError_VRScreenshotError_NotFound => "Screenshot not found",
+		VRScreenshotError_BufferTooSmall = sys::EVRScreenshotError_VRScreenshotError_BufferTooSmall => "The buffer is too small",
+		VRScreenshotError_ScreenshotAlreadyInProgress = sys::EVRScreenshotError_VRScreenshotError_ScreenshotAlreadyInProgress => "A screenshot is already in progress",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn codes_round_trip_through_symbols() {
+		for table in ERROR_TABLES {
+			for error in table.codes {
+				let symbol = table.symbol(error.code);
+				assert_eq!(symbol, error.symbol);
+				assert_eq!(table.find_symbol(&symbol).map(|found| found.code), Some(error.code), "{}", symbol);
+			}
+		}
+	}
+	
+	#[test]
+	fn codes_and_symbols_are_unique() {
+		for table in ERROR_TABLES {
+			for (i, error) in table.codes.iter().enumerate() {
+				assert!(table.codes[..i].iter().all(|other| other.code != error.code), "{} lists {} twice", table.name, error.code);
+				assert!(table.codes[..i].iter().all(|other| other.symbol != error.symbol), "{} lists {} twice", table.name, error.symbol);
+			}
+		}
+	}
+	
+	#[test]
+	fn sys_constants_belong_to_their_symbols() {
+		assert_eq!(ERROR_TABLES.len(), CODE_SOURCES.len());
+		
+		for (table, sources) in ERROR_TABLES.iter().zip(CODE_SOURCES) {
+			for (error, source) in table.codes.iter().zip(sources.iter()) {
+				let source = source.replace(' ', "");
+				if source.starts_with("sys::") {
+					assert_eq!(source, format!("sys::{}_{}", table.name, error.symbol));
+				} else {
+					// Written out codes are the ones openvr_sys doesn't have yet
+					assert!(source.parse::<i64>().is_ok(), "{}", source);
+				}
+			}
+		}
+	}
+	
+	#[test]
+	fn unknown_codes_keep_their_number() {
+		assert_eq!(INIT_ERRORS.symbol(99999), "EVRInitError(99999)");
+		assert!(INIT_ERRORS.find(99999).is_none());
+	}
+	
+	#[test]
+	fn runtime_names_take_precedence() {
+		let table = ErrorTable {
+			name: "TestError",
+			codes: &[ErrorCode { code: 1, symbol: "TestError_Known", description: "Known" }],
+		};
+		
+		table.remember(1, Some("TestError_Renamed".into()), None);
+		table.remember(2, Some("TestError_New".into()), Some("New".into()));
+		table.remember(3, Some(String::new()), None);
+		
+		assert_eq!(table.symbol(1), "TestError_Renamed");
+		assert_eq!(table.description(1), "Known");
+		assert_eq!(table.symbol(2), "TestError_New");
+		assert_eq!(table.description(2), "New");
+		assert_eq!(table.symbol(3), "TestError(3)");
+	}
+}
@@ -1,8 +1,16 @@
 use std::{error, fmt};
-use std::ffi::CStr;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use openvr_sys as sys;
 
+use crate::openvr_errors::INIT_ERRORS;
+use crate::openvr_api::OpenVrApi;
+use crate::system::System;
+use crate::tracked_camera::TrackedCamera;
+use crate::compositor::Compositor;
+use crate::settings::Settings;
+
 pub type TrackedDeviceIndex = sys::TrackedDeviceIndex_t;
 pub type TrackedCameraHandle = sys::TrackedCameraHandle_t;
 pub type TrackedDeviceProperty = sys::TrackedDeviceProperty;
@@ -29,78 +37,335 @@ pub enum ApplicationType {
 	Bootstrapper = sys::EVRApplicationType_VRApplication_Bootstrapper as isize,
 }
 
+/// The space poses are given in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TrackingUniverseOrigin {
+	/// Relative to the seated zero pose
+	Seated = sys::ETrackingUniverseOrigin_TrackingUniverseSeated as isize,
+	/// Relative to the play area set up in room setup
+	Standing = sys::ETrackingUniverseOrigin_TrackingUniverseStanding as isize,
+	/// Relative to the origin of the tracking system, not adjusted by room setup
+	RawAndUncalibrated = sys::ETrackingUniverseOrigin_TrackingUniverseRawAndUncalibrated as isize,
+}
+
+impl TrackingUniverseOrigin {
+	pub fn from_raw(origin: sys::ETrackingUniverseOrigin) -> Option<Self> {
+		match origin {
+			sys::ETrackingUniverseOrigin_TrackingUniverseSeated => Some(TrackingUniverseOrigin::Seated),
+			sys::ETrackingUniverseOrigin_TrackingUniverseStanding => Some(TrackingUniverseOrigin::Standing),
+			sys::ETrackingUniverseOrigin_TrackingUniverseRawAndUncalibrated => Some(TrackingUniverseOrigin::RawAndUncalibrated),
+			_ => None,
+		}
+	}
+	
+	/// `seated`, `standing` or `raw`, as given on the command line.
+	pub fn parse(name: &str) -> Option<Self> {
+		match name {
+			"seated" => Some(TrackingUniverseOrigin::Seated),
+			"standing" => Some(TrackingUniverseOrigin::Standing),
+			"raw" => Some(TrackingUniverseOrigin::RawAndUncalibrated),
+			_ => None,
+		}
+	}
+	
+	pub fn name(&self) -> &'static str {
+		match self {
+			TrackingUniverseOrigin::Seated => "seated",
+			TrackingUniverseOrigin::Standing => "standing",
+			TrackingUniverseOrigin::RawAndUncalibrated => "raw",
+		}
+	}
+}
+
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
 
-pub struct Context;
+/// A running OpenVR session. Clones share it, and OpenVR is shut down once the last clone, including the ones held by
+/// the interfaces, is dropped. After that a new `Context` can be created, for example when SteamVR was restarted.
+#[derive(Clone)]
+pub struct Context(Arc<Session>);
+
+/// Function table addresses by the version asked for first, with the version the runtime had.
+type Tables = HashMap<&'static [u8], (usize, &'static [u8])>;
+
+struct Session {
+	api: OpenVrApi,
+	ty: ApplicationType,
+	tables: Mutex<Tables>,
+}
 
 impl Context {
 	pub fn new(ty: ApplicationType) -> Result<Self, InitError> {
+		Context::with_api(OpenVrApi::load(None)?, ty)
+	}
+	
+	pub fn with_api(api: OpenVrApi, ty: ApplicationType) -> Result<Self, InitError> {
 		if INITIALIZED.swap(true, Ordering::Acquire) {
-			panic!("OpenVR has already been initialized!");
+			return Err(InitError::AlreadyInitialized);
 		}
 		
-		unsafe {
-			let mut error = sys::EVRInitError_VRInitError_None;
-			sys::VR_InitInternal(&mut error, ty as sys::EVRApplicationType);
-			
-			if error != sys::EVRInitError_VRInitError_None {
-				return Err(InitError(error));
-			}
-			
-			if !sys::VR_IsInterfaceVersionValid(sys::IVRSystem_Version.as_ptr() as *const i8) {
-				sys::VR_ShutdownInternal();
-				return Err(InitError(
-					sys::EVRInitError_VRInitError_Init_InterfaceNotFound,
-				));
-			}
+		if let Err(err) = init(&api, ty) {
+			INITIALIZED.store(false, Ordering::Release);
+			return Err(err);
 		}
 		
-		Ok(Context)
+		Ok(Context(Arc::new(Session {
+			api,
+			ty,
+			tables: Mutex::new(HashMap::new()),
+		})))
+	}
+	
+	/// Shuts this session down and starts a new one of the same type from the same library. Fails with
+	/// `AlreadyInitialized` while anything else still holds the old context.
+	pub fn reinit(self) -> Result<Self, InitError> {
+		let ty = self.0.ty;
+		let path = self.0.api.path().to_owned();
+		drop(self);
+		
+		Context::with_api(OpenVrApi::load(Some(&path))?, ty)
+	}
+	
+	pub fn api(&self) -> &OpenVrApi {
+		&self.0.api
+	}
+	
+	pub fn application_type(&self) -> ApplicationType {
+		self.0.ty
+	}
+	
+	/// Loads the function table of the first of `versions` the runtime has, or returns the one this session bound
+	/// before, together with the version that was bound. `versions` starts with the one `openvr_sys` was built with,
+	/// the rest must be older versions whose tables share the layout of the functions `T` is used for. The table is
+	/// only valid while the session is, so it can't outlive this context.
+	pub fn load<T>(&self, versions: &[&'static [u8]]) -> Result<(&T, &'static str), InitError> {
+		let mut tables = self.0.tables.lock().unwrap();
+		
+		let (table, version) = match tables.get(versions[0]) {
+			Some(&(table, version)) => (table as *const T, version),
+			None => {
+				let (table, version) = negotiate::<T>(&self.0.api, versions)?;
+				tables.insert(versions[0], (table as usize, version));
+				(table, version)
+			},
+		};
+		
+		Ok(unsafe { (&*table, version_name(version)) })
+	}
+	
+	/// The interfaces this tool uses, their function tables are loaded on first use and shared by every clone.
+	pub fn interfaces(&self) -> Result<Interfaces, InitError> {
+		Ok(Interfaces {
+			system: System::new(self)?,
+			tracked_camera: TrackedCamera::new(self)?,
+			compositor: Compositor::new(self)?,
+			settings: Settings::new(self)?,
+		})
+	}
+	
+	/// Which interface versions the runtime bound, `None` for the ones it has no compatible version of.
+	pub fn capabilities(&self) -> Capabilities {
+		let tracked_camera = TrackedCamera::new(self).ok();
+		
+		Capabilities {
+			system: System::new(self).ok().map(|system| system.version()),
+			tracked_camera: tracked_camera.as_ref().map(|camera| camera.version()),
+			compositor: Compositor::new(self).ok().map(|compositor| compositor.version()),
+			settings: Settings::new(self).ok().map(|settings| settings.version()),
+			camera_tracking_space: tracked_camera.is_some_and(|camera| camera.has_tracking_space()),
+		}
 	}
 }
 
-impl Drop for Context {
+impl Drop for Session {
 	fn drop(&mut self) {
 		unsafe {
-			sys::VR_ShutdownInternal();
+			self.api.shutdown_internal();
 			INITIALIZED.store(false, Ordering::Release);
 		}
 	}
 }
 
-pub fn load<T>(suffix: &[u8]) -> Result<*const T, InitError> {
+#[derive(Clone)]
+pub struct Interfaces {
+	pub system: System,
+	pub tracked_camera: TrackedCamera,
+	pub compositor: Compositor,
+	pub settings: Settings,
+}
+
+fn init(api: &OpenVrApi, ty: ApplicationType) -> Result<(), InitError> {
+	api.precheck(ty)?;
+	
+	unsafe {
+		let mut error = sys::EVRInitError_VRInitError_None;
+		api.init_internal(&mut error, ty);
+		
+		if error != sys::EVRInitError_VRInitError_None {
+			return Err(api.init_error(error));
+		}
+		
+		if !crate::system::VERSIONS.iter().any(|version| api.is_interface_version_valid(version)) {
+			api.shutdown_internal();
+			return Err(api.init_error(sys::EVRInitError_VRInitError_Init_InterfaceNotFound));
+		}
+	}
+	
+	Ok(())
+}
+
+fn load<T>(api: &OpenVrApi, suffix: &[u8]) -> Result<*const T, InitError> {
 	let mut magic = Vec::from(b"FnTable:".as_ref());
 	magic.extend(suffix);
 	let mut error = sys::EVRInitError_VRInitError_None;
-	let result = unsafe { sys::VR_GetGenericInterface(magic.as_ptr() as *const i8, &mut error) };
+	let result = unsafe { api.get_generic_interface(&magic, &mut error) };
 	if error != sys::EVRInitError_VRInitError_None {
-		return Err(InitError(
-			sys::EVRInitError_VRInitError_Init_InterfaceNotFound,
-		));
+		return Err(api.init_error(error));
+	}
+	if result == 0 {
+		return Err(api.init_error(sys::EVRInitError_VRInitError_Init_InterfaceNotFound));
 	}
 	Ok(result as *const T)
 }
 
-pub struct InitError(pub sys::EVRInitError);
+/// Binds the first of `versions` the runtime has. When none of them are there, the error of the first one is kept.
+fn negotiate<T>(api: &OpenVrApi, versions: &[&'static [u8]]) -> Result<(*const T, &'static [u8]), InitError> {
+	let mut first_error = None;
+	
+	for &version in versions {
+		match load::<T>(api, version) {
+			Ok(table) => return Ok((table, version)),
+			Err(err) => { first_error.get_or_insert(err); },
+		}
+	}
+	
+	Err(first_error.unwrap_or_else(|| api.init_error(sys::EVRInitError_VRInitError_Init_InterfaceNotFound)))
+}
+
+/// `b"IVRSystem_019\0"` as `"IVRSystem_019"`.
+pub(crate) fn version_name(version: &'static [u8]) -> &'static str {
+	std::str::from_utf8(version).unwrap_or("")
+	                            .trim_end_matches('\0')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+	pub system: Option<&'static str>,
+	pub tracked_camera: Option<&'static str>,
+	pub compositor: Option<&'static str>,
+	pub settings: Option<&'static str>,
+	/// `GetCameraTrackingSpace` and `SetCameraTrackingSpace`, added in `IVRTrackedCamera_006`
+	pub camera_tracking_space: bool,
+}
+
+impl fmt::Display for Capabilities {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let interfaces = [self.system, self.tracked_camera, self.compositor, self.settings];
+		let bound = interfaces.iter()
+		                      .map(|version| version.unwrap_or("missing"))
+		                      .collect::<Vec<_>>();
+		
+		write!(f, "System {}, tracked camera {}, compositor {}, settings {}", bound[0], bound[1], bound[2], bound[3])?;
+		
+		if !self.camera_tracking_space {
+			write!(f, ", no camera tracking space")?;
+		}
+		
+		Ok(())
+	}
+}
+
+pub enum InitError {
+	OpenVR(sys::EVRInitError),
+	/// Another `Context` is still alive.
+	AlreadyInitialized,
+	/// `openvr_api` couldn't be loaded, with why for every path that was tried.
+	Library(Vec<String>),
+}
+
+impl InitError {
+	/// What the user can do about the common failures.
+	pub fn hint(&self) -> Option<&'static str> {
+		let code = match self {
+			InitError::OpenVR(code) => *code,
+			InitError::AlreadyInitialized => return Some("Drop every other OpenVR context and interface before initializing again"),
+			InitError::Library(_) => return Some("Install SteamVR and run it once, or set OPENVR_API_PATH to the openvr_api library"),
+		};
+		
+		match code {
+			sys::EVRInitError_VRInitError_Init_HmdNotFound |
+			sys::EVRInitError_VRInitError_Init_HmdNotFoundPresenceFailed => Some("Connect the headset and start SteamVR"),
+			sys::EVRInitError_VRInitError_Init_NoServerForBackgroundApp => Some("Start SteamVR first"),
+			sys::EVRInitError_VRInitError_Init_InstallationNotFound |
+			sys::EVRInitError_VRInitError_Init_VRClientDLLNotFound |
+			sys::EVRInitError_VRInitError_Init_PathRegistryNotFound => Some("Install SteamVR from Steam and run it once"),
+			sys::EVRInitError_VRInitError_Init_InterfaceNotFound => Some("Update SteamVR"),
+			sys::EVRInitError_VRInitError_IPC_ConnectFailed |
+			sys::EVRInitError_VRInitError_IPC_ConnectFailedAfterMultipleAttempts => Some("Restart SteamVR"),
+			sys::EVRInitError_VRInitError_Driver_HmdDisplayNotFound => Some("Check the headset cables and the link box power"),
+			_ => None,
+		}
+	}
+}
 
 impl error::Error for InitError {}
 
 impl fmt::Debug for InitError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		let msg = unsafe { CStr::from_ptr(sys::VR_GetVRInitErrorAsSymbol(self.0)) };
-		f.pad(
-			msg.to_str()
-			   .expect("OpenVR init error symbol was not valid UTF-8"),
-		)
+		match self {
+			InitError::OpenVR(code) => f.pad(&INIT_ERRORS.symbol(*code as i64)),
+			InitError::AlreadyInitialized => f.pad("AlreadyInitialized"),
+			InitError::Library(_) => f.pad("Library"),
+		}
 	}
 }
 
 impl fmt::Display for InitError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		let msg = unsafe { CStr::from_ptr(sys::VR_GetVRInitErrorAsEnglishDescription(self.0)) };
-		f.pad(
-			msg.to_str()
-			   .expect("OpenVR init error description was not valid UTF-8")
-		)
+		match self {
+			InitError::OpenVR(code) => f.pad(&format!("{} ({})", INIT_ERRORS.description(*code as i64), INIT_ERRORS.symbol(*code as i64))),
+			InitError::AlreadyInitialized => f.pad("OpenVR is already initialized by another context"),
+			InitError::Library(failures) => f.pad(&format!("Unable to load the OpenVR API library, tried {}", failures.join("; "))),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const INTERFACES: &[(&str, &[&[u8]])] = &[
+		("system", crate::system::VERSIONS),
+		("tracked camera", crate::tracked_camera::VERSIONS),
+		("compositor", crate::compositor::VERSIONS),
+		("settings", crate::settings::VERSIONS),
+	];
+	
+	#[test]
+	fn versions_start_with_the_compiled_one() {
+		let compiled: [&[u8]; 4] = [sys::IVRSystem_Version, sys::IVRTrackedCamera_Version, sys::IVRCompositor_Version, sys::IVRSettings_Version];
+		
+		for (&(name, versions), compiled) in INTERFACES.iter().zip(compiled.iter()) {
+			assert_eq!(versions[0], *compiled, "{}", name);
+		}
+	}
+	
+	#[test]
+	fn versions_have_no_duplicates() {
+		for &(name, versions) in INTERFACES {
+			for (i, version) in versions.iter().enumerate() {
+				assert!(!versions[..i].contains(version), "{} lists {} twice", name, version_name(version));
+			}
+		}
+	}
+	
+	#[test]
+	fn versions_are_nul_terminated_names() {
+		for &(name, versions) in INTERFACES {
+			for version in versions.iter() {
+				assert_eq!(version.last(), Some(&0), "{}", name);
+				assert!(version_name(version).starts_with("IVR"), "{}", name);
+				assert!(!version_name(version).contains('\0'), "{}", name);
+			}
+		}
 	}
 }
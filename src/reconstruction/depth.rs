@@ -0,0 +1,103 @@
+use std::fmt::{self, Display, Formatter};
+use image::{ImageBuffer, Luma, RgbaImage};
+use image::imageops::{self, FilterType};
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+
+use super::PointCloud;
+use crate::calibration::{LighthouseConfig, StereoRig, Rectification, RECTIFIED_FOV};
+use crate::vision::{DisparityMap, semi_global_matching, to_gray};
+
+/// Camera images are shrunk by this much before matching, which keeps the disparities of anything further than about
+/// 25 cm within `SGM_DISPARITIES` and the matching fast.
+pub const DEPTH_IMAGE_SCALE: u32 = 2;
+/// Smaller disparities are too far away to give useful points, in rectified pixels.
+pub const MIN_POINT_DISPARITY: f32 = 1.0;
+
+pub type DepthImage = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+/// Dense depth of the left rectified image of a frame.
+pub struct StereoDepth {
+	pub rig: StereoRig,
+	pub rectification: Rectification,
+	/// Left rectified image, disparities and depths are per pixel of it.
+	pub image: RgbaImage,
+	pub disparity: DisparityMap,
+	/// Distance along the rectified optical axis, meters, row major.
+	pub depth: Vec<Option<f32>>,
+}
+
+impl StereoDepth {
+	/// Matches the per-camera images of a frame, see `vision::split_frame`.
+	pub fn new(config: &LighthouseConfig, images: &[RgbaImage]) -> Option<StereoDepth> {
+		let (first, second) = match images {
+			[first, second] => (first, second),
+			_ => return None,
+		};
+		
+		let (width, height) = (first.width() / DEPTH_IMAGE_SCALE, first.height() / DEPTH_IMAGE_SCALE);
+		let rig = StereoRig::from_config(config, width, height)?;
+		let rectification = Rectification::new(&rig, RECTIFIED_FOV);
+		
+		let images = [first, second].iter()
+		                            .enumerate()
+		                            .map(|(index, image)| rectification.remap(&rig, index, &imageops::resize(*image, width, height, FilterType::Triangle)))
+		                            .collect::<Vec<_>>();
+		let masks = [rectification.valid_mask(&rig, 0), rectification.valid_mask(&rig, 1)];
+		
+		let disparity = semi_global_matching(&to_gray(&images[0]), &to_gray(&images[1]), Some(&masks[0]), Some(&masks[1]));
+		let focal = rectification.camera.focal.x as f32;
+		let depth = disparity.values.iter()
+		                            .map(|value| value.filter(|&disparity| disparity >= MIN_POINT_DISPARITY)
+		                                              .map(|disparity| focal * rectification.baseline as f32 / disparity))
+		                            .collect();
+		
+		Some(StereoDepth { rig, rectification, image: images[0].clone(), disparity, depth })
+	}
+	
+	/// Depth in millimeters, zero where it's unknown.
+	pub fn depth_image(&self) -> DepthImage {
+		DepthImage::from_fn(self.disparity.width, self.disparity.height, |x, y| {
+			Luma([self.depth[(y * self.disparity.width + x) as usize].map_or(0, |depth| (depth * 1000.0).round().min(u16::MAX as f32) as u16)])
+		})
+	}
+	
+	/// Points in head space, colored from the left image.
+	pub fn point_cloud(&self) -> PointCloud {
+		let camera = &self.rectification.camera;
+		let head_from_rectified = self.rig.head_from_camera[0] * UnitQuaternion::from_rotation_matrix(&self.rectification.rotations[0].inverse());
+		let mut cloud = PointCloud::default();
+		
+		for (index, depth) in self.depth.iter().enumerate() {
+			let depth = match depth {
+				Some(depth) => *depth as f64,
+				None => continue,
+			};
+			
+			let (x, y) = (index as u32 % camera.width, index as u32 / camera.width);
+			let point = Vector3::new((x as f64 + 0.5 - camera.center.x) / camera.focal.x, (y as f64 + 0.5 - camera.center.y) / camera.focal.y, 1.0) * depth;
+			let color = self.image.get_pixel(x, y);
+			
+			cloud.push((head_from_rectified * Point3::from(point)).cast::<f32>(), [color[0], color[1], color[2]]);
+		}
+		
+		cloud
+	}
+}
+
+impl Display for StereoDepth {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		let mut depths = self.depth.iter().flatten().copied().collect::<Vec<_>>();
+		depths.sort_by(|a, b| a.total_cmp(b));
+		
+		writeln!(f, "Rectified {}x{}, baseline {:.4} m, focal {:.2} px",
+		         self.disparity.width, self.disparity.height, self.rectification.baseline, self.rectification.camera.focal.x)?;
+		writeln!(f, "Matched {} of {} pixels ({:.1}%)", self.disparity.valid_count(), self.depth.len(),
+		         self.disparity.valid_count() as f64 / self.depth.len() as f64 * 100.0)?;
+		
+		if let (Some(near), Some(far)) = (depths.first(), depths.last()) {
+			writeln!(f, "Depth: {:.3} m to {:.3} m, median {:.3} m", near, far, depths[depths.len() / 2])?;
+		}
+		
+		Ok(())
+	}
+}
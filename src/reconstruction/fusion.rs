@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use nalgebra::{Point3, Vector3};
+
+use super::{PointCloud, StereoDepth};
+use crate::calibration::Recording;
+
+/// Side of the voxels the fused points are averaged in, meters.
+pub const FUSION_VOXEL: f64 = 0.01;
+/// Points further than this from the headset are dropped, stereo depth is too noisy there to be worth fusing, meters.
+pub const MAX_FUSION_DISTANCE: f32 = 4.0;
+
+#[derive(Debug, Clone, Default)]
+struct Voxel {
+	sum: Vector3<f64>,
+	color: [u32; 3],
+	count: u32,
+}
+
+/// Points of many frames averaged per voxel, in standing tracking space.
+#[derive(Debug, Clone)]
+pub struct VoxelCloud {
+	pub voxel: f64,
+	voxels: HashMap<[i32; 3], Voxel>,
+}
+
+impl VoxelCloud {
+	pub fn new(voxel: f64) -> VoxelCloud {
+		VoxelCloud { voxel, voxels: HashMap::new() }
+	}
+	
+	pub fn add(&mut self, cloud: &PointCloud) {
+		for (point, color) in cloud.points.iter().zip(&cloud.colors) {
+			let key = [0, 1, 2].map(|axis| (point[axis] as f64 / self.voxel).floor() as i32);
+			let voxel = self.voxels.entry(key).or_default();
+			
+			voxel.sum += point.coords.cast::<f64>();
+			for (sum, value) in voxel.color.iter_mut().zip(color) {
+				*sum += *value as u32;
+			}
+			voxel.count += 1;
+		}
+	}
+	
+	pub fn len(&self) -> usize {
+		self.voxels.len()
+	}
+	
+	/// One point per voxel with at least `min_count` points in it.
+	pub fn to_point_cloud(&self, min_count: u32) -> PointCloud {
+		let mut cloud = PointCloud::default();
+		
+		for voxel in self.voxels.values().filter(|voxel| voxel.count >= min_count) {
+			let point = voxel.sum / voxel.count as f64;
+			cloud.push(Point3::from(point.cast::<f32>()), voxel.color.map(|channel| (channel / voxel.count) as u8));
+		}
+		
+		cloud
+	}
+}
+
+/// How one frame went into the fused cloud.
+#[derive(Debug, Clone)]
+pub struct FusedFrame {
+	pub frame: usize,
+	pub result: Result<usize, String>,
+}
+
+/// Stereo depth of every frame with a tracked pose, fused in standing tracking space.
+pub struct Fusion {
+	pub serial: String,
+	pub cloud: VoxelCloud,
+	pub frames: Vec<FusedFrame>,
+}
+
+impl Fusion {
+	pub fn new(recording: &Recording, voxel: f64) -> Fusion {
+		let mut cloud = VoxelCloud::new(voxel);
+		let mut frames = vec![];
+		
+		for (index, frame) in recording.frames.iter().enumerate() {
+			let result = (|| {
+				let pose = frame.pose.ok_or_else(|| "no valid headset pose".to_string())?;
+				let images = frame.camera_images().map_err(|err| err.to_string())?;
+				let depth = StereoDepth::new(&recording.config, &images).ok_or_else(|| "config needs two cameras with extrinsics".to_string())?;
+				
+				let mut points = depth.point_cloud();
+				points.retain(|point| point.coords.norm() <= MAX_FUSION_DISTANCE);
+				points.transform(&pose);
+				cloud.add(&points);
+				
+				Ok::<_, String>(points.len())
+			})();
+			
+			frames.push(FusedFrame { frame: index, result });
+		}
+		
+		Fusion { serial: recording.serial().to_string(), cloud, frames }
+	}
+}
+
+impl Display for Fusion {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Headset {}: {} frames fused in {} voxels of {:.1} cm",
+		         self.serial, self.frames.iter().filter(|frame| frame.result.is_ok()).count(), self.cloud.len(), self.cloud.voxel * 100.0)?;
+		writeln!(f)?;
+		
+		for frame in &self.frames {
+			match &frame.result {
+				Ok(points) => writeln!(f, "Frame {}: {} points", frame.frame, points)?,
+				Err(err) => writeln!(f, "Frame {}: skipped, {}", frame.frame, err)?,
+			}
+		}
+		
+		Ok(())
+	}
+}
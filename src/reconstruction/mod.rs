@@ -0,0 +1,9 @@
+mod point_cloud;
+mod depth;
+mod fusion;
+mod panorama;
+
+pub use point_cloud::*;
+pub use depth::*;
+pub use fusion::*;
+pub use panorama::*;
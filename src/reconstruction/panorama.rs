@@ -0,0 +1,112 @@
+use std::f64::consts::PI;
+use std::fmt::{self, Display, Formatter};
+use image::{Rgba, RgbaImage};
+use nalgebra::Vector3;
+
+use crate::calibration::{Recording, StereoRig};
+use crate::vision::sample_bilinear;
+
+/// Default panorama width, the height is half of it.
+pub const PANORAMA_WIDTH: u32 = 4096;
+/// Frames taken with the headset further than this from its mean position don't share a single viewpoint, so near objects won't
+/// line up even with a perfect calibration, meters.
+pub const MAX_PANORAMA_SPREAD: f64 = 0.1;
+pub const PANORAMA_BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Equirectangular panorama in standing tracking space, longitude 0 looks down -Z and +Y is up.
+///
+/// Each pixel comes from the camera image that sees it closest to its optical axis, without any blending or feature
+/// based alignment, so seams show the rotation error of the calibration and the tracking directly.
+pub struct Panorama {
+	pub serial: String,
+	pub image: RgbaImage,
+	/// Frames with a valid pose that went into the panorama.
+	pub frames: Vec<usize>,
+	pub skipped: Vec<(usize, String)>,
+	/// Largest distance of the headset from its mean position over the frames, meters.
+	pub spread: f64,
+	/// Share of the panorama covered by some camera.
+	pub coverage: f64,
+}
+
+impl Panorama {
+	pub fn new(recording: &Recording, width: u32) -> Panorama {
+		let mut sources = vec![];
+		let mut frames = vec![];
+		let mut skipped = vec![];
+		let mut positions = vec![];
+		
+		for (index, frame) in recording.frames.iter().enumerate() {
+			let pose = match frame.pose {
+				Some(pose) => pose,
+				None => { skipped.push((index, "no valid headset pose".to_string())); continue },
+			};
+			
+			let images = match frame.camera_images() {
+				Ok(images) => images,
+				Err(err) => { skipped.push((index, err.to_string())); continue },
+			};
+			
+			let rig = match images.first().and_then(|image| StereoRig::from_config(&recording.config, image.width(), image.height())) {
+				Some(rig) => rig,
+				None => { skipped.push((index, "config needs two cameras with extrinsics".to_string())); continue },
+			};
+			
+			for (camera, image) in images.into_iter().enumerate().take(2) {
+				sources.push((pose * rig.head_from_camera[camera], rig.cameras[camera], image));
+			}
+			positions.push(pose.translation.vector);
+			frames.push(index);
+		}
+		
+		let mean = positions.iter().sum::<Vector3<f64>>() / positions.len().max(1) as f64;
+		let spread = positions.iter().map(|position| (position - mean).norm()).fold(0.0, f64::max);
+		
+		let height = width / 2;
+		let mut covered = 0;
+		let image = RgbaImage::from_fn(width, height, |x, y| {
+			let longitude = (x as f64 + 0.5) / width as f64 * 2.0 * PI - PI;
+			let latitude = PI / 2.0 - (y as f64 + 0.5) / height as f64 * PI;
+			let direction = Vector3::new(latitude.cos() * longitude.sin(), latitude.sin(), -latitude.cos() * longitude.cos());
+			
+			let pixel = sources.iter().filter_map(|(world_from_camera, camera, image)| {
+				let ray = world_from_camera.rotation.inverse() * direction;
+				let pixel = camera.project(&ray).filter(|pixel| ray.z > 0.0 && camera.contains(pixel))?;
+				
+				Some((ray.z, sample_bilinear(image, pixel.x, pixel.y)?))
+			}).max_by(|a, b| a.0.total_cmp(&b.0));
+			
+			match pixel {
+				Some((_, color)) => { covered += 1; color },
+				None => PANORAMA_BACKGROUND,
+			}
+		});
+		
+		Panorama {
+			serial: recording.serial().to_string(),
+			coverage: covered as f64 / (width * height) as f64,
+			image,
+			frames,
+			skipped,
+			spread,
+		}
+	}
+}
+
+impl Display for Panorama {
+	fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+		writeln!(f, "Headset {}: {}x{} panorama of {} frames, {:.1}% covered",
+		         self.serial, self.image.width(), self.image.height(), self.frames.len(), self.coverage * 100.0)?;
+		
+		for (frame, reason) in &self.skipped {
+			writeln!(f, "Frame {}: skipped, {}", frame, reason)?;
+		}
+		
+		write!(f, "Headset is up to {:.1} cm from its mean position", self.spread * 100.0)?;
+		if self.spread > MAX_PANORAMA_SPREAD {
+			write!(f, ", near objects will be doubled by parallax, stay on one spot and only turn")?;
+		}
+		
+		writeln!(f)
+	}
+}
@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use nalgebra::{Isometry3, Point3};
+
+/// Colored points, in whatever space they were built in.
+#[derive(Debug, Clone, Default)]
+pub struct PointCloud {
+	pub points: Vec<Point3<f32>>,
+	pub colors: Vec<[u8; 3]>,
+}
+
+impl PointCloud {
+	pub fn push(&mut self, point: Point3<f32>, color: [u8; 3]) {
+		self.points.push(point);
+		self.colors.push(color);
+	}
+	
+	pub fn len(&self) -> usize {
+		self.points.len()
+	}
+	
+	pub fn retain<F: Fn(&Point3<f32>) -> bool>(&mut self, keep: F) {
+		let (points, colors) = self.points.iter().zip(&self.colors).filter(|(point, _)| keep(point)).unzip();
+		self.points = points;
+		self.colors = colors;
+	}
+	
+	pub fn transform(&mut self, pose: &Isometry3<f64>) {
+		let pose = pose.cast::<f32>();
+		
+		for point in self.points.iter_mut() {
+			*point = pose * *point;
+		}
+	}
+	
+	/// Binary little endian PLY with float positions and 8 bit colors, which MeshLab and CloudCompare open.
+	pub fn write_ply(&self, path: &Path) -> io::Result<()> {
+		let mut file = BufWriter::new(File::create(path)?);
+		
+		write!(file, "ply\nformat binary_little_endian 1.0\nelement vertex {}\n", self.len())?;
+		write!(file, "property float x\nproperty float y\nproperty float z\n")?;
+		write!(file, "property uchar red\nproperty uchar green\nproperty uchar blue\nend_header\n")?;
+		
+		for (point, color) in self.points.iter().zip(&self.colors) {
+			for coordinate in point.coords.iter() {
+				file.write_all(&coordinate.to_le_bytes())?;
+			}
+			file.write_all(color)?;
+		}
+		
+		file.flush()
+	}
+}
@@ -1,41 +1,46 @@
 use std::{error, fmt};
-use std::ffi::CStr;
 use openvr_sys as sys;
 
 use super::FnTable;
+use crate::openvr_errors::{SETTINGS_ERRORS, runtime_string};
 
 pub struct SettingsError {
 	pub code: sys::EVRSettingsError,
-	pub name: String,
+}
+
+impl SettingsError {
+	/// What the user can do about the common failures.
+	pub fn hint(&self) -> Option<&'static str> {
+		match self.code {
+			sys::EVRSettingsError_VRSettingsError_IPCFailed => Some("Restart SteamVR"),
+			sys::EVRSettingsError_VRSettingsError_WriteFailed => Some("Check that steamvr.vrsettings in the Steam config folder isn't read only"),
+			_ => None,
+		}
+	}
 }
 
 impl error::Error for SettingsError {}
 
 impl fmt::Debug for SettingsError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&format!("{}({})", self.name, self.code))
+		f.pad(&SETTINGS_ERRORS.symbol(self.code as i64))
 	}
 }
 
 impl fmt::Display for SettingsError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&self.name)
+		f.pad(&format!("{} ({})", SETTINGS_ERRORS.description(self.code as i64), SETTINGS_ERRORS.symbol(self.code as i64)))
 	}
 }
 
+/// Also remembers what the runtime calls the error, for displaying it.
 pub fn check_err(fn_tab: FnTable, code: sys::EVRSettingsError) -> Result<(), SettingsError> {
 	if code == sys::EVRSettingsError_VRSettingsError_None {
 		Ok(())
 	} else {
-		let name = fn_tab.GetSettingsErrorNameFromEnum
-		                 .map(|f| unsafe { f(code) })
-		                 .map(|msg| unsafe { CStr::from_ptr(msg) })
-		                 .map(CStr::to_str)
-		                 .map(Result::ok)
-		                 .flatten()
-		                 .unwrap_or("VRSettingsError_UnknownError")
-		                 .into();
+		let symbol = fn_tab.GetSettingsErrorNameFromEnum.and_then(|name| unsafe { runtime_string(name(code)) });
+		SETTINGS_ERRORS.remember(code as i64, symbol, None);
 		
-		Err(SettingsError{ code, name })
+		Err(SettingsError{ code })
 	}
 }
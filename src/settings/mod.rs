@@ -1,22 +1,34 @@
-use std::marker::PhantomData;
 use std::ffi::CString;
 use openvr_sys as sys;
 
 mod error;
 
 pub use error::*;
-use crate::openvr_load::{load, InitError, Context};
+use crate::openvr_load::{InitError, Context};
 
-pub type FnTable = &'static sys::VR_IVRSettings_FnTable;
+/// Only valid while the session is, which the `Context` held next to it ensures.
+type FnTable = &'static sys::VR_IVRSettings_FnTable;
 
-#[derive(Copy, Clone)]
-pub struct Settings<'a>(FnTable, PhantomData<&'a Context>);
+/// Interface versions to try, the one `openvr_sys` was built with first.
+pub const VERSIONS: &[&[u8]] = &[
+	// IVRSettings_002. 001 took the default values as arguments, so there's no older version to fall back to.
+	sys::IVRSettings_Version,
+];
 
-impl<'a> Settings<'a> {
-	pub fn new(_context: &Context) -> Result<Settings, InitError> {
-		let fn_tab: FnTable = unsafe { &*load(sys::IVRSettings_Version)? };
+#[derive(Clone)]
+pub struct Settings(FnTable, #[allow(dead_code)] Context, &'static str);
+
+impl Settings {
+	pub fn new(context: &Context) -> Result<Settings, InitError> {
+		let (fn_tab, version) = context.load::<sys::VR_IVRSettings_FnTable>(VERSIONS)?;
+		let fn_tab: FnTable = unsafe { &*(fn_tab as *const _) };
 		
-		Ok(Settings(fn_tab, PhantomData))
+		Ok(Settings(fn_tab, context.clone(), version))
+	}
+	
+	/// The interface version the runtime bound.
+	pub fn version(&self) -> &'static str {
+		self.2
 	}
 	
 	pub fn get_i32(&self, section: &str, key: &str) -> Result<i32, SettingsError> {
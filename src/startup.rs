@@ -0,0 +1,396 @@
+use std::{error, fmt, thread};
+use std::time::{Duration, Instant};
+use openvr_sys as sys;
+use openvr_sys::k_unTrackedDeviceIndex_Hmd as HMD;
+
+use crate::openvr_load::{ApplicationType, Context, InitError, Interfaces};
+use crate::tracked_camera::FrameType;
+
+/// Where the wait loop gets its time from, so it can be driven without actually waiting.
+pub trait Clock {
+	/// Time since the clock was started.
+	fn elapsed(&self) -> Duration;
+	fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock(Instant);
+
+impl SystemClock {
+	pub fn new() -> Self {
+		SystemClock(Instant::now())
+	}
+}
+
+impl Clock for SystemClock {
+	fn elapsed(&self) -> Duration {
+		self.0.elapsed()
+	}
+	
+	fn sleep(&self, duration: Duration) {
+		thread::sleep(duration)
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+	/// Give up after this long.
+	pub timeout: Duration,
+	/// First delay between init attempts, doubled after every failure up to `max_backoff`.
+	pub backoff: Duration,
+	pub max_backoff: Duration,
+	/// Delay between event polls once OpenVR is initialized.
+	pub poll_interval: Duration,
+}
+
+impl Default for WaitOptions {
+	fn default() -> Self {
+		WaitOptions {
+			timeout: Duration::from_secs(300),
+			backoff: Duration::from_secs(1),
+			max_backoff: Duration::from_secs(10),
+			poll_interval: Duration::from_millis(250),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+	NoHmd,
+	NoCamera,
+	Ready,
+	/// SteamVR is shutting down, the session has to be dropped.
+	Quit,
+}
+
+pub enum WaitProgress<'a> {
+	Retrying { attempt: u32, error: &'a InitError, delay: Duration },
+	Connected,
+	Waiting(Readiness),
+}
+
+impl fmt::Display for WaitProgress<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WaitProgress::Retrying { attempt, error, delay } => {
+				write!(f, "SteamVR isn't ready: {}, retrying in {:.0?} (attempt {})", error, delay, attempt)
+			},
+			WaitProgress::Connected => write!(f, "Connected to SteamVR"),
+			WaitProgress::Waiting(Readiness::NoHmd) => write!(f, "Waiting for the headset to connect"),
+			WaitProgress::Waiting(Readiness::NoCamera) => write!(f, "Waiting for the headset camera"),
+			WaitProgress::Waiting(Readiness::Ready) => write!(f, "Headset and camera are ready"),
+			WaitProgress::Waiting(Readiness::Quit) => write!(f, "SteamVR quit, waiting for it to start again"),
+		}
+	}
+}
+
+pub enum WaitError {
+	/// Failed in a way waiting won't fix.
+	Init(InitError),
+	TimedOut { waited: Duration, waiting_for: String },
+}
+
+impl error::Error for WaitError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			WaitError::Init(err) => Some(err),
+			WaitError::TimedOut { .. } => None,
+		}
+	}
+}
+
+impl fmt::Debug for WaitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WaitError::Init(err) => write!(f, "Init({:?})", err),
+			WaitError::TimedOut { waited, waiting_for } => write!(f, "TimedOut({:?}, {})", waited, waiting_for),
+		}
+	}
+}
+
+impl fmt::Display for WaitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WaitError::Init(_) => write!(f, "Unable to initialize OpenVR"),
+			WaitError::TimedOut { waited, waiting_for } => write!(f, "Gave up after {:.0?}, {}", waited, waiting_for),
+		}
+	}
+}
+
+/// Whether the init error can go away by itself once SteamVR has started and found the headset.
+pub fn is_retryable(err: &InitError) -> bool {
+	match err {
+		InitError::OpenVR(code) => !matches!(
+			*code,
+			sys::EVRInitError_VRInitError_Init_InstallationNotFound |
+			sys::EVRInitError_VRInitError_Init_InstallationCorrupt |
+			sys::EVRInitError_VRInitError_Init_VRClientDLLNotFound |
+			sys::EVRInitError_VRInitError_Init_PathRegistryNotFound |
+			sys::EVRInitError_VRInitError_Init_InterfaceNotFound |
+			sys::EVRInitError_VRInitError_Init_InvalidApplicationType
+		),
+		InitError::AlreadyInitialized | InitError::Library(_) => false,
+	}
+}
+
+/// Connects with backoff until `connect` succeeds, then polls `readiness` until it reports `Ready`. Starts over after the
+/// same backoff when SteamVR quits in between. `progress` is told about every retry and every change of readiness.
+pub fn wait_for<C: Clock, T>(
+	clock: &C,
+	options: &WaitOptions,
+	mut connect: impl FnMut() -> Result<T, InitError>,
+	mut readiness: impl FnMut(&T) -> Readiness,
+	mut progress: impl FnMut(WaitProgress),
+) -> Result<T, WaitError> {
+	let deadline = clock.elapsed() + options.timeout;
+	let timed_out = |waiting_for: String| WaitError::TimedOut { waited: options.timeout, waiting_for };
+	
+	let mut attempt = 0;
+	let mut delay = options.backoff;
+	
+	loop {
+		attempt += 1;
+		
+		let session = match connect() {
+			Ok(session) => session,
+			Err(err) if is_retryable(&err) => {
+				if clock.elapsed() + delay > deadline {
+					return Err(timed_out(format!("OpenVR still fails with {}", err)));
+				}
+				
+				progress(WaitProgress::Retrying { attempt, error: &err, delay });
+				clock.sleep(delay);
+				delay = (delay * 2).min(options.max_backoff);
+				continue;
+			},
+			Err(err) => return Err(WaitError::Init(err)),
+		};
+		
+		progress(WaitProgress::Connected);
+		
+		let mut last = None;
+		loop {
+			let state = readiness(&session);
+			if last != Some(state) {
+				progress(WaitProgress::Waiting(state));
+				last = Some(state);
+			}
+			
+			match state {
+				Readiness::Ready => return Ok(session),
+				Readiness::Quit => {
+					if clock.elapsed() + delay > deadline {
+						return Err(timed_out("SteamVR kept quitting".into()));
+					}
+					
+					clock.sleep(delay);
+					delay = (delay * 2).min(options.max_backoff);
+					break;
+				},
+				Readiness::NoHmd if clock.elapsed() >= deadline => return Err(timed_out("the headset never connected".into())),
+				Readiness::NoCamera if clock.elapsed() >= deadline => {
+					return Err(timed_out("the headset camera never got ready, is it enabled in the SteamVR settings?".into()))
+				},
+				_ => clock.sleep(options.poll_interval),
+			}
+		}
+	}
+}
+
+/// Initializes OpenVR as a background application, which doesn't start SteamVR, and waits until the headset and its
+/// camera are there.
+pub fn wait_for_steamvr<C: Clock>(
+	clock: &C,
+	options: &WaitOptions,
+	progress: impl FnMut(WaitProgress),
+) -> Result<(Context, Interfaces), WaitError> {
+	let connect = || {
+		let context = Context::new(ApplicationType::Background)?;
+		let interfaces = context.interfaces()?;
+		
+		Ok((context, interfaces))
+	};
+	
+	let readiness = |(_, interfaces): &(Context, Interfaces)| {
+		while let Some(event) = interfaces.system.poll_next_event() {
+			if event.eventType == sys::EVREventType_VREvent_Quit {
+				return Readiness::Quit;
+			}
+		}
+		
+		if !interfaces.system.is_tracked_device_connected(HMD) {
+			Readiness::NoHmd
+		} else if !camera_ready(interfaces) {
+			Readiness::NoCamera
+		} else {
+			Readiness::Ready
+		}
+	};
+	
+	wait_for(clock, options, connect, readiness, progress)
+}
+
+/// The headset reports a camera as soon as it's connected, but the camera is only usable once it has a frame size, or
+/// at least once it's enabled in the settings, which makes SteamVR start it.
+fn camera_ready(interfaces: &Interfaces) -> bool {
+	interfaces.tracked_camera.has_camera(HMD) && (
+		interfaces.tracked_camera.get_camera_frame_size(HMD, FrameType::Distorted).is_ok() ||
+		interfaces.settings.get_bool("camera", "enableCamera").unwrap_or(false)
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::{Cell, RefCell};
+	
+	/// Only moves forward when slept on.
+	struct FakeClock(Cell<Duration>);
+	
+	impl FakeClock {
+		fn new() -> Self {
+			FakeClock(Cell::new(Duration::from_secs(0)))
+		}
+	}
+	
+	impl Clock for FakeClock {
+		fn elapsed(&self) -> Duration {
+			self.0.get()
+		}
+		
+		fn sleep(&self, duration: Duration) {
+			self.0.set(self.0.get() + duration)
+		}
+	}
+	
+	const NO_SERVER: InitError = InitError::OpenVR(sys::EVRInitError_VRInitError_Init_NoServerForBackgroundApp);
+	const NOT_INSTALLED: InitError = InitError::OpenVR(sys::EVRInitError_VRInitError_Init_InstallationNotFound);
+	
+	fn options() -> WaitOptions {
+		WaitOptions {
+			timeout: Duration::from_secs(60),
+			backoff: Duration::from_secs(1),
+			max_backoff: Duration::from_secs(8),
+			poll_interval: Duration::from_millis(250),
+		}
+	}
+	
+	/// Delays of the retries reported before connecting after `failures` failed attempts.
+	fn retry_delays(failures: u32) -> Vec<Duration> {
+		let clock = FakeClock::new();
+		let attempts = Cell::new(0);
+		let mut delays = Vec::new();
+		
+		let result = wait_for(&clock, &options(), || {
+			attempts.set(attempts.get() + 1);
+			if attempts.get() <= failures { Err(NO_SERVER) } else { Ok(()) }
+		}, |_| Readiness::Ready, |progress| if let WaitProgress::Retrying { delay, .. } = progress {
+			delays.push(delay);
+		});
+		
+		assert!(result.is_ok());
+		assert_eq!(clock.elapsed(), delays.iter().sum());
+		delays
+	}
+	
+	#[test]
+	fn backoff_doubles_up_to_the_maximum() {
+		let delays = retry_delays(6).into_iter().map(|delay| delay.as_secs()).collect::<Vec<_>>();
+		
+		assert_eq!(delays, [1, 2, 4, 8, 8, 8]);
+	}
+	
+	#[test]
+	fn times_out_while_retrying() {
+		let clock = FakeClock::new();
+		let attempts = Cell::new(0);
+		
+		let result = wait_for(&clock, &options(), || {
+			attempts.set(attempts.get() + 1);
+			Err::<(), _>(NO_SERVER)
+		}, |_| Readiness::Ready, |_| {});
+		
+		match result {
+			Err(WaitError::TimedOut { waited, .. }) => assert_eq!(waited, options().timeout),
+			other => panic!("expected a timeout, got {:?}", other.err()),
+		}
+		// 1 + 2 + 4 + 8 * 6 = 55 s of sleeping, the next 8 s would pass the deadline.
+		assert_eq!(clock.elapsed(), Duration::from_secs(55));
+		assert_eq!(attempts.get(), 10);
+	}
+	
+	#[test]
+	fn non_retryable_errors_fail_right_away() {
+		let clock = FakeClock::new();
+		let attempts = Cell::new(0);
+		
+		let result = wait_for(&clock, &options(), || {
+			attempts.set(attempts.get() + 1);
+			Err::<(), _>(NOT_INSTALLED)
+		}, |_| Readiness::Ready, |_| {});
+		
+		assert!(matches!(result, Err(WaitError::Init(InitError::OpenVR(code))) if code == sys::EVRInitError_VRInitError_Init_InstallationNotFound));
+		assert_eq!(attempts.get(), 1);
+		assert_eq!(clock.elapsed(), Duration::from_secs(0));
+	}
+	
+	#[test]
+	fn reconnects_after_quit() {
+		let clock = FakeClock::new();
+		let sessions = Cell::new(0);
+		let states = RefCell::new(vec![Readiness::NoHmd, Readiness::Quit, Readiness::Ready]);
+		
+		let result = wait_for(&clock, &options(), || {
+			sessions.set(sessions.get() + 1);
+			Ok(sessions.get())
+		}, |_| states.borrow_mut().remove(0), |_| {});
+		
+		assert_eq!(result.ok(), Some(2));
+		assert!(states.borrow().is_empty());
+	}
+	
+	#[test]
+	fn backs_off_when_steamvr_keeps_quitting() {
+		let clock = FakeClock::new();
+		let sessions = Cell::new(0);
+		
+		let result = wait_for(&clock, &options(), || {
+			sessions.set(sessions.get() + 1);
+			Ok(())
+		}, |_| Readiness::Quit, |_| {});
+		
+		assert!(matches!(result, Err(WaitError::TimedOut { .. })));
+		// Same delays as failing to connect, 1 + 2 + 4 + 8 * 6 = 55 s
+		assert_eq!(clock.elapsed(), Duration::from_secs(55));
+		assert_eq!(sessions.get(), 10);
+	}
+	
+	#[test]
+	fn reports_readiness_changes_once() {
+		let clock = FakeClock::new();
+		let states = RefCell::new(vec![
+			Readiness::NoHmd, Readiness::NoHmd, Readiness::NoCamera, Readiness::NoCamera, Readiness::NoCamera, Readiness::Ready,
+		]);
+		let mut log = Vec::new();
+		
+		let result = wait_for(&clock, &options(), || Ok(()), |_| states.borrow_mut().remove(0), |progress| log.push(progress.to_string()));
+		
+		assert!(result.is_ok());
+		assert_eq!(log, [
+			WaitProgress::Connected.to_string(),
+			WaitProgress::Waiting(Readiness::NoHmd).to_string(),
+			WaitProgress::Waiting(Readiness::NoCamera).to_string(),
+			WaitProgress::Waiting(Readiness::Ready).to_string(),
+		]);
+		assert_eq!(clock.elapsed(), options().poll_interval * 5);
+	}
+	
+	#[test]
+	fn times_out_waiting_for_the_camera() {
+		let clock = FakeClock::new();
+		
+		let result = wait_for(&clock, &options(), || Ok(()), |_| Readiness::NoCamera, |_| {});
+		
+		assert!(matches!(result, Err(WaitError::TimedOut { .. })));
+		assert_eq!(clock.elapsed(), options().timeout);
+	}
+}
@@ -1,44 +1,47 @@
 use std::{error, fmt};
 use openvr_sys as sys;
 
+use super::FnTable;
+use crate::openvr_errors::{TRACKED_PROPERTY_ERRORS, runtime_string};
+
 pub struct TrackedPropertyError {
-	pub code: sys::EVRTrackedCameraError,
-	pub name: String,
+	pub code: sys::ETrackedPropertyError,
+}
+
+impl TrackedPropertyError {
+	/// What the user can do about the common failures.
+	pub fn hint(&self) -> Option<&'static str> {
+		match self.code {
+			sys::ETrackedPropertyError_TrackedProp_CouldNotContactServer => Some("Start SteamVR"),
+			sys::ETrackedPropertyError_TrackedProp_InvalidDevice => Some("Connect the headset and wait for SteamVR to find it"),
+			sys::ETrackedPropertyError_TrackedProp_NotYetAvailable => Some("Wait for the headset to finish starting up and try again"),
+			_ => None,
+		}
+	}
 }
 
 impl error::Error for TrackedPropertyError {}
 
 impl fmt::Debug for TrackedPropertyError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&format!("{}({})", self.name, self.code))
+		f.pad(&TRACKED_PROPERTY_ERRORS.symbol(self.code as i64))
 	}
 }
 
 impl fmt::Display for TrackedPropertyError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&self.name)
+		f.pad(&format!("{} ({})", TRACKED_PROPERTY_ERRORS.description(self.code as i64), TRACKED_PROPERTY_ERRORS.symbol(self.code as i64)))
 	}
 }
 
-pub fn check_err(code: sys::EVRTrackedCameraError) -> Result<(), TrackedPropertyError> {
+/// Also remembers what the runtime calls the error, for displaying it.
+pub fn check_err(fn_tab: FnTable, code: sys::ETrackedPropertyError) -> Result<(), TrackedPropertyError> {
 	if code == sys::ETrackedPropertyError_TrackedProp_Success {
 		Ok(())
 	} else {
-		let name = match code {
-			sys::ETrackedPropertyError_TrackedProp_Success => "TrackedProp_Success",
-			sys::ETrackedPropertyError_TrackedProp_WrongDataType => "TrackedProp_WrongDataType",
-			sys::ETrackedPropertyError_TrackedProp_WrongDeviceClass => "TrackedProp_WrongDeviceClass",
-			sys::ETrackedPropertyError_TrackedProp_BufferTooSmall => "TrackedProp_BufferTooSmall",
-			sys::ETrackedPropertyError_TrackedProp_UnknownProperty => "TrackedProp_UnknownProperty",
-			sys::ETrackedPropertyError_TrackedProp_InvalidDevice => "TrackedProp_InvalidDevice",
-			sys::ETrackedPropertyError_TrackedProp_CouldNotContactServer => "TrackedProp_CouldNotContactServer",
-			sys::ETrackedPropertyError_TrackedProp_ValueNotProvidedByDevice => "TrackedProp_ValueNotProvidedByDevice",
-			sys::ETrackedPropertyError_TrackedProp_StringExceedsMaximumLength => "TrackedProp_StringExceedsMaximumLength",
-			sys::ETrackedPropertyError_TrackedProp_NotYetAvailable => "TrackedProp_NotYetAvailable",
-			sys::ETrackedPropertyError_TrackedProp_PermissionDenied => "TrackedProp_PermissionDenied",
-			_ => "TrackedProp_UnknownError",
-		}.into();
+		let symbol = fn_tab.GetPropErrorNameFromEnum.and_then(|name| unsafe { runtime_string(name(code)) });
+		TRACKED_PROPERTY_ERRORS.remember(code as i64, symbol, None);
 		
-		Err(TrackedPropertyError { code, name })
+		Err(TrackedPropertyError { code })
 	}
 }
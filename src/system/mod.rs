@@ -1,22 +1,40 @@
-use std::marker::PhantomData;
 use std::ffi::CString;
+use std::mem;
 use openvr_sys as sys;
 
 mod error;
+mod properties;
+mod utils;
 
 pub use error::*;
-use crate::openvr_load::{load, InitError, Context, TrackedDeviceIndex, TrackedDeviceProperty};
+pub use properties::*;
+pub use utils::*;
+use crate::openvr_load::{InitError, Context, TrackedDeviceIndex, TrackedDeviceProperty};
 
-pub type FnTable = &'static sys::VR_IVRSystem_FnTable;
+/// Only valid while the session is, which the `Context` held next to it ensures.
+type FnTable = &'static sys::VR_IVRSystem_FnTable;
 
-#[derive(Copy, Clone)]
-pub struct System<'a>(FnTable, PhantomData<&'a Context>);
+/// Interface versions to try, the one `openvr_sys` was built with first.
+pub const VERSIONS: &[&[u8]] = &[
+	// IVRSystem_019. 017 has no GetArrayTrackedDeviceProperty, which moves the string property and event functions,
+	// so there's no older version to fall back to.
+	sys::IVRSystem_Version,
+];
 
-impl<'a> System<'a> {
-	pub fn new(_context: &Context) -> Result<System, InitError> {
-		let fn_tab: FnTable = unsafe { &*load(sys::IVRSystem_Version)? };
+#[derive(Clone)]
+pub struct System(FnTable, #[allow(dead_code)] Context, &'static str);
+
+impl System {
+	pub fn new(context: &Context) -> Result<System, InitError> {
+		let (fn_tab, version) = context.load::<sys::VR_IVRSystem_FnTable>(VERSIONS)?;
+		let fn_tab: FnTable = unsafe { &*(fn_tab as *const _) };
 		
-		Ok(System(fn_tab, PhantomData))
+		Ok(System(fn_tab, context.clone(), version))
+	}
+	
+	/// The interface version the runtime bound.
+	pub fn version(&self) -> &'static str {
+		self.2
 	}
 	
 	pub fn string_tracked_device_property(
@@ -28,12 +46,12 @@ impl<'a> System<'a> {
 			let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
 			let size = self.0.GetStringTrackedDeviceProperty.unwrap()(device, property, std::ptr::null_mut(), 0, &mut error);
 			if error != sys::ETrackedPropertyError_TrackedProp_BufferTooSmall {
-				check_err(error)?;
+				check_err(self.0, error)?;
 			}
 			
 			let mut output = vec![0u8; size as usize];
 			self.0.GetStringTrackedDeviceProperty.unwrap()(device, property, output.as_mut_ptr() as *mut _, output.len() as u32, &mut error);
-			check_err(error)?;
+			check_err(self.0, error)?;
 			
 			Ok(CString::new(&output[0..output.len() - 1])
 			           .expect("Failed to read property")
@@ -41,4 +59,169 @@ impl<'a> System<'a> {
 			           .expect("Failed to parse property"))
 		}
 	}
+	
+	pub fn bool_tracked_device_property(
+		&self,
+		device: TrackedDeviceIndex,
+		property: TrackedDeviceProperty,
+	) -> Result<bool, TrackedPropertyError> {
+		let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
+		let out = unsafe { self.0.GetBoolTrackedDeviceProperty.unwrap()(device, property, &mut error) };
+		check_err(self.0, error)?;
+		
+		Ok(out)
+	}
+	
+	pub fn float_tracked_device_property(
+		&self,
+		device: TrackedDeviceIndex,
+		property: TrackedDeviceProperty,
+	) -> Result<f32, TrackedPropertyError> {
+		let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
+		let out = unsafe { self.0.GetFloatTrackedDeviceProperty.unwrap()(device, property, &mut error) };
+		check_err(self.0, error)?;
+		
+		Ok(out)
+	}
+	
+	pub fn int32_tracked_device_property(
+		&self,
+		device: TrackedDeviceIndex,
+		property: TrackedDeviceProperty,
+	) -> Result<i32, TrackedPropertyError> {
+		let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
+		let out = unsafe { self.0.GetInt32TrackedDeviceProperty.unwrap()(device, property, &mut error) };
+		check_err(self.0, error)?;
+		
+		Ok(out)
+	}
+	
+	pub fn uint64_tracked_device_property(
+		&self,
+		device: TrackedDeviceIndex,
+		property: TrackedDeviceProperty,
+	) -> Result<u64, TrackedPropertyError> {
+		let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
+		let out = unsafe { self.0.GetUint64TrackedDeviceProperty.unwrap()(device, property, &mut error) };
+		check_err(self.0, error)?;
+		
+		Ok(out)
+	}
+	
+	pub fn matrix34_tracked_device_property(
+		&self,
+		device: TrackedDeviceIndex,
+		property: TrackedDeviceProperty,
+	) -> Result<Matrix34, TrackedPropertyError> {
+		let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
+		let out = unsafe { self.0.GetMatrix34TrackedDeviceProperty.unwrap()(device, property, &mut error) };
+		check_err(self.0, error)?;
+		
+		Ok(out.m)
+	}
+	
+	pub fn array_tracked_device_property<T: PropertyArrayElement>(
+		&self,
+		device: TrackedDeviceIndex,
+		property: TrackedDeviceProperty,
+	) -> Result<Vec<T>, TrackedPropertyError> {
+		unsafe {
+			let mut error = sys::ETrackedPropertyError_TrackedProp_Success;
+			let size = self.0.GetArrayTrackedDeviceProperty.unwrap()(device, property, T::TAG, std::ptr::null_mut(), 0, &mut error);
+			if error != sys::ETrackedPropertyError_TrackedProp_BufferTooSmall {
+				check_err(self.0, error)?;
+			}
+			
+			let mut output = vec![T::default(); size as usize / mem::size_of::<T>()];
+			let size = self.0.GetArrayTrackedDeviceProperty.unwrap()(device,
+			                                                          property,
+			                                                          T::TAG,
+			                                                          output.as_mut_ptr() as *mut _,
+			                                                          (output.len() * mem::size_of::<T>()) as u32,
+			                                                          &mut error);
+			check_err(self.0, error)?;
+			
+			output.truncate(size as usize / mem::size_of::<T>());
+			
+			Ok(output)
+		}
+	}
+	
+	pub fn tracked_device_property(
+		&self,
+		device: TrackedDeviceIndex,
+		property: &PropertyInfo,
+	) -> Result<PropertyValue, TrackedPropertyError> {
+		let id = property.id;
+		
+		Ok(match property.ty {
+			PropertyType::Bool => PropertyValue::Bool(self.bool_tracked_device_property(device, id)?),
+			PropertyType::Float => PropertyValue::Float(self.float_tracked_device_property(device, id)?),
+			PropertyType::Int32 => PropertyValue::Int32(self.int32_tracked_device_property(device, id)?),
+			PropertyType::Uint64 => PropertyValue::Uint64(self.uint64_tracked_device_property(device, id)?),
+			PropertyType::String => PropertyValue::String(self.string_tracked_device_property(device, id)?),
+			PropertyType::Matrix34 => PropertyValue::Matrix34(self.matrix34_tracked_device_property(device, id)?),
+			PropertyType::Vector3 => {
+				let array = self.array_tracked_device_property::<[f32; 3]>(device, id)?;
+				PropertyValue::Vector3(*array.first().ok_or(TrackedPropertyError { code: sys::ETrackedPropertyError_TrackedProp_ValueNotProvidedByDevice })?)
+			},
+			PropertyType::FloatArray => PropertyValue::FloatArray(self.array_tracked_device_property(device, id)?),
+			PropertyType::Int32Array => PropertyValue::Int32Array(self.array_tracked_device_property(device, id)?),
+			PropertyType::Matrix34Array => PropertyValue::Matrix34Array(self.array_tracked_device_property(device, id)?),
+			PropertyType::Vector4Array => PropertyValue::Vector4Array(self.array_tracked_device_property(device, id)?),
+		})
+	}
+	
+	pub fn is_tracked_device_connected(&self, device: TrackedDeviceIndex) -> bool {
+		unsafe { self.0.IsTrackedDeviceConnected.unwrap()(device) }
+	}
+	
+	/// Transform from the seated zero pose to the standing space.
+	pub fn seated_zero_pose_to_standing(&self) -> Matrix34 {
+		unsafe { self.0.GetSeatedZeroPoseToStandingAbsoluteTrackingPose.unwrap()().m }
+	}
+	
+	/// Transform from the raw tracking space to the standing space, what room setup made of the tracking universe.
+	pub fn raw_zero_pose_to_standing(&self) -> Matrix34 {
+		unsafe { self.0.GetRawZeroPoseToStandingAbsoluteTrackingPose.unwrap()().m }
+	}
+	
+	/// Takes the next event off the queue, `None` once it's empty.
+	pub fn poll_next_event(&self) -> Option<sys::VREvent_t> {
+		unsafe {
+			let mut event = mem::zeroed::<sys::VREvent_t>();
+			
+			if self.0.PollNextEvent.unwrap()(&mut event, mem::size_of::<sys::VREvent_t>() as u32) {
+				Some(event)
+			} else {
+				None
+			}
+		}
+	}
+	
+	/// Reads every property from the catalogue, keeping the per-property errors.
+	pub fn all_tracked_device_properties(
+		&self,
+		device: TrackedDeviceIndex,
+	) -> Vec<(&'static PropertyInfo, Result<PropertyValue, TrackedPropertyError>)> {
+		PROPERTIES.iter()
+		          .map(|info| (info, self.tracked_device_property(device, info)))
+		          .collect()
+	}
+	
+	pub fn camera_properties(&self, device: TrackedDeviceIndex) -> CameraProperties {
+		CameraProperties {
+			num_cameras: self.int32_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_NumCameras_Int32),
+			frame_layout: self.int32_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_CameraFrameLayout_Int32),
+			camera_to_head: self.matrix34_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_CameraToHeadTransform_Matrix34),
+			cameras_to_head: self.array_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_CameraToHeadTransforms_Matrix34_Array),
+			distortion_functions: self.array_tracked_device_property::<i32>(device, sys::ETrackedDeviceProperty_Prop_CameraDistortionFunction_Int32_Array)
+			                          .map(|functions| functions.into_iter().map(DistortionFunction::from).collect()),
+			distortion_coefficients: self.array_tracked_device_property::<f32>(device, sys::ETrackedDeviceProperty_Prop_CameraDistortionCoefficients_Float_Array)
+			                             .map(split_coefficients),
+			white_balance: self.array_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_CameraWhiteBalance_Vector4_Array),
+			firmware_version: self.uint64_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_CameraFirmwareVersion_Uint64),
+			compatibility_mode: self.int32_tracked_device_property(device, sys::ETrackedDeviceProperty_Prop_CameraCompatibilityMode_Int32),
+		}
+	}
 }
@@ -0,0 +1,320 @@
+use openvr_sys as sys;
+
+use crate::openvr_load::TrackedDeviceProperty;
+
+pub type Matrix34 = [[f32; 4]; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+	Bool,
+	Float,
+	Int32,
+	Uint64,
+	String,
+	Matrix34,
+	Vector3,
+	FloatArray,
+	Int32Array,
+	Matrix34Array,
+	Vector4Array,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+	Bool(bool),
+	Float(f32),
+	Int32(i32),
+	Uint64(u64),
+	String(String),
+	Matrix34(Matrix34),
+	Vector3([f32; 3]),
+	FloatArray(Vec<f32>),
+	Int32Array(Vec<i32>),
+	Matrix34Array(Vec<Matrix34>),
+	Vector4Array(Vec<[f32; 4]>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PropertyInfo {
+	pub id: TrackedDeviceProperty,
+	pub name: &'static str,
+	pub ty: PropertyType,
+}
+
+/// Element types that can be read through `GetArrayTrackedDeviceProperty`.
+///
+/// # Safety
+///
+/// The runtime writes the property into a buffer of these, so `TAG` has to name a property type with exactly the size
+/// and layout of `Self`, and any bit pattern of that size has to be a valid `Self`.
+pub unsafe trait PropertyArrayElement: Copy + Default {
+	const TAG: sys::PropertyTypeTag_t;
+}
+
+unsafe impl PropertyArrayElement for f32 { const TAG: sys::PropertyTypeTag_t = sys::k_unFloatPropertyTag; }
+unsafe impl PropertyArrayElement for i32 { const TAG: sys::PropertyTypeTag_t = sys::k_unInt32PropertyTag; }
+unsafe impl PropertyArrayElement for u64 { const TAG: sys::PropertyTypeTag_t = sys::k_unUint64PropertyTag; }
+unsafe impl PropertyArrayElement for Matrix34 { const TAG: sys::PropertyTypeTag_t = sys::k_unHmdMatrix34PropertyTag; }
+unsafe impl PropertyArrayElement for [f32; 3] { const TAG: sys::PropertyTypeTag_t = sys::k_unHmdVector3PropertyTag; }
+unsafe impl PropertyArrayElement for [f32; 4] { const TAG: sys::PropertyTypeTag_t = sys::k_unHmdVector4PropertyTag; }
+
+macro_rules! properties {
+	($($name:ident = $id:expr => $ty:ident,)*) => {
+		/// Every `ETrackedDeviceProperty` we know how to read, as listed in `openvr.h`. IDs `openvr_sys` has constants for
+		/// are taken from them, the ones of newer SDKs are written out.
+		pub const PROPERTIES: &[PropertyInfo] = &[
+			$(PropertyInfo { id: $id, name: stringify!($name), ty: PropertyType::$ty },)*
+		];
+		
+		/// Where the ID of every entry of `PROPERTIES` came from, to check the `sys` constants against the names.
+		#[cfg(test)]
+		const ID_SOURCES: &[&str] = &[$(stringify!($id),)*];
+	};
+}
+
+properties! {
+	Prop_TrackingSystemName_String = sys::ETrackedDeviceProperty_Prop_TrackingSystemName_String => String,
+	Prop_ModelNumber_String = sys::ETrackedDeviceProperty_Prop_ModelNumber_String => String,
+	Prop_SerialNumber_String = sys::ETrackedDeviceProperty_Prop_SerialNumber_String => String,
+	Prop_RenderModelName_String = sys::ETrackedDeviceProperty_Prop_RenderModelName_String => String,
+	Prop_WillDriftInYaw_Bool = sys::ETrackedDeviceProperty_Prop_WillDriftInYaw_Bool => Bool,
+	Prop_ManufacturerName_String = sys::ETrackedDeviceProperty_Prop_ManufacturerName_String => String,
+	Prop_TrackingFirmwareVersion_String = sys::ETrackedDeviceProperty_Prop_TrackingFirmwareVersion_String => String,
+	Prop_HardwareRevision_String = sys::ETrackedDeviceProperty_Prop_HardwareRevision_String => String,
+	Prop_AllWirelessDongleDescriptions_String = sys::ETrackedDeviceProperty_Prop_AllWirelessDongleDescriptions_String => String,
+	Prop_ConnectedWirelessDongle_String = sys::ETrackedDeviceProperty_Prop_ConnectedWirelessDongle_String => String,
+	Prop_DeviceIsWireless_Bool = sys::ETrackedDeviceProperty_Prop_DeviceIsWireless_Bool => Bool,
+	Prop_DeviceIsCharging_Bool = sys::ETrackedDeviceProperty_Prop_DeviceIsCharging_Bool => Bool,
+	Prop_DeviceBatteryPercentage_Float = sys::ETrackedDeviceProperty_Prop_DeviceBatteryPercentage_Float => Float,
+	Prop_StatusDisplayTransform_Matrix34 = sys::ETrackedDeviceProperty_Prop_StatusDisplayTransform_Matrix34 => Matrix34,
+	Prop_Firmware_UpdateAvailable_Bool = sys::ETrackedDeviceProperty_Prop_Firmware_UpdateAvailable_Bool => Bool,
+	Prop_Firmware_ManualUpdate_Bool = sys::ETrackedDeviceProperty_Prop_Firmware_ManualUpdate_Bool => Bool,
+	Prop_Firmware_ManualUpdateURL_String = sys::ETrackedDeviceProperty_Prop_Firmware_ManualUpdateURL_String => String,
+	Prop_HardwareRevision_Uint64 = sys::ETrackedDeviceProperty_Prop_HardwareRevision_Uint64 => Uint64,
+	Prop_FirmwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_FirmwareVersion_Uint64 => Uint64,
+	Prop_FPGAVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_FPGAVersion_Uint64 => Uint64,
+	Prop_VRCVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_VRCVersion_Uint64 => Uint64,
+	Prop_RadioVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_RadioVersion_Uint64 => Uint64,
+	Prop_DongleVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_DongleVersion_Uint64 => Uint64,
+	Prop_BlockServerShutdown_Bool = sys::ETrackedDeviceProperty_Prop_BlockServerShutdown_Bool => Bool,
+	Prop_CanUnifyCoordinateSystemWithHmd_Bool = sys::ETrackedDeviceProperty_Prop_CanUnifyCoordinateSystemWithHmd_Bool => Bool,
+	Prop_ContainsProximitySensor_Bool = sys::ETrackedDeviceProperty_Prop_ContainsProximitySensor_Bool => Bool,
+	Prop_DeviceProvidesBatteryStatus_Bool = sys::ETrackedDeviceProperty_Prop_DeviceProvidesBatteryStatus_Bool => Bool,
+	Prop_DeviceCanPowerOff_Bool = sys::ETrackedDeviceProperty_Prop_DeviceCanPowerOff_Bool => Bool,
+	Prop_Firmware_ProgrammingTarget_String = sys::ETrackedDeviceProperty_Prop_Firmware_ProgrammingTarget_String => String,
+	Prop_DeviceClass_Int32 = sys::ETrackedDeviceProperty_Prop_DeviceClass_Int32 => Int32,
+	Prop_HasCamera_Bool = sys::ETrackedDeviceProperty_Prop_HasCamera_Bool => Bool,
+	Prop_DriverVersion_String = sys::ETrackedDeviceProperty_Prop_DriverVersion_String => String,
+	Prop_Firmware_ForceUpdateRequired_Bool = sys::ETrackedDeviceProperty_Prop_Firmware_ForceUpdateRequired_Bool => Bool,
+	Prop_ViveSystemButtonFixRequired_Bool = sys::ETrackedDeviceProperty_Prop_ViveSystemButtonFixRequired_Bool => Bool,
+	Prop_ParentDriver_Uint64 = sys::ETrackedDeviceProperty_Prop_ParentDriver_Uint64 => Uint64,
+	Prop_ResourceRoot_String = sys::ETrackedDeviceProperty_Prop_ResourceRoot_String => String,
+	Prop_RegisteredDeviceType_String = sys::ETrackedDeviceProperty_Prop_RegisteredDeviceType_String => String,
+	Prop_InputProfilePath_String = sys::ETrackedDeviceProperty_Prop_InputProfilePath_String => String,
+	Prop_NeverTracked_Bool = sys::ETrackedDeviceProperty_Prop_NeverTracked_Bool => Bool,
+	Prop_NumCameras_Int32 = sys::ETrackedDeviceProperty_Prop_NumCameras_Int32 => Int32,
+	Prop_CameraFrameLayout_Int32 = sys::ETrackedDeviceProperty_Prop_CameraFrameLayout_Int32 => Int32,
+	Prop_CameraStreamFormat_Int32 = sys::ETrackedDeviceProperty_Prop_CameraStreamFormat_Int32 => Int32,
+	Prop_AdditionalDeviceSettingsPath_String = sys::ETrackedDeviceProperty_Prop_AdditionalDeviceSettingsPath_String => String,
+	Prop_Identifiable_Bool = sys::ETrackedDeviceProperty_Prop_Identifiable_Bool => Bool,
+	Prop_BootloaderVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_BootloaderVersion_Uint64 => Uint64,
+	Prop_AdditionalSystemReportData_String = sys::ETrackedDeviceProperty_Prop_AdditionalSystemReportData_String => String,
+	Prop_CompositeFirmwareVersion_String = sys::ETrackedDeviceProperty_Prop_CompositeFirmwareVersion_String => String,
+	Prop_Firmware_RemindUpdate_Bool = 1047 => Bool, // newer SDK
+	Prop_PeripheralApplicationVersion_Uint64 = 1048 => Uint64, // newer SDK
+	Prop_ManufacturerSerialNumber_String = 1049 => String, // newer SDK
+	Prop_ComputedSerialNumber_String = 1050 => String, // newer SDK
+	Prop_EstimatedDeviceFirstUseTime_Int32 = 1051 => Int32, // newer SDK
+	
+	Prop_ReportsTimeSinceVSync_Bool = sys::ETrackedDeviceProperty_Prop_ReportsTimeSinceVSync_Bool => Bool,
+	Prop_SecondsFromVsyncToPhotons_Float = sys::ETrackedDeviceProperty_Prop_SecondsFromVsyncToPhotons_Float => Float,
+	Prop_DisplayFrequency_Float = sys::ETrackedDeviceProperty_Prop_DisplayFrequency_Float => Float,
+	Prop_UserIpdMeters_Float = sys::ETrackedDeviceProperty_Prop_UserIpdMeters_Float => Float,
+	Prop_CurrentUniverseId_Uint64 = sys::ETrackedDeviceProperty_Prop_CurrentUniverseId_Uint64 => Uint64,
+	Prop_PreviousUniverseId_Uint64 = sys::ETrackedDeviceProperty_Prop_PreviousUniverseId_Uint64 => Uint64,
+	Prop_DisplayFirmwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_DisplayFirmwareVersion_Uint64 => Uint64,
+	Prop_IsOnDesktop_Bool = sys::ETrackedDeviceProperty_Prop_IsOnDesktop_Bool => Bool,
+	Prop_DisplayMCType_Int32 = sys::ETrackedDeviceProperty_Prop_DisplayMCType_Int32 => Int32,
+	Prop_DisplayMCOffset_Float = sys::ETrackedDeviceProperty_Prop_DisplayMCOffset_Float => Float,
+	Prop_DisplayMCScale_Float = sys::ETrackedDeviceProperty_Prop_DisplayMCScale_Float => Float,
+	Prop_EdidVendorID_Int32 = sys::ETrackedDeviceProperty_Prop_EdidVendorID_Int32 => Int32,
+	Prop_DisplayMCImageLeft_String = sys::ETrackedDeviceProperty_Prop_DisplayMCImageLeft_String => String,
+	Prop_DisplayMCImageRight_String = sys::ETrackedDeviceProperty_Prop_DisplayMCImageRight_String => String,
+	Prop_DisplayGCBlackClamp_Float = sys::ETrackedDeviceProperty_Prop_DisplayGCBlackClamp_Float => Float,
+	Prop_EdidProductID_Int32 = sys::ETrackedDeviceProperty_Prop_EdidProductID_Int32 => Int32,
+	Prop_CameraToHeadTransform_Matrix34 = sys::ETrackedDeviceProperty_Prop_CameraToHeadTransform_Matrix34 => Matrix34,
+	Prop_DisplayGCType_Int32 = sys::ETrackedDeviceProperty_Prop_DisplayGCType_Int32 => Int32,
+	Prop_DisplayGCOffset_Float = sys::ETrackedDeviceProperty_Prop_DisplayGCOffset_Float => Float,
+	Prop_DisplayGCScale_Float = sys::ETrackedDeviceProperty_Prop_DisplayGCScale_Float => Float,
+	Prop_DisplayGCPrescale_Float = sys::ETrackedDeviceProperty_Prop_DisplayGCPrescale_Float => Float,
+	Prop_DisplayGCImage_String = sys::ETrackedDeviceProperty_Prop_DisplayGCImage_String => String,
+	Prop_LensCenterLeftU_Float = sys::ETrackedDeviceProperty_Prop_LensCenterLeftU_Float => Float,
+	Prop_LensCenterLeftV_Float = sys::ETrackedDeviceProperty_Prop_LensCenterLeftV_Float => Float,
+	Prop_LensCenterRightU_Float = sys::ETrackedDeviceProperty_Prop_LensCenterRightU_Float => Float,
+	Prop_LensCenterRightV_Float = sys::ETrackedDeviceProperty_Prop_LensCenterRightV_Float => Float,
+	Prop_UserHeadToEyeDepthMeters_Float = sys::ETrackedDeviceProperty_Prop_UserHeadToEyeDepthMeters_Float => Float,
+	Prop_CameraFirmwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_CameraFirmwareVersion_Uint64 => Uint64,
+	Prop_CameraFirmwareDescription_String = sys::ETrackedDeviceProperty_Prop_CameraFirmwareDescription_String => String,
+	Prop_DisplayFPGAVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_DisplayFPGAVersion_Uint64 => Uint64,
+	Prop_DisplayBootloaderVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_DisplayBootloaderVersion_Uint64 => Uint64,
+	Prop_DisplayHardwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_DisplayHardwareVersion_Uint64 => Uint64,
+	Prop_AudioFirmwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_AudioFirmwareVersion_Uint64 => Uint64,
+	Prop_CameraCompatibilityMode_Int32 = sys::ETrackedDeviceProperty_Prop_CameraCompatibilityMode_Int32 => Int32,
+	Prop_ScreenshotHorizontalFieldOfViewDegrees_Float = sys::ETrackedDeviceProperty_Prop_ScreenshotHorizontalFieldOfViewDegrees_Float => Float,
+	Prop_ScreenshotVerticalFieldOfViewDegrees_Float = sys::ETrackedDeviceProperty_Prop_ScreenshotVerticalFieldOfViewDegrees_Float => Float,
+	Prop_DisplaySuppressed_Bool = sys::ETrackedDeviceProperty_Prop_DisplaySuppressed_Bool => Bool,
+	Prop_DisplayAllowNightMode_Bool = sys::ETrackedDeviceProperty_Prop_DisplayAllowNightMode_Bool => Bool,
+	Prop_DisplayMCImageWidth_Int32 = sys::ETrackedDeviceProperty_Prop_DisplayMCImageWidth_Int32 => Int32,
+	Prop_DisplayMCImageHeight_Int32 = sys::ETrackedDeviceProperty_Prop_DisplayMCImageHeight_Int32 => Int32,
+	Prop_DisplayMCImageNumChannels_Int32 = sys::ETrackedDeviceProperty_Prop_DisplayMCImageNumChannels_Int32 => Int32,
+	Prop_SecondsFromPhotonsToVblank_Float = sys::ETrackedDeviceProperty_Prop_SecondsFromPhotonsToVblank_Float => Float,
+	Prop_DriverDirectModeSendsVsyncEvents_Bool = sys::ETrackedDeviceProperty_Prop_DriverDirectModeSendsVsyncEvents_Bool => Bool,
+	Prop_DisplayDebugMode_Bool = sys::ETrackedDeviceProperty_Prop_DisplayDebugMode_Bool => Bool,
+	Prop_GraphicsAdapterLuid_Uint64 = sys::ETrackedDeviceProperty_Prop_GraphicsAdapterLuid_Uint64 => Uint64,
+	Prop_DriverProvidedChaperonePath_String = sys::ETrackedDeviceProperty_Prop_DriverProvidedChaperonePath_String => String,
+	Prop_ExpectedTrackingReferenceCount_Int32 = sys::ETrackedDeviceProperty_Prop_ExpectedTrackingReferenceCount_Int32 => Int32,
+	Prop_ExpectedControllerCount_Int32 = sys::ETrackedDeviceProperty_Prop_ExpectedControllerCount_Int32 => Int32,
+	Prop_NamedIconPathControllerLeftDeviceOff_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathControllerLeftDeviceOff_String => String,
+	Prop_NamedIconPathControllerRightDeviceOff_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathControllerRightDeviceOff_String => String,
+	Prop_NamedIconPathTrackingReferenceDeviceOff_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathTrackingReferenceDeviceOff_String => String,
+	Prop_DoNotApplyPrediction_Bool = sys::ETrackedDeviceProperty_Prop_DoNotApplyPrediction_Bool => Bool,
+	Prop_CameraToHeadTransforms_Matrix34_Array = sys::ETrackedDeviceProperty_Prop_CameraToHeadTransforms_Matrix34_Array => Matrix34Array,
+	Prop_DistortionMeshSettings_Int32 = sys::ETrackedDeviceProperty_Prop_DistortionMeshResolution_Int32 => Int32, // renamed in the newer SDK
+	Prop_DriverIsDrawingControllers_Bool = sys::ETrackedDeviceProperty_Prop_DriverIsDrawingControllers_Bool => Bool,
+	Prop_DriverRequestsApplicationPause_Bool = sys::ETrackedDeviceProperty_Prop_DriverRequestsApplicationPause_Bool => Bool,
+	Prop_DriverRequestsReducedRendering_Bool = sys::ETrackedDeviceProperty_Prop_DriverRequestsReducedRendering_Bool => Bool,
+	Prop_MinimumIpdStepMeters_Float = sys::ETrackedDeviceProperty_Prop_MinimumIpdStepMeters_Float => Float,
+	Prop_AudioBridgeFirmwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_AudioBridgeFirmwareVersion_Uint64 => Uint64,
+	Prop_ImageBridgeFirmwareVersion_Uint64 = sys::ETrackedDeviceProperty_Prop_ImageBridgeFirmwareVersion_Uint64 => Uint64,
+	Prop_ImuToHeadTransform_Matrix34 = sys::ETrackedDeviceProperty_Prop_ImuToHeadTransform_Matrix34 => Matrix34,
+	Prop_ImuFactoryGyroBias_Vector3 = sys::ETrackedDeviceProperty_Prop_ImuFactoryGyroBias_Vector3 => Vector3,
+	Prop_ImuFactoryGyroScale_Vector3 = sys::ETrackedDeviceProperty_Prop_ImuFactoryGyroScale_Vector3 => Vector3,
+	Prop_ImuFactoryAccelerometerBias_Vector3 = sys::ETrackedDeviceProperty_Prop_ImuFactoryAccelerometerBias_Vector3 => Vector3,
+	Prop_ImuFactoryAccelerometerScale_Vector3 = sys::ETrackedDeviceProperty_Prop_ImuFactoryAccelerometerScale_Vector3 => Vector3,
+	Prop_ConfigurationIncludesLighthouse20Features_Bool = sys::ETrackedDeviceProperty_Prop_ConfigurationIncludesLighthouse20Features_Bool => Bool,
+	Prop_AdditionalRadioFeatures_Uint64 = sys::ETrackedDeviceProperty_Prop_AdditionalRadioFeatures_Uint64 => Uint64,
+	Prop_CameraWhiteBalance_Vector4_Array = sys::ETrackedDeviceProperty_Prop_CameraWhiteBalance_Vector4_Array => Vector4Array,
+	Prop_CameraDistortionFunction_Int32_Array = sys::ETrackedDeviceProperty_Prop_CameraDistortionFunction_Int32_Array => Int32Array,
+	Prop_CameraDistortionCoefficients_Float_Array = sys::ETrackedDeviceProperty_Prop_CameraDistortionCoefficients_Float_Array => FloatArray,
+	Prop_ExpectedControllerType_String = sys::ETrackedDeviceProperty_Prop_ExpectedControllerType_String => String,
+	Prop_HmdTrackingStyle_Int32 = 2075 => Int32, // newer SDK
+	Prop_DriverProvidedChaperoneVisibility_Bool = 2076 => Bool, // newer SDK
+	Prop_HmdColumnCorrectionSettingPrefix_String = 2077 => String, // newer SDK
+	Prop_CameraSupportsCompatibilityModes_Bool = 2078 => Bool, // newer SDK
+	Prop_SupportsRoomViewDepthProjection_Bool = 2079 => Bool, // newer SDK
+	Prop_DisplayAvailableFrameRates_Float_Array = sys::ETrackedDeviceProperty_Prop_DisplayAvailableFrameRates_Float_Array => FloatArray,
+	Prop_DisplaySupportsMultipleFramerates_Bool = sys::ETrackedDeviceProperty_Prop_DisplaySupportsMultipleFramerates_Bool => Bool,
+	Prop_DisplayColorMultLeft_Vector3 = 2082 => Vector3, // newer SDK
+	Prop_DisplayColorMultRight_Vector3 = 2083 => Vector3, // newer SDK
+	Prop_DisplaySupportsRuntimeFramerateChange_Bool = 2084 => Bool, // newer SDK
+	Prop_DisplaySupportsAnalogGain_Bool = 2085 => Bool, // newer SDK
+	Prop_DisplayMinAnalogGain_Float = 2086 => Float, // newer SDK
+	Prop_DisplayMaxAnalogGain_Float = 2087 => Float, // newer SDK
+	Prop_CameraExposureTime_Float = 2088 => Float, // newer SDK
+	Prop_CameraGlobalGain_Float = 2089 => Float, // newer SDK
+	Prop_DashboardLayoutPathName_String = sys::ETrackedDeviceProperty_Prop_DashboardLayoutPathName_String => String,
+	Prop_DashboardScale_Float = 2091 => Float, // newer SDK
+	Prop_IpdUIRangeMinMeters_Float = 2100 => Float, // newer SDK
+	Prop_IpdUIRangeMaxMeters_Float = 2101 => Float, // newer SDK
+	
+	Prop_DriverRequestedMuraCorrectionMode_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraCorrectionMode_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_InnerLeft_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_InnerLeft_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_InnerRight_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_InnerRight_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_InnerTop_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_InnerTop_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_InnerBottom_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_InnerBottom_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_OuterLeft_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_OuterLeft_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_OuterRight_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_OuterRight_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_OuterTop_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_OuterTop_Int32 => Int32,
+	Prop_DriverRequestedMuraFeather_OuterBottom_Int32 = sys::ETrackedDeviceProperty_Prop_DriverRequestedMuraFeather_OuterBottom_Int32 => Int32,
+	
+	Prop_AttachedDeviceId_String = sys::ETrackedDeviceProperty_Prop_AttachedDeviceId_String => String,
+	Prop_SupportedButtons_Uint64 = sys::ETrackedDeviceProperty_Prop_SupportedButtons_Uint64 => Uint64,
+	Prop_Axis0Type_Int32 = sys::ETrackedDeviceProperty_Prop_Axis0Type_Int32 => Int32,
+	Prop_Axis1Type_Int32 = sys::ETrackedDeviceProperty_Prop_Axis1Type_Int32 => Int32,
+	Prop_Axis2Type_Int32 = sys::ETrackedDeviceProperty_Prop_Axis2Type_Int32 => Int32,
+	Prop_Axis3Type_Int32 = sys::ETrackedDeviceProperty_Prop_Axis3Type_Int32 => Int32,
+	Prop_Axis4Type_Int32 = sys::ETrackedDeviceProperty_Prop_Axis4Type_Int32 => Int32,
+	Prop_ControllerRoleHint_Int32 = sys::ETrackedDeviceProperty_Prop_ControllerRoleHint_Int32 => Int32,
+	
+	Prop_FieldOfViewLeftDegrees_Float = sys::ETrackedDeviceProperty_Prop_FieldOfViewLeftDegrees_Float => Float,
+	Prop_FieldOfViewRightDegrees_Float = sys::ETrackedDeviceProperty_Prop_FieldOfViewRightDegrees_Float => Float,
+	Prop_FieldOfViewTopDegrees_Float = sys::ETrackedDeviceProperty_Prop_FieldOfViewTopDegrees_Float => Float,
+	Prop_FieldOfViewBottomDegrees_Float = sys::ETrackedDeviceProperty_Prop_FieldOfViewBottomDegrees_Float => Float,
+	Prop_TrackingRangeMinimumMeters_Float = sys::ETrackedDeviceProperty_Prop_TrackingRangeMinimumMeters_Float => Float,
+	Prop_TrackingRangeMaximumMeters_Float = sys::ETrackedDeviceProperty_Prop_TrackingRangeMaximumMeters_Float => Float,
+	Prop_ModeLabel_String = sys::ETrackedDeviceProperty_Prop_ModeLabel_String => String,
+	Prop_CanWirelessIdentify_Bool = sys::ETrackedDeviceProperty_Prop_CanWirelessIdentify_Bool => Bool,
+	Prop_Nonce_Int32 = sys::ETrackedDeviceProperty_Prop_Nonce_Int32 => Int32,
+	
+	Prop_IconPathName_String = sys::ETrackedDeviceProperty_Prop_IconPathName_String => String,
+	Prop_NamedIconPathDeviceOff_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceOff_String => String,
+	Prop_NamedIconPathDeviceSearching_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceSearching_String => String,
+	Prop_NamedIconPathDeviceSearchingAlert_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceSearchingAlert_String => String,
+	Prop_NamedIconPathDeviceReady_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceReady_String => String,
+	Prop_NamedIconPathDeviceReadyAlert_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceReadyAlert_String => String,
+	Prop_NamedIconPathDeviceNotReady_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceNotReady_String => String,
+	Prop_NamedIconPathDeviceStandby_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceStandby_String => String,
+	Prop_NamedIconPathDeviceAlertLow_String = sys::ETrackedDeviceProperty_Prop_NamedIconPathDeviceAlertLow_String => String,
+	Prop_NamedIconPathDeviceStandbyAlert_String = 5009 => String, // newer SDK
+	
+	Prop_ParentContainer = sys::ETrackedDeviceProperty_Prop_ParentContainer => Uint64,
+	Prop_OverrideContainer_Uint64 = 5201 => Uint64, // newer SDK
+	
+	Prop_UserConfigPath_String = sys::ETrackedDeviceProperty_Prop_UserConfigPath_String => String,
+	Prop_InstallPath_String = sys::ETrackedDeviceProperty_Prop_InstallPath_String => String,
+	Prop_HasDisplayComponent_Bool = sys::ETrackedDeviceProperty_Prop_HasDisplayComponent_Bool => Bool,
+	Prop_HasControllerComponent_Bool = sys::ETrackedDeviceProperty_Prop_HasControllerComponent_Bool => Bool,
+	Prop_HasCameraComponent_Bool = sys::ETrackedDeviceProperty_Prop_HasCameraComponent_Bool => Bool,
+	Prop_HasDriverDirectModeComponent_Bool = sys::ETrackedDeviceProperty_Prop_HasDriverDirectModeComponent_Bool => Bool,
+	Prop_HasVirtualDisplayComponent_Bool = sys::ETrackedDeviceProperty_Prop_HasVirtualDisplayComponent_Bool => Bool,
+	Prop_HasSpatialAnchorsSupport_Bool = sys::ETrackedDeviceProperty_Prop_HasSpatialAnchorsSupport_Bool => Bool,
+	
+	Prop_ControllerType_String = sys::ETrackedDeviceProperty_Prop_ControllerType_String => String,
+	Prop_ControllerHandSelectionPriority_Int32 = sys::ETrackedDeviceProperty_Prop_ControllerHandSelectionPriority_Int32 => Int32,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashSet;
+	
+	#[test]
+	fn ids_and_names_are_unique() {
+		assert_eq!(PROPERTIES.iter().map(|info| info.id).collect::<HashSet<_>>().len(), PROPERTIES.len());
+		assert_eq!(PROPERTIES.iter().map(|info| info.name).collect::<HashSet<_>>().len(), PROPERTIES.len());
+	}
+	
+	#[test]
+	fn sys_constants_belong_to_their_names() {
+		let renamed = ["Prop_DistortionMeshSettings_Int32"];
+		
+		for (info, source) in PROPERTIES.iter().zip(ID_SOURCES) {
+			let source = source.replace(' ', "");
+			if let Some(constant) = source.strip_prefix("sys::ETrackedDeviceProperty_") {
+				assert!(constant == info.name || renamed.contains(&info.name), "{} is read with {}", info.name, source);
+			}
+		}
+	}
+	
+	#[test]
+	fn type_suffixes_match_types() {
+		for info in PROPERTIES {
+			let suffix = match info.ty {
+				PropertyType::Bool => "_Bool",
+				PropertyType::Float => "_Float",
+				PropertyType::Int32 => "_Int32",
+				PropertyType::Uint64 => "_Uint64",
+				PropertyType::String => "_String",
+				PropertyType::Matrix34 => "_Matrix34",
+				PropertyType::Vector3 => "_Vector3",
+				PropertyType::FloatArray => "_Float_Array",
+				PropertyType::Int32Array => "_Int32_Array",
+				PropertyType::Matrix34Array => "_Matrix34_Array",
+				PropertyType::Vector4Array => "_Vector4_Array",
+			};
+			
+			// Container handles are the only properties without a suffix.
+			assert!(info.name.ends_with(suffix) || info.name == "Prop_ParentContainer", "{} isn't {:?}", info.name, info.ty);
+		}
+	}
+}
@@ -0,0 +1,50 @@
+use openvr_sys as sys;
+
+use super::{Matrix34, TrackedPropertyError};
+
+pub const MAX_DISTORTION_PARAMETERS: usize = sys::k_unMaxDistortionFunctionParameters as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistortionFunction {
+	None,
+	FTheta,
+	ExtendedFTheta,
+	Unknown(i32),
+}
+
+impl From<i32> for DistortionFunction {
+	fn from(value: i32) -> Self {
+		match value as sys::EVRDistortionFunctionType {
+			sys::EVRDistortionFunctionType_VRDistortionFunctionType_None => DistortionFunction::None,
+			sys::EVRDistortionFunctionType_VRDistortionFunctionType_FTheta => DistortionFunction::FTheta,
+			sys::EVRDistortionFunctionType_VRDistortionFunctionType_Extended_FTheta => DistortionFunction::ExtendedFTheta,
+			_ => DistortionFunction::Unknown(value),
+		}
+	}
+}
+
+pub fn split_coefficients(coeffs: Vec<f32>) -> Vec<[f32; MAX_DISTORTION_PARAMETERS]> {
+	coeffs.chunks_exact(MAX_DISTORTION_PARAMETERS)
+	      .map(|chunk| {
+		      let mut out = [0.0; MAX_DISTORTION_PARAMETERS];
+		      out.copy_from_slice(chunk);
+		      out
+	      })
+	      .collect()
+}
+
+/// Camera related device properties, each one kept with its own error so a missing one doesn't hide the rest.
+#[derive(Debug)]
+pub struct CameraProperties {
+	pub num_cameras: Result<i32, TrackedPropertyError>,
+	pub frame_layout: Result<i32, TrackedPropertyError>,
+	pub camera_to_head: Result<Matrix34, TrackedPropertyError>,
+	pub cameras_to_head: Result<Vec<Matrix34>, TrackedPropertyError>,
+	pub distortion_functions: Result<Vec<DistortionFunction>, TrackedPropertyError>,
+	/// `k_unMaxDistortionFunctionParameters` coefficients per camera.
+	pub distortion_coefficients: Result<Vec<[f32; MAX_DISTORTION_PARAMETERS]>, TrackedPropertyError>,
+	/// RGBG white balance per camera.
+	pub white_balance: Result<Vec<[f32; 4]>, TrackedPropertyError>,
+	pub firmware_version: Result<u64, TrackedPropertyError>,
+	pub compatibility_mode: Result<i32, TrackedPropertyError>,
+}
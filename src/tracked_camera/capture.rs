@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use openvr_sys as sys;
+
+use super::{VideoStream, Frame, FrameType, FrameError, TrackedCameraError, DEFAULT_POLL_INTERVAL};
+
+/// How long the worker waits for a frame before checking whether it should stop.
+const STOP_CHECK: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+	pub frame_type: FrameType,
+	/// How many of the latest frames to keep, at least one.
+	pub capacity: usize,
+	pub poll_interval: Duration,
+}
+
+impl Default for CaptureOptions {
+	fn default() -> Self {
+		CaptureOptions {
+			frame_type: FrameType::Distorted,
+			capacity: 30,
+			poll_interval: DEFAULT_POLL_INTERVAL,
+		}
+	}
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CaptureStats {
+	/// Frames put in the ring buffer, including the ones pushed out of it since.
+	pub captured: u64,
+	/// Frames the camera sent that the worker never saw, from gaps in the frame sequence.
+	pub missed: u64,
+	/// Frames the worker threw away because a snapshot was being taken.
+	pub dropped: u64,
+}
+
+struct Shared {
+	ring: Mutex<VecDeque<Arc<Frame>>>,
+	capacity: usize,
+	/// Sequence of the last frame handed to `push`, only the worker touches it.
+	last_sequence: Mutex<Option<u32>>,
+	captured: AtomicU64,
+	missed: AtomicU64,
+	dropped: AtomicU64,
+	last_error: Mutex<Option<sys::EVRTrackedCameraError>>,
+}
+
+/// What became of a frame handed to the ring buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Push {
+	Pushed,
+	/// A snapshot was being taken.
+	Dropped,
+}
+
+impl Shared {
+	fn new(capacity: usize) -> Shared {
+		assert!(capacity > 0, "a capture has to keep at least one frame");
+		
+		Shared {
+			ring: Mutex::new(VecDeque::with_capacity(capacity)),
+			capacity,
+			last_sequence: Mutex::new(None),
+			captured: AtomicU64::new(0),
+			missed: AtomicU64::new(0),
+			dropped: AtomicU64::new(0),
+			last_error: Mutex::new(None),
+		}
+	}
+	
+	/// Counts the frames missed since the previous one and puts the frame in the ring buffer, pushing out the oldest
+	/// one when it's full.
+	fn push(&self, frame: Frame) -> Push {
+		let mut last_sequence = self.last_sequence.lock().unwrap();
+		if let Some(last) = *last_sequence {
+			self.missed.fetch_add(frame.sequence().saturating_sub(last + 1) as u64, Ordering::Relaxed);
+		}
+		*last_sequence = Some(frame.sequence());
+		
+		// Never wait for a snapshot, the camera doesn't wait for us either
+		match self.ring.try_lock() {
+			Ok(mut ring) => {
+				if ring.len() >= self.capacity {
+					ring.pop_front();
+				}
+				ring.push_back(Arc::new(frame));
+				self.captured.fetch_add(1, Ordering::Relaxed);
+				Push::Pushed
+			},
+			Err(_) => {
+				self.dropped.fetch_add(1, Ordering::Relaxed);
+				Push::Dropped
+			},
+		}
+	}
+}
+
+/// Keeps the latest frames of a video stream, read by a worker thread until stopped.
+pub struct Capture {
+	shared: Arc<Shared>,
+	stop: Arc<AtomicBool>,
+	worker: Option<JoinHandle<()>>,
+}
+
+impl Capture {
+	/// Panics if `options.capacity` is 0.
+	pub fn start(mut stream: VideoStream, options: CaptureOptions) -> Capture {
+		let shared = Arc::new(Shared::new(options.capacity));
+		let stop = Arc::new(AtomicBool::new(false));
+		
+		stream.set_poll_interval(options.poll_interval);
+		
+		let worker = {
+			let shared = shared.clone();
+			let stop = stop.clone();
+			
+			thread::spawn(move || {
+				while !stop.load(Ordering::Relaxed) {
+					let frame = match stream.next_frame(options.frame_type, STOP_CHECK) {
+						Ok(frame) => frame,
+						Err(FrameError::Camera(err)) | Err(FrameError::TimedOut { last: Some(err), .. }) => {
+							*shared.last_error.lock().unwrap() = Some(err.code);
+							continue;
+						},
+						Err(_) => continue,
+					};
+					
+					shared.push(frame);
+				}
+			})
+		};
+		
+		Capture { shared, stop, worker: Some(worker) }
+	}
+	
+	/// The frames in the ring buffer, oldest first.
+	pub fn snapshot(&self) -> Vec<Arc<Frame>> {
+		self.shared.ring.lock().unwrap().iter().cloned().collect()
+	}
+	
+	pub fn stats(&self) -> CaptureStats {
+		CaptureStats {
+			captured: self.shared.captured.load(Ordering::Relaxed),
+			missed: self.shared.missed.load(Ordering::Relaxed),
+			dropped: self.shared.dropped.load(Ordering::Relaxed),
+		}
+	}
+	
+	/// What reading a frame failed with last, if it ever did.
+	pub fn last_error(&self) -> Option<TrackedCameraError> {
+		self.shared.last_error.lock().unwrap().map(|code| TrackedCameraError { code })
+	}
+}
+
+impl Drop for Capture {
+	/// Stops the worker, which releases the stream.
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+		
+		if let Some(worker) = self.worker.take() {
+			// A panic of the worker was already reported, dropping shouldn't panic again
+			let _ = worker.join();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+	
+	fn frame(sequence: u32) -> Frame {
+		// Plain C struct, all zeroes is a valid header
+		let mut header: sys::CameraVideoStreamFrameHeader_t = unsafe { std::mem::zeroed() };
+		header.nFrameSequence = sequence;
+		
+		Frame { header, buffer: vec![], received: Instant::now() }
+	}
+	
+	fn sequences(shared: &Shared) -> Vec<u32> {
+		shared.ring.lock().unwrap().iter().map(|frame| frame.sequence()).collect()
+	}
+	
+	#[test]
+	fn keeps_the_latest_frames() {
+		let shared = Shared::new(3);
+		for sequence in 1..=5 {
+			assert_eq!(shared.push(frame(sequence)), Push::Pushed);
+		}
+		
+		assert_eq!(sequences(&shared), [3, 4, 5]);
+		assert_eq!(shared.captured.load(Ordering::Relaxed), 5);
+		assert_eq!(shared.missed.load(Ordering::Relaxed), 0);
+	}
+	
+	#[test]
+	fn counts_gaps_in_the_sequence_as_missed() {
+		let shared = Shared::new(10);
+		for &sequence in [7, 8, 11, 12, 20].iter() {
+			shared.push(frame(sequence));
+		}
+		
+		assert_eq!(shared.missed.load(Ordering::Relaxed), 2 + 7);
+		assert_eq!(sequences(&shared), [7, 8, 11, 12, 20]);
+	}
+	
+	#[test]
+	fn drops_frames_while_a_snapshot_holds_the_ring() {
+		let shared = Shared::new(3);
+		shared.push(frame(1));
+		
+		{
+			let _snapshot = shared.ring.lock().unwrap();
+			assert_eq!(shared.push(frame(2)), Push::Dropped);
+			assert_eq!(shared.push(frame(4)), Push::Dropped);
+		}
+		assert_eq!(shared.push(frame(5)), Push::Pushed);
+		
+		assert_eq!(sequences(&shared), [1, 5]);
+		assert_eq!(shared.captured.load(Ordering::Relaxed), 2);
+		assert_eq!(shared.dropped.load(Ordering::Relaxed), 2);
+		// Dropped frames were still seen, only 3 is missing
+		assert_eq!(shared.missed.load(Ordering::Relaxed), 1);
+	}
+	
+	#[test]
+	#[should_panic(expected = "at least one frame")]
+	fn needs_room_for_a_frame() {
+		Shared::new(0);
+	}
+}
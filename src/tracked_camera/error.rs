@@ -1,41 +1,105 @@
 use std::{error, fmt};
-use std::ffi::CStr;
+use std::time::Duration;
 use openvr_sys as sys;
 
 use super::FnTable;
+use crate::openvr_errors::{TRACKED_CAMERA_ERRORS, runtime_string};
 
 pub struct TrackedCameraError {
 	pub code: sys::EVRTrackedCameraError,
-	pub name: String,
+}
+
+impl TrackedCameraError {
+	/// What the user can do about the common failures.
+	pub fn hint(&self) -> Option<&'static str> {
+		match self.code {
+			sys::EVRTrackedCameraError_VRTrackedCameraError_NoFrameAvailable => {
+				Some("Turn on Room View in the SteamVR camera settings, the camera only streams while it's on")
+			},
+			sys::EVRTrackedCameraError_VRTrackedCameraError_NotSupportedForThisDevice => Some("Enable the camera in the SteamVR camera settings"),
+			sys::EVRTrackedCameraError_VRTrackedCameraError_OutOfHandles => Some("Close other apps that use the headset camera"),
+			sys::EVRTrackedCameraError_VRTrackedCameraError_IPCFailure |
+			sys::EVRTrackedCameraError_VRTrackedCameraError_SharedMemoryFailure |
+			sys::EVRTrackedCameraError_VRTrackedCameraError_FrameBufferingFailure |
+			sys::EVRTrackedCameraError_VRTrackedCameraError_StreamSetupFailure => Some("Restart SteamVR and close other apps that use the headset camera"),
+			_ => None,
+		}
+	}
 }
 
 impl error::Error for TrackedCameraError {}
 
 impl fmt::Debug for TrackedCameraError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&format!("{}({})", self.name, self.code))
+		f.pad(&TRACKED_CAMERA_ERRORS.symbol(self.code as i64))
 	}
 }
 
 impl fmt::Display for TrackedCameraError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		f.pad(&self.name)
+		f.pad(&format!("{} ({})", TRACKED_CAMERA_ERRORS.description(self.code as i64), TRACKED_CAMERA_ERRORS.symbol(self.code as i64)))
+	}
+}
+
+pub enum FrameError {
+	Camera(TrackedCameraError),
+	/// The stream still has frame `sequence`, the camera runs slower than it's polled.
+	NoNewFrame { sequence: u32 },
+	/// No new frame within `waited`. `last` is what the last poll failed with, if it did.
+	TimedOut { waited: Duration, last: Option<TrackedCameraError> },
+}
+
+impl FrameError {
+	pub fn hint(&self) -> Option<&'static str> {
+		match self {
+			FrameError::Camera(err) | FrameError::TimedOut { last: Some(err), .. } => err.hint(),
+			FrameError::NoNewFrame { .. } => None,
+			FrameError::TimedOut { last: None, .. } => Some("The camera stopped sending frames, turn Room View off and on again or restart SteamVR"),
+		}
+	}
+}
+
+impl From<TrackedCameraError> for FrameError {
+	fn from(err: TrackedCameraError) -> Self { FrameError::Camera(err) }
+}
+
+impl error::Error for FrameError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			FrameError::TimedOut { last: Some(err), .. } => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Debug for FrameError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FrameError::Camera(err) => write!(f, "Camera({:?})", err),
+			FrameError::NoNewFrame { sequence } => write!(f, "NoNewFrame({})", sequence),
+			FrameError::TimedOut { waited, last } => write!(f, "TimedOut({:?}, {:?})", waited, last),
+		}
+	}
+}
+
+impl fmt::Display for FrameError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			FrameError::Camera(err) => err.fmt(f),
+			FrameError::NoNewFrame { sequence } => write!(f, "No new frame since frame {}", sequence),
+			FrameError::TimedOut { waited, .. } => write!(f, "No new camera frame in {:.0?}", waited),
+		}
 	}
 }
 
+/// Also remembers what the runtime calls the error, for displaying it.
 pub fn check_err(fn_tab: FnTable, code: sys::EVRTrackedCameraError) -> Result<(), TrackedCameraError> {
 	if code == sys::EVRTrackedCameraError_VRTrackedCameraError_None {
 		Ok(())
 	} else {
-		let name = fn_tab.GetCameraErrorNameFromEnum
-		                 .map(|f| unsafe { f(code) })
-		                 .map(|msg| unsafe { CStr::from_ptr(msg) })
-		                 .map(CStr::to_str)
-		                 .map(Result::ok)
-		                 .flatten()
-		                 .unwrap_or("VRTrackedCameraError_UnknownError")
-		                 .into();
+		let symbol = fn_tab.GetCameraErrorNameFromEnum.and_then(|name| unsafe { runtime_string(name(code)) });
+		TRACKED_CAMERA_ERRORS.remember(code as i64, symbol, None);
 		
-		Err(TrackedCameraError{ code, name })
+		Err(TrackedCameraError{ code })
 	}
 }
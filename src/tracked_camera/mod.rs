@@ -1,24 +1,87 @@
-use std::marker::PhantomData;
 use openvr_sys as sys;
 
 mod error;
 mod utils;
+mod stream;
+mod capture;
 
 pub use error::*;
 pub use utils::*;
+pub use stream::*;
+pub use capture::*;
 
-use crate::openvr_load::{InitError, load, Context, TrackedDeviceIndex};
+use crate::openvr_load::{InitError, Context, TrackedDeviceIndex, TrackingUniverseOrigin};
 
-pub type FnTable = &'static sys::VR_IVRTrackedCamera_FnTable;
+/// Only valid while the session is, which the `Context` held next to it ensures.
+type FnTable = &'static sys::VR_IVRTrackedCamera_FnTable;
 
-#[derive(Copy, Clone)]
-pub struct TrackedCamera<'a>(FnTable, PhantomData<&'a Context>);
+/// Interface versions to try, the one `openvr_sys` was built with first.
+pub const VERSIONS: &[&[u8]] = &[
+	// IVRTrackedCamera_005. 003 has the same functions, but GetCameraIntrinsics and GetCameraProjection don't take the
+	// camera index yet, so there's no older version to fall back to.
+	sys::IVRTrackedCamera_Version,
+];
 
-impl<'a> TrackedCamera<'a> {
-	pub fn new(_context: &Context) -> Result<TrackedCamera, InitError> {
-		let fn_tab: FnTable = unsafe { &*load(sys::IVRTrackedCamera_Version)? };
+/// The version with the tracking space functions, bound next to the main table when the runtime has it.
+pub const TRACKING_SPACE_VERSION: &[u8] = b"IVRTrackedCamera_006\0";
+
+/// `IVRTrackedCamera_006`, which `openvr_sys` has no bindings for. It's the 005 table with two functions appended.
+#[repr(C)]
+#[allow(non_snake_case)]
+struct FnTable006 {
+	base: sys::VR_IVRTrackedCamera_FnTable,
+	SetCameraTrackingSpace: Option<unsafe extern "C" fn(eUniverse: sys::ETrackingUniverseOrigin)>,
+	GetCameraTrackingSpace: Option<unsafe extern "C" fn() -> sys::ETrackingUniverseOrigin>,
+}
+
+#[derive(Clone)]
+pub struct TrackedCamera(FnTable, #[allow(dead_code)] Context, &'static str, Option<&'static FnTable006>);
+
+impl TrackedCamera {
+	pub fn new(context: &Context) -> Result<TrackedCamera, InitError> {
+		let (fn_tab, version) = context.load::<sys::VR_IVRTrackedCamera_FnTable>(VERSIONS)?;
+		let fn_tab: FnTable = unsafe { &*(fn_tab as *const _) };
+		let tracking_space = context.load::<FnTable006>(&[TRACKING_SPACE_VERSION])
+		                            .ok()
+		                            .map(|(fn_tab, _)| unsafe { &*(fn_tab as *const FnTable006) });
 		
-		Ok(TrackedCamera(fn_tab, PhantomData))
+		Ok(TrackedCamera(fn_tab, context.clone(), version, tracking_space))
+	}
+	
+	/// The interface version the runtime bound.
+	pub fn version(&self) -> &'static str {
+		self.2
+	}
+	
+	/// Whether the runtime has `IVRTrackedCamera_006` to select the space of the frame header poses, older ones
+	/// always use standing.
+	pub fn has_tracking_space(&self) -> bool {
+		self.3.is_some()
+	}
+	
+	/// The space of the frame header poses, `None` when the interface is too old to tell.
+	pub fn camera_tracking_space(&self) -> Option<TrackingUniverseOrigin> {
+		TrackingUniverseOrigin::from_raw(self.raw_camera_tracking_space()?)
+	}
+	
+	/// Puts the frame header poses of every stream in `origin` until the returned guard is dropped. The space is shared
+	/// by every application, so the guard puts back the one that was set before. `None` when the interface is too old
+	/// for it.
+	pub fn select_camera_tracking_space(&self, origin: TrackingUniverseOrigin) -> Option<CameraTrackingSpace> {
+		let previous = self.raw_camera_tracking_space()?;
+		self.set_raw_camera_tracking_space(origin as sys::ETrackingUniverseOrigin);
+		
+		Some(CameraTrackingSpace { tracked_camera: self.clone(), previous })
+	}
+	
+	fn raw_camera_tracking_space(&self) -> Option<sys::ETrackingUniverseOrigin> {
+		self.3.map(|fn_tab| unsafe { fn_tab.GetCameraTrackingSpace.unwrap()() })
+	}
+	
+	fn set_raw_camera_tracking_space(&self, origin: sys::ETrackingUniverseOrigin) {
+		if let Some(fn_tab) = self.3 {
+			unsafe { fn_tab.SetCameraTrackingSpace.unwrap()(origin); }
+		}
 	}
 	
 	pub fn has_camera(&self, index: TrackedDeviceIndex) -> bool {
@@ -57,8 +120,12 @@ impl<'a> TrackedCamera<'a> {
 		Ok(out)
 	}
 	
-	pub fn get_camera_projection(&self, index: TrackedDeviceIndex, camera_index: u32, frame_type: FrameType, z_near: f32, z_far: f32) -> Result<[[f32; 4]; 4], TrackedCameraError> {
-		let mut out = [[0.0; 4]; 4];
+	pub fn get_camera_projection(&self, index: TrackedDeviceIndex, camera_index: u32, frame_type: FrameType, z_near: f32, z_far: f32) -> Result<Projection, TrackedCameraError> {
+		let mut out = Projection {
+			z_near,
+			z_far,
+			projection: [[0.0; 4]; 4],
+		};
 		
 		check_err(self.0, unsafe {
 			self.0.GetCameraProjection.unwrap()(index,
@@ -66,7 +133,7 @@ impl<'a> TrackedCamera<'a> {
 			                                    frame_type.into(),
 			                                    z_near,
 			                                    z_far,
-			                                    &mut out as *mut _ as *mut sys::HmdMatrix44_t)
+			                                    &mut out.projection as *mut _ as *mut sys::HmdMatrix44_t)
 		})?;
 		
 		Ok(out)
@@ -83,7 +150,6 @@ impl<'a> TrackedCamera<'a> {
 		Ok(out)
 	}
 	
-	#[allow(dead_code)]
 	pub unsafe fn release_video_streaming_service(&self, handle: TrackedCameraHandle) -> Result<(), TrackedCameraError> {
 		check_err(self.0,
 			self.0.ReleaseVideoStreamingService.unwrap()(handle)
@@ -122,3 +188,15 @@ impl<'a> TrackedCamera<'a> {
 		Ok(header)
 	}
 }
+
+/// A camera tracking space selected by `TrackedCamera::select_camera_tracking_space`, restored when dropped.
+pub struct CameraTrackingSpace {
+	tracked_camera: TrackedCamera,
+	previous: sys::ETrackingUniverseOrigin,
+}
+
+impl Drop for CameraTrackingSpace {
+	fn drop(&mut self) {
+		self.tracked_camera.set_raw_camera_tracking_space(self.previous);
+	}
+}
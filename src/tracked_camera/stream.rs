@@ -0,0 +1,189 @@
+use std::{mem, thread};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use image::RgbaImage;
+use openvr_sys as sys;
+
+use crate::openvr_load::TrackedDeviceIndex;
+use super::{TrackedCamera, TrackedCameraError, TrackedCameraHandle, CameraVideoStreamFrameHeader, FrameType, FrameError};
+
+/// How often `next_frame` asks for a new frame, a few times per frame of a 60 Hz camera.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Releases that failed, as (device, handle, error code), oldest first.
+static RELEASE_ERRORS: Mutex<Vec<(TrackedDeviceIndex, TrackedCameraHandle, sys::EVRTrackedCameraError)>> = Mutex::new(Vec::new());
+
+/// Every failed release of a video stream so far.
+pub fn release_errors() -> Vec<(TrackedDeviceIndex, TrackedCameraHandle, TrackedCameraError)> {
+	RELEASE_ERRORS.lock()
+	              .unwrap()
+	              .iter()
+	              .map(|&(device, handle, code)| (device, handle, TrackedCameraError { code }))
+	              .collect()
+}
+
+/// The video stream of a tracked device's camera, released when dropped.
+pub struct VideoStream {
+	tracked_camera: TrackedCamera,
+	device: TrackedDeviceIndex,
+	handle: Option<TrackedCameraHandle>,
+	poll_interval: Duration,
+	last_sequence: Option<u32>,
+	/// Polls read into this, it's only copied out for new frames.
+	buffer: Vec<u8>,
+	buffer_type: Option<FrameType>,
+}
+
+/// A camera frame copied out of the stream, with the header it came with.
+#[derive(Clone)]
+pub struct Frame {
+	pub header: CameraVideoStreamFrameHeader,
+	pub buffer: Vec<u8>,
+	/// When the stream returned it.
+	pub received: Instant,
+}
+
+impl Frame {
+	pub fn sequence(&self) -> u32 {
+		self.header.nFrameSequence
+	}
+	
+	/// The frame as an image, with the alpha channel OpenVR leaves undefined made opaque.
+	pub fn to_image(&self) -> Option<RgbaImage> {
+		let mut buffer = self.buffer.clone();
+		for i in (3..buffer.len()).step_by(4) {
+			buffer[i] = 255;
+		}
+		
+		RgbaImage::from_raw(self.header.nWidth, self.header.nHeight, buffer)
+	}
+}
+
+impl VideoStream {
+	pub fn acquire(tracked_camera: &TrackedCamera, device: TrackedDeviceIndex) -> Result<Self, TrackedCameraError> {
+		let handle = unsafe { tracked_camera.acquire_video_streaming_service(device)? };
+		
+		Ok(VideoStream {
+			tracked_camera: tracked_camera.clone(),
+			device,
+			handle: Some(handle),
+			poll_interval: DEFAULT_POLL_INTERVAL,
+			last_sequence: None,
+			buffer: Vec::new(),
+			buffer_type: None,
+		})
+	}
+	
+	pub fn set_poll_interval(&mut self, poll_interval: Duration) {
+		self.poll_interval = poll_interval;
+	}
+	
+	/// Reads the current frame, if it's newer than the last one this stream returned.
+	pub fn poll_frame(&mut self, frame_type: FrameType) -> Result<Frame, FrameError> {
+		if self.buffer_type != Some(frame_type) {
+			let size = self.tracked_camera.get_camera_frame_size(self.device, frame_type)?;
+			self.buffer = vec![0u8; size.frame_buffer_size as usize];
+			self.buffer_type = Some(frame_type);
+		}
+		
+		let mut buffer = mem::take(&mut self.buffer);
+		let header = self.frame_buffer(frame_type, &mut buffer);
+		self.buffer = buffer;
+		let header = header?;
+		
+		if self.last_sequence == Some(header.nFrameSequence) {
+			return Err(FrameError::NoNewFrame { sequence: header.nFrameSequence });
+		}
+		
+		self.last_sequence = Some(header.nFrameSequence);
+		
+		Ok(Frame { header, buffer: self.buffer.clone(), received: Instant::now() })
+	}
+	
+	/// Polls until there is a new frame or `timeout` passes.
+	pub fn next_frame(&mut self, frame_type: FrameType, timeout: Duration) -> Result<Frame, FrameError> {
+		let start = Instant::now();
+		
+		loop {
+			let last = match self.poll_frame(frame_type) {
+				Ok(frame) => return Ok(frame),
+				Err(FrameError::Camera(err)) => Some(err),
+				Err(_) => None,
+			};
+			
+			if start.elapsed() >= timeout {
+				return Err(FrameError::TimedOut { waited: start.elapsed(), last });
+			}
+			
+			thread::sleep(self.poll_interval);
+		}
+	}
+	
+	/// New frames as they come, each waited for up to `timeout`. It never ends by itself.
+	pub fn frames(&mut self, frame_type: FrameType, timeout: Duration) -> Frames<'_> {
+		Frames { stream: self, frame_type, timeout }
+	}
+	
+	/// Copies the latest frame into `buffer`. A stream that failed to set up is reacquired once before giving up.
+	pub fn frame_buffer(&mut self, frame_type: FrameType, buffer: &mut [u8]) -> Result<CameraVideoStreamFrameHeader, TrackedCameraError> {
+		// No handle left when the last reacquire failed
+		let handle = match self.handle {
+			Some(handle) => handle,
+			None => self.reacquire()?,
+		};
+		
+		match unsafe { self.tracked_camera.get_video_stream_frame_buffer(handle, frame_type, buffer) } {
+			Err(err) if err.code == sys::EVRTrackedCameraError_VRTrackedCameraError_StreamSetupFailure => {
+				let handle = self.reacquire()?;
+				unsafe { self.tracked_camera.get_video_stream_frame_buffer(handle, frame_type, buffer) }
+			},
+			result => result,
+		}
+	}
+	
+	/// Releases the handle and acquires a new one. A failed release is logged and recorded like on drop.
+	pub fn reacquire(&mut self) -> Result<TrackedCameraHandle, TrackedCameraError> {
+		if let Some(handle) = self.handle.take() {
+			if let Err(err) = unsafe { self.tracked_camera.release_video_streaming_service(handle) } {
+				record_release_error(self.device, handle, err);
+			}
+		}
+		
+		let handle = unsafe { self.tracked_camera.acquire_video_streaming_service(self.device)? };
+		self.handle = Some(handle);
+		
+		Ok(handle)
+	}
+}
+
+pub struct Frames<'s> {
+	stream: &'s mut VideoStream,
+	frame_type: FrameType,
+	timeout: Duration,
+}
+
+impl Iterator for Frames<'_> {
+	type Item = Result<Frame, FrameError>;
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		Some(self.stream.next_frame(self.frame_type, self.timeout))
+	}
+}
+
+impl Drop for VideoStream {
+	fn drop(&mut self) {
+		if let Some(handle) = self.handle.take() {
+			if let Err(err) = unsafe { self.tracked_camera.release_video_streaming_service(handle) } {
+				record_release_error(self.device, handle, err);
+			}
+		}
+	}
+}
+
+fn record_release_error(device: TrackedDeviceIndex, handle: TrackedCameraHandle, err: TrackedCameraError) {
+	eprintln!("Unable to release video stream {:#x} of device {}: {}", handle, device, err);
+	
+	RELEASE_ERRORS.lock()
+	              .unwrap()
+	              .push((device, handle, err.code));
+}
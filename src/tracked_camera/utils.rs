@@ -3,19 +3,72 @@ use openvr_sys as sys;
 pub type TrackedCameraHandle = openvr_sys::TrackedCameraHandle_t;
 pub type CameraVideoStreamFrameHeader = openvr_sys::CameraVideoStreamFrameHeader_t;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 pub struct FrameSize {
 	pub width: u32,
 	pub height: u32,
 	pub frame_buffer_size: u32,
 }
 
-#[derive(Default, Debug)]
+impl FrameSize {
+	/// Size of a single camera image inside the frame.
+	pub fn camera_size(&self, layout: FrameLayout) -> (u32, u32) {
+		match layout {
+			FrameLayout::Mono => (self.width, self.height),
+			FrameLayout::StereoVertical => (self.width, self.height / 2),
+			FrameLayout::StereoHorizontal => (self.width / 2, self.height),
+		}
+	}
+	
+	/// Top left corner of a single camera image inside the frame.
+	pub fn camera_offset(&self, layout: FrameLayout, camera_index: u32) -> (u32, u32) {
+		let (width, height) = self.camera_size(layout);
+		
+		match layout {
+			FrameLayout::Mono => (0, 0),
+			FrameLayout::StereoVertical => (0, height * camera_index),
+			FrameLayout::StereoHorizontal => (width * camera_index, 0),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameLayout {
+	Mono,
+	StereoVertical,
+	StereoHorizontal,
+}
+
+impl FrameLayout {
+	/// Decodes `Prop_CameraFrameLayout_Int32`, guessing from the frame shape when the property is not available.
+	pub fn new(property: Option<i32>, size: &FrameSize) -> FrameLayout {
+		match property.map(|flags| flags as sys::EVRTrackedCameraFrameLayout) {
+			Some(flags) if flags & sys::EVRTrackedCameraFrameLayout_Mono != 0 => FrameLayout::Mono,
+			Some(flags) if flags & sys::EVRTrackedCameraFrameLayout_HorizontalLayout != 0 => FrameLayout::StereoHorizontal,
+			Some(flags) if flags & sys::EVRTrackedCameraFrameLayout_VerticalLayout != 0 => FrameLayout::StereoVertical,
+			_ if size.height >= size.width * 2 => FrameLayout::StereoVertical,
+			_ if size.width >= size.height * 2 => FrameLayout::StereoHorizontal,
+			_ => FrameLayout::Mono,
+		}
+	}
+	
+	pub fn camera_count(&self) -> u32 {
+		match self {
+			FrameLayout::Mono => 1,
+			FrameLayout::StereoVertical | FrameLayout::StereoHorizontal => 2,
+		}
+	}
+}
+
+#[derive(Default, Debug, Clone, Copy)]
 pub struct Intrinsics {
 	pub focal_length: [f32; 2],
 	pub center: [f32; 2],
 }
 
+pub const Z_NEAR: f32 = 0.01;
+pub const Z_FAR: f32 = 100.01;
+
 #[derive(Default, Debug)]
 pub struct Projection {
 	pub z_near: f32,
@@ -23,6 +76,66 @@ pub struct Projection {
 	pub projection: [[f32; 4]; 4],
 }
 
+impl Projection {
+	/// Tangents of the frustum sides, in OpenVR view space (+X right, +Y up, looking down -Z).
+	pub fn frustum(&self) -> Frustum {
+		let m = &self.projection;
+		let (m00, m02) = (m[0][0] as f64, m[0][2] as f64);
+		let (m11, m12) = (m[1][1] as f64, m[1][2] as f64);
+		
+		Frustum {
+			left: (m02 - 1.0) / m00,
+			right: (m02 + 1.0) / m00,
+			bottom: (m12 - 1.0) / m11,
+			top: (m12 + 1.0) / m11,
+		}
+	}
+	
+	/// Pinhole intrinsics equivalent to this projection for an image of `width`x`height`, with Y pointing down.
+	pub fn intrinsics(&self, width: u32, height: u32) -> Intrinsics {
+		let m = &self.projection;
+		
+		Intrinsics {
+			focal_length: [m[0][0] * width as f32 / 2.0, m[1][1] * height as f32 / 2.0],
+			center: [(1.0 - m[0][2]) * width as f32 / 2.0, (1.0 + m[1][2]) * height as f32 / 2.0],
+		}
+	}
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Frustum {
+	pub left: f64,
+	pub right: f64,
+	pub bottom: f64,
+	pub top: f64,
+}
+
+impl Frustum {
+	pub fn fov(&self) -> Fov {
+		let corner = |x: f64, y: f64| {
+			let length = (x * x + y * y + 1.0).sqrt();
+			[x / length, y / length, -1.0 / length]
+		};
+		let a = corner(self.left, self.top);
+		let b = corner(self.right, self.bottom);
+		let dot = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+		
+		Fov {
+			horizontal: (self.right.atan() - self.left.atan()).to_degrees(),
+			vertical: (self.top.atan() - self.bottom.atan()).to_degrees(),
+			diagonal: dot.clamp(-1.0, 1.0).acos().to_degrees(),
+		}
+	}
+}
+
+/// Field of view in degrees.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Fov {
+	pub horizontal: f64,
+	pub vertical: f64,
+	pub diagonal: f64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FrameType {
 	Distorted = sys::EVRTrackedCameraFrameType_VRTrackedCameraFrameType_Distorted as isize,
@@ -30,12 +143,47 @@ pub enum FrameType {
 	MaximumUndistorted = sys::EVRTrackedCameraFrameType_VRTrackedCameraFrameType_MaximumUndistorted as isize,
 }
 
-impl Into<sys::EVRTrackedCameraFrameType> for FrameType { fn into(self) -> sys::EVRTrackedCameraFrameType { self as sys::EVRTrackedCameraFrameType } }
-impl Into<FrameType> for sys::EVRTrackedCameraFrameType { fn into(self) -> FrameType {
-	match self {
+impl From<FrameType> for sys::EVRTrackedCameraFrameType { fn from(frame_type: FrameType) -> Self { frame_type as sys::EVRTrackedCameraFrameType } }
+impl From<sys::EVRTrackedCameraFrameType> for FrameType { fn from(frame_type: sys::EVRTrackedCameraFrameType) -> Self {
+	match frame_type {
 		sys::EVRTrackedCameraFrameType_VRTrackedCameraFrameType_Distorted => FrameType::Distorted,
 		sys::EVRTrackedCameraFrameType_VRTrackedCameraFrameType_Undistorted => FrameType::Undistorted,
 		sys::EVRTrackedCameraFrameType_VRTrackedCameraFrameType_MaximumUndistorted => FrameType::MaximumUndistorted,
-		_ => panic!("Unknown TrackedCameraFrameType = {}", self),
+		_ => panic!("Unknown TrackedCameraFrameType = {}", frame_type),
 	}
 } }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn projection_gives_back_its_intrinsics() {
+		let (fx, fy, cx, cy, width, height) = (280.0, 290.0, 300.0, 260.0, 612.0, 460.0);
+		// Frustum tangents of those intrinsics, +Y up
+		let (left, right) = (-cx / fx, (width - cx) / fx);
+		let (bottom, top) = (-(height - cy) / fy, cy / fy);
+		
+		let mut projection = Projection { z_near: Z_NEAR, z_far: Z_FAR, projection: [[0.0; 4]; 4] };
+		projection.projection[0][0] = (2.0 / (right - left)) as f32;
+		projection.projection[0][2] = ((right + left) / (right - left)) as f32;
+		projection.projection[1][1] = (2.0 / (top - bottom)) as f32;
+		projection.projection[1][2] = ((top + bottom) / (top - bottom)) as f32;
+		
+		let intrinsics = projection.intrinsics(width as u32, height as u32);
+		let expected = [fx, fy, cx, cy];
+		let actual = [intrinsics.focal_length[0], intrinsics.focal_length[1], intrinsics.center[0], intrinsics.center[1]];
+		for (actual, expected) in actual.iter().zip(expected) {
+			assert!((*actual as f64 - expected).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+		}
+		
+		let frustum = projection.frustum();
+		for (actual, expected) in [frustum.left, frustum.right, frustum.bottom, frustum.top].iter().zip([left, right, bottom, top]) {
+			assert!((actual - expected).abs() < 1e-6);
+		}
+		
+		let fov = frustum.fov();
+		assert!((fov.horizontal - (right.atan() - left.atan()).to_degrees()).abs() < 1e-4);
+		assert!(fov.diagonal > fov.horizontal && fov.diagonal > fov.vertical);
+	}
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::path::Path;
+use image::GrayImage;
+
+use super::{BoardError, Corner, CornerGrid, Dictionary, FloatImage, Homography, detect_corners, grow_grid, read_marker};
+
+#[derive(Debug, Clone)]
+pub enum BoardKind {
+	Checkerboard,
+	/// Markers fill the white squares, ids go in row major order. The top left square is black, like the current
+	/// OpenCV pattern.
+	Charuco { marker: f64, dictionary: Dictionary },
+}
+
+/// Calibration target, sizes are in squares and meters.
+#[derive(Debug, Clone)]
+pub struct Board {
+	pub columns: u32,
+	pub rows: u32,
+	pub square: f64,
+	pub kind: BoardKind,
+}
+
+/// Inner corners found in an image, with pixel centers at +0.5.
+#[derive(Debug, Clone, Default)]
+pub struct BoardDetection {
+	pub ids: Vec<u32>,
+	pub pixels: Vec<[f64; 2]>,
+}
+
+impl Board {
+	/// Parses `checkerboard:<columns>x<rows>:<square>` or `charuco:<columns>x<rows>:<square>:<marker>:<dictionary>`.
+	pub fn parse(spec: &str) -> Result<Board, BoardError> {
+		let invalid = || BoardError::Spec(spec.to_string());
+		let parts = spec.splitn(5, ':').collect::<Vec<_>>();
+		
+		let size = parts.get(1).ok_or_else(invalid)?.split('x').map(str::parse::<u32>).collect::<Result<Vec<_>, _>>().map_err(|_| invalid())?;
+		let square = parts.get(2).ok_or_else(invalid)?.parse::<f64>().map_err(|_| invalid())?;
+		
+		if size.len() != 2 || size[0] < 3 || size[1] < 3 || square <= 0.0 {
+			return Err(invalid());
+		}
+		
+		let kind = match (parts[0], parts.len()) {
+			("checkerboard", 3) => BoardKind::Checkerboard,
+			("charuco", 5) => BoardKind::Charuco {
+				marker: parts[3].parse::<f64>().ok().filter(|&marker| marker > 0.0 && marker < square).ok_or_else(invalid)?,
+				dictionary: Dictionary::load(Path::new(parts[4]))?,
+			},
+			_ => return Err(invalid()),
+		};
+		
+		Ok(Board { columns: size[0], rows: size[1], square, kind })
+	}
+	
+	/// Inner corners per row and per column.
+	pub fn corner_size(&self) -> (u32, u32) {
+		(self.columns - 1, self.rows - 1)
+	}
+	
+	pub fn corner_count(&self) -> usize {
+		let (columns, rows) = self.corner_size();
+		(columns * rows) as usize
+	}
+	
+	/// Column and row of the square a ChArUco marker is in.
+	pub fn marker_square(&self, id: u32) -> Option<(u32, u32)> {
+		(0..self.rows).flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+		              .filter(|(column, row)| column % 2 != row % 2)
+		              .nth(id as usize)
+	}
+	
+	/// Corner position on the board, +X along the rows, +Y down the columns, Z out of the back.
+	pub fn object_point(&self, id: u32) -> [f64; 3] {
+		let (columns, _) = self.corner_size();
+		[(id % columns + 1) as f64 * self.square, (id / columns + 1) as f64 * self.square, 0.0]
+	}
+	
+	pub fn detect(&self, image: &GrayImage) -> Option<BoardDetection> {
+		let corners = detect_corners(image);
+		let grid = grow_grid(&corners);
+		let (columns, rows) = self.corner_size();
+		
+		// Grid coordinates to board corner coordinates, counted from the outer top left corner
+		let transform = match &self.kind {
+			BoardKind::Checkerboard => {
+				if grid.len() != self.corner_count() {
+					return None;
+				}
+				
+				checkerboard_transform(&corners, &grid, columns as i32, rows as i32)?
+			},
+			BoardKind::Charuco { marker, dictionary } => charuco_transform(image, &corners, &grid, self, *marker, dictionary)?,
+		};
+		
+		let mut detection = BoardDetection::default();
+		let mut cells = grid.iter().collect::<Vec<_>>();
+		cells.sort();
+		
+		for (&(i, j), &index) in cells {
+			let (x, y) = transform.apply(i, j);
+			
+			if x >= 1 && y >= 1 && x <= columns as i32 && y <= rows as i32 {
+				detection.ids.push((y - 1) as u32 * columns + (x - 1) as u32);
+				detection.pixels.push([corners[index].x, corners[index].y]);
+			}
+		}
+		
+		if detection.ids.len() >= 6 { Some(detection) } else { None }
+	}
+}
+
+/// One of the four rotations by 90 degrees followed by an offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GridTransform {
+	rotation: u8,
+	offset: (i32, i32),
+}
+
+impl GridTransform {
+	fn rotate(rotation: u8, x: i32, y: i32) -> (i32, i32) {
+		match rotation % 4 {
+			0 => (x, y),
+			1 => (-y, x),
+			2 => (-x, -y),
+			_ => (y, -x),
+		}
+	}
+	
+	fn apply(&self, x: i32, y: i32) -> (i32, i32) {
+		let (x, y) = GridTransform::rotate(self.rotation, x, y);
+		(x + self.offset.0, y + self.offset.1)
+	}
+}
+
+/// Picks the rotation that fits the grid on the board with its first row closest to the top of the image.
+///
+/// A checkerboard is symmetric, so the board must not be turned upside down between captures.
+fn checkerboard_transform(corners: &[Corner], grid: &CornerGrid, columns: i32, rows: i32) -> Option<GridTransform> {
+	let width = grid.keys().map(|&(i, _)| i).max()?;
+	let height = grid.keys().map(|&(_, j)| j).max()?;
+	let position = |cell: (i32, i32)| grid.get(&cell).map(|&index| (corners[index].x, corners[index].y));
+	
+	(0..4u8).filter_map(|rotation| {
+		let cells = [(0, 0), (width, 0), (0, height), (width, height)];
+		let rotated = cells.iter().map(|&(i, j)| GridTransform::rotate(rotation, i, j)).collect::<Vec<_>>();
+		let min = (rotated.iter().map(|c| c.0).min()?, rotated.iter().map(|c| c.1).min()?);
+		let transform = GridTransform { rotation, offset: (1 - min.0, 1 - min.1) };
+		
+		let (w, h) = GridTransform::rotate(rotation, width, height);
+		if w.abs() + 1 != columns || h.abs() + 1 != rows {
+			return None;
+		}
+		
+		// Average image y of the board's first row
+		let first_row = cells.iter().zip(&rotated).filter(|(_, &(_, y))| y + transform.offset.1 == 1).filter_map(|(&cell, _)| position(cell)).map(|p| p.1).sum::<f64>();
+		Some((first_row, transform))
+	}).min_by(|a, b| a.0.total_cmp(&b.0)).map(|(_, transform)| transform)
+}
+
+/// Decodes the markers in the grid squares, every marker votes for where the grid sits on the board.
+fn charuco_transform(image: &GrayImage, corners: &[Corner], grid: &CornerGrid, board: &Board, marker: f64, dictionary: &Dictionary) -> Option<GridTransform> {
+	let float = FloatImage::from_gray(image);
+	let bits = dictionary.bits;
+	let margin = (1.0 - marker / board.square) / 2.0;
+	let mut votes = HashMap::new();
+	
+	for &(i, j) in grid.keys() {
+		let square = [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)];
+		let pixels = match square.iter().map(|cell| grid.get(cell).map(|&index| [corners[index].x - 0.5, corners[index].y - 0.5])).collect::<Option<Vec<_>>>() {
+			Some(pixels) => pixels,
+			None => continue,
+		};
+		
+		let homography = match Homography::from_points(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]], &pixels) {
+			Some(homography) => homography,
+			None => continue,
+		};
+		
+		let code = match read_marker(&float, &homography, bits, margin) {
+			Some(code) => code,
+			None => continue,
+		};
+		
+		let center = (bits as i32 - 1) as f64 / 2.0;
+		
+		for rotation in 0..4u8 {
+			// The same rotation applied to the bits, around their center
+			let mut rotated = vec![false; bits * bits];
+			for (index, &bit) in code.iter().enumerate() {
+				let (x, y) = ((index % bits) as f64 - center, (index / bits) as f64 - center);
+				let (rx, ry) = GridTransform::rotate(rotation, (x * 2.0) as i32, (y * 2.0) as i32);
+				let (rx, ry) = ((rx as f64 / 2.0 + center).round() as usize, (ry as f64 / 2.0 + center).round() as usize);
+				rotated[ry * bits + rx] = bit;
+			}
+			
+			let (column, row) = match dictionary.find(&rotated).and_then(|id| board.marker_square(id)) {
+				Some(square) => square,
+				None => continue,
+			};
+			
+			let rotated_square = square.iter().map(|&(x, y)| GridTransform::rotate(rotation, x, y)).collect::<Vec<_>>();
+			let min = (rotated_square.iter().map(|c| c.0).min()?, rotated_square.iter().map(|c| c.1).min()?);
+			let transform = GridTransform { rotation, offset: (column as i32 - min.0, row as i32 - min.1) };
+			
+			*votes.entry(transform).or_insert(0) += 1;
+		}
+	}
+	
+	votes.into_iter().max_by_key(|&(_, count)| count).map(|(transform, _)| transform)
+}
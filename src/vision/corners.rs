@@ -0,0 +1,378 @@
+use std::collections::{HashMap, VecDeque};
+use image::GrayImage;
+
+/// Standard deviation of the blur applied before looking for saddle points.
+pub const SADDLE_BLUR: f32 = 1.5;
+/// Saddle points have to be the strongest within this radius.
+pub const SADDLE_RADIUS: i32 = 4;
+/// Radius of the circle checked for the four alternating sectors of a checkerboard corner.
+pub const RING_RADIUS: f64 = 4.0;
+const RING_SAMPLES: usize = 24;
+/// Half size of the window the subpixel refinement looks at.
+pub const REFINE_RADIUS: i32 = 5;
+const REFINE_ITERATIONS: usize = 10;
+/// How far from its predicted position, relative to the grid step, a corner can be to join the grid. Small enough
+/// to skip the corners between the bits of ChArUco markers.
+pub const GRID_SNAP: f64 = 0.2;
+/// Minimum difference between the dark and bright sectors around a corner.
+pub const MIN_CORNER_CONTRAST: f32 = 20.0;
+
+/// Checkerboard style X corner, with pixel centers at +0.5.
+#[derive(Debug, Clone, Copy)]
+pub struct Corner {
+	pub x: f64,
+	pub y: f64,
+	pub strength: f32,
+}
+
+/// Single channel float image.
+pub struct FloatImage {
+	pub width: usize,
+	pub height: usize,
+	pub data: Vec<f32>,
+}
+
+impl FloatImage {
+	pub fn from_gray(image: &GrayImage) -> FloatImage {
+		FloatImage {
+			width: image.width() as usize,
+			height: image.height() as usize,
+			data: image.as_raw().iter().map(|&v| v as f32).collect(),
+		}
+	}
+	
+	pub fn at(&self, x: i32, y: i32) -> f32 {
+		let x = x.max(0).min(self.width as i32 - 1) as usize;
+		let y = y.max(0).min(self.height as i32 - 1) as usize;
+		self.data[y * self.width + x]
+	}
+	
+	/// Bilinear sample, pixel centers are at integer coordinates here.
+	pub fn sample(&self, x: f64, y: f64) -> f32 {
+		let (x0, y0) = (x.floor(), y.floor());
+		let (fx, fy) = ((x - x0) as f32, (y - y0) as f32);
+		let (x0, y0) = (x0 as i32, y0 as i32);
+		
+		let top = self.at(x0, y0) * (1.0 - fx) + self.at(x0 + 1, y0) * fx;
+		let bottom = self.at(x0, y0 + 1) * (1.0 - fx) + self.at(x0 + 1, y0 + 1) * fx;
+		top * (1.0 - fy) + bottom * fy
+	}
+	
+	pub fn blur(&self, sigma: f32) -> FloatImage {
+		let radius = (sigma * 3.0).ceil() as i32;
+		let kernel = (-radius..=radius).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect::<Vec<_>>();
+		let sum = kernel.iter().sum::<f32>();
+		let kernel = kernel.iter().map(|k| k / sum).collect::<Vec<_>>();
+		
+		let pass = |source: &FloatImage, horizontal: bool| {
+			let mut data = vec![0.0; source.data.len()];
+			for y in 0..source.height as i32 {
+				for x in 0..source.width as i32 {
+					data[y as usize * source.width + x as usize] = kernel.iter().enumerate().map(|(i, k)| {
+						let offset = i as i32 - radius;
+						k * if horizontal { source.at(x + offset, y) } else { source.at(x, y + offset) }
+					}).sum();
+				}
+			}
+			
+			FloatImage { width: source.width, height: source.height, data }
+		};
+		
+		pass(&pass(self, true), false)
+	}
+	
+	/// Gradient and hessian from central differences.
+	fn derivatives(&self, x: f64, y: f64) -> ([f64; 2], [f64; 3]) {
+		let s = |dx: f64, dy: f64| self.sample(x + dx, y + dy) as f64;
+		let center = s(0.0, 0.0);
+		
+		(
+			[(s(1.0, 0.0) - s(-1.0, 0.0)) / 2.0, (s(0.0, 1.0) - s(0.0, -1.0)) / 2.0],
+			[
+				s(1.0, 0.0) - 2.0 * center + s(-1.0, 0.0),
+				(s(1.0, 1.0) - s(1.0, -1.0) - s(-1.0, 1.0) + s(-1.0, -1.0)) / 4.0,
+				s(0.0, 1.0) - 2.0 * center + s(0.0, -1.0),
+			],
+		)
+	}
+}
+
+/// Finds checkerboard corners as saddle points of the blurred image, refined to subpixel precision.
+pub fn detect_corners(image: &GrayImage) -> Vec<Corner> {
+	let raw = FloatImage::from_gray(image);
+	let blurred = raw.blur(SADDLE_BLUR);
+	let (width, height) = (blurred.width as i32, blurred.height as i32);
+	let margin = SADDLE_RADIUS.max(RING_RADIUS.ceil() as i32 + 2);
+	
+	let mut response = vec![0.0f32; blurred.data.len()];
+	for y in margin..height - margin {
+		for x in margin..width - margin {
+			let (_, [xx, xy, yy]) = blurred.derivatives(x as f64, y as f64);
+			response[(y * width + x) as usize] = (xy * xy - xx * yy).max(0.0) as f32;
+		}
+	}
+	
+	let strongest = response.iter().cloned().fold(0.0, f32::max);
+	let mut corners: Vec<Corner> = vec![];
+	
+	for y in margin..height - margin {
+		for x in margin..width - margin {
+			let value = response[(y * width + x) as usize];
+			if value <= strongest * 0.01 {
+				continue;
+			}
+			
+			let is_maximum = (-SADDLE_RADIUS..=SADDLE_RADIUS).all(|dy| (-SADDLE_RADIUS..=SADDLE_RADIUS).all(|dx| {
+				// Ties go to the first pixel in raster order
+				let other = response[((y + dy) * width + x + dx) as usize];
+				(dx == 0 && dy == 0) || other < value || (other == value && (dy, dx) > (0, 0))
+			}));
+			
+			if !is_maximum {
+				continue;
+			}
+			
+			let (cx, cy) = match refine_corner(&raw, x as f64, y as f64) {
+				Some(position) => position,
+				None => continue,
+			};
+			
+			if is_x_corner(&raw, cx, cy) && corners.iter().all(|c| (c.x - 0.5 - cx).powi(2) + (c.y - 0.5 - cy).powi(2) > 4.0) {
+				corners.push(Corner { x: cx + 0.5, y: cy + 0.5, strength: value });
+			}
+		}
+	}
+	
+	corners
+}
+
+/// Moves the corner to where the image gradients around it all point away from it, like OpenCV's `cornerSubPix`.
+fn refine_corner(image: &FloatImage, x: f64, y: f64) -> Option<(f64, f64)> {
+	let (mut px, mut py) = (x, y);
+	
+	for _ in 0..REFINE_ITERATIONS {
+		let mut normal = [0.0; 3];
+		let mut target = [0.0; 2];
+		
+		for dy in -REFINE_RADIUS..=REFINE_RADIUS {
+			for dx in -REFINE_RADIUS..=REFINE_RADIUS {
+				let (sx, sy) = (px + dx as f64, py + dy as f64);
+				let gx = (image.sample(sx + 1.0, sy) - image.sample(sx - 1.0, sy)) as f64 / 2.0;
+				let gy = (image.sample(sx, sy + 1.0) - image.sample(sx, sy - 1.0)) as f64 / 2.0;
+				let weight = (-((dx * dx + dy * dy) as f64) / (REFINE_RADIUS * REFINE_RADIUS) as f64).exp();
+				let (xx, xy, yy) = (gx * gx * weight, gx * gy * weight, gy * gy * weight);
+				
+				normal[0] += xx;
+				normal[1] += xy;
+				normal[2] += yy;
+				target[0] += xx * sx + xy * sy;
+				target[1] += xy * sx + yy * sy;
+			}
+		}
+		
+		let determinant = normal[0] * normal[2] - normal[1] * normal[1];
+		if determinant.abs() < 1e-9 {
+			return None;
+		}
+		
+		let qx = (normal[2] * target[0] - normal[1] * target[1]) / determinant;
+		let qy = (normal[0] * target[1] - normal[1] * target[0]) / determinant;
+		let moved = (qx - px).powi(2) + (qy - py).powi(2);
+		px = qx;
+		py = qy;
+		
+		if (px - x).abs() > 2.0 || (py - y).abs() > 2.0 {
+			return None;
+		}
+		
+		if moved < 1e-6 {
+			break;
+		}
+	}
+	
+	Some((px, py))
+}
+
+/// Checks for two dark and two bright sectors alternating around the point.
+fn is_x_corner(image: &FloatImage, x: f64, y: f64) -> bool {
+	let samples = (0..RING_SAMPLES).map(|i| {
+		let angle = i as f64 * std::f64::consts::PI * 2.0 / RING_SAMPLES as f64;
+		image.sample(x + RING_RADIUS * angle.cos(), y + RING_RADIUS * angle.sin())
+	}).collect::<Vec<_>>();
+	
+	let min = samples.iter().cloned().fold(f32::MAX, f32::min);
+	let max = samples.iter().cloned().fold(f32::MIN, f32::max);
+	if max - min < MIN_CORNER_CONTRAST {
+		return false;
+	}
+	
+	let middle = (min + max) / 2.0;
+	let transitions = (0..RING_SAMPLES).filter(|&i| (samples[i] > middle) != (samples[(i + 1) % RING_SAMPLES] > middle)).count();
+	
+	transitions == 4
+}
+
+/// Corners organized in a grid, `(column, row)` to corner index.
+pub type CornerGrid = HashMap<(i32, i32), usize>;
+
+/// Links corners into the largest grid it can grow, following neighbours and extrapolating along rows and columns
+/// so the grid can bend with lens distortion.
+///
+/// The grid starts at (0, 0) and is right handed in the image: columns go right when rows go down.
+pub fn grow_grid(corners: &[Corner]) -> CornerGrid {
+	let neighbours = corners.iter().map(|corner| {
+		let mut distances = corners.iter().enumerate().map(|(index, other)| (distance(corner, other), index)).collect::<Vec<_>>();
+		distances.sort_by(|a, b| a.0.total_cmp(&b.0));
+		distances.into_iter().skip(1).take(8).collect::<Vec<_>>()
+	}).collect::<Vec<_>>();
+	
+	let mut seeds = (0..corners.len()).collect::<Vec<_>>();
+	seeds.sort_by(|&a, &b| corners[b].strength.total_cmp(&corners[a].strength));
+	
+	let mut best = CornerGrid::new();
+	for &seed in seeds.iter().take(20) {
+		// The nearest neighbour isn't always along the grid, ChArUco markers have corners of their own
+		for first in 0..neighbours[seed].len().min(4) {
+			if let Some(grid) = grow_from(corners, &neighbours, seed, first) {
+				if grid.len() > best.len() {
+					best = grid;
+				}
+			}
+		}
+	}
+	
+	best
+}
+
+fn distance(a: &Corner, b: &Corner) -> f64 {
+	((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+fn grow_from(corners: &[Corner], neighbours: &[Vec<(f64, usize)>], seed: usize, first: usize) -> Option<CornerGrid> {
+	let position = |index: usize| [corners[index].x, corners[index].y];
+	let (length, first) = *neighbours[seed].get(first)?;
+	let u = sub(position(first), position(seed));
+	
+	let &(_, second) = neighbours[seed].iter().find(|(other_length, other)| {
+		let v = sub(position(*other), position(seed));
+		let cosine = (u[0] * v[0] + u[1] * v[1]) / (length * other_length);
+		
+		cosine.abs() < 0.5 && *other_length < length * 2.0 && *other_length > length * 0.5
+	})?;
+	let v = sub(position(second), position(seed));
+	
+	let mut grid = CornerGrid::new();
+	let mut used = vec![false; corners.len()];
+	let mut queue = VecDeque::new();
+	
+	for &(cell, index) in &[((0, 0), seed), ((1, 0), first), ((0, 1), second)] {
+		grid.insert(cell, index);
+		used[index] = true;
+		queue.push_back(cell);
+	}
+	
+	while let Some((i, j)) = queue.pop_front() {
+		for &(di, dj) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+			let target = (i + di, j + dj);
+			if grid.contains_key(&target) {
+				continue;
+			}
+			
+			let here = position(grid[&(i, j)]);
+			// Step along (di, dj) from the nearest cells that have one, or the seed axes
+			let step = [(i, j), (i + dj, j + di), (i - dj, j - di)].iter().find_map(|&(ci, cj)| {
+				match (grid.get(&(ci, cj)), grid.get(&(ci - di, cj - dj)), grid.get(&(ci + di, cj + dj))) {
+					(Some(&c), Some(&back), _) => Some(sub(position(c), position(back))),
+					(Some(&c), None, Some(&forward)) => Some(sub(position(forward), position(c))),
+					_ => None,
+				}
+			}).unwrap_or_else(|| {
+				let axis = if di != 0 { u } else { v };
+				let sign = (di + dj) as f64;
+				[axis[0] * sign, axis[1] * sign]
+			});
+			
+			let predicted = [here[0] + step[0], here[1] + step[1]];
+			let radius = (step[0] * step[0] + step[1] * step[1]).sqrt() * GRID_SNAP;
+			
+			let nearest = (0..corners.len()).filter(|&index| !used[index]).min_by(|&a, &b| {
+				let da = (corners[a].x - predicted[0]).powi(2) + (corners[a].y - predicted[1]).powi(2);
+				let db = (corners[b].x - predicted[0]).powi(2) + (corners[b].y - predicted[1]).powi(2);
+				da.total_cmp(&db)
+			});
+			
+			if let Some(index) = nearest {
+				if ((corners[index].x - predicted[0]).powi(2) + (corners[index].y - predicted[1]).powi(2)).sqrt() <= radius {
+					grid.insert(target, index);
+					used[index] = true;
+					queue.push_back(target);
+				}
+			}
+		}
+	}
+	
+	// Mirror left handed grids and move the origin to (0, 0)
+	let cross = u[0] * v[1] - u[1] * v[0];
+	let min_i = grid.keys().map(|&(i, j)| if cross > 0.0 { i } else { j }).min()?;
+	let min_j = grid.keys().map(|&(i, j)| if cross > 0.0 { j } else { i }).min()?;
+	
+	Some(grid.into_iter().map(|((i, j), index)| {
+		let (i, j) = if cross > 0.0 { (i, j) } else { (j, i) };
+		((i - min_i, j - min_j), index)
+	}).collect())
+}
+
+fn sub(a: [f64; 2], b: [f64; 2]) -> [f64; 2] {
+	[a[0] - b[0], a[1] - b[1]]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const SQUARE: f64 = 20.0;
+	const ORIGIN: f64 = 30.0;
+	
+	/// 8x6 squares on a white background, 7x5 interior corners at `ORIGIN + SQUARE * n`.
+	fn checkerboard() -> GrayImage {
+		GrayImage::from_fn(220, 180, |x, y| {
+			let column = ((x as f64 + 0.5 - ORIGIN) / SQUARE).floor() as i32;
+			let row = ((y as f64 + 0.5 - ORIGIN) / SQUARE).floor() as i32;
+			let on_board = (0..8).contains(&column) && (0..6).contains(&row);
+			image::Luma([if on_board && (column + row) % 2 == 0 { 20 } else { 230 }])
+		})
+	}
+	
+	#[test]
+	fn finds_the_interior_corners() {
+		let corners = detect_corners(&checkerboard());
+		assert_eq!(corners.len(), 35);
+		
+		for corner in &corners {
+			let column = ((corner.x - ORIGIN) / SQUARE).round();
+			let row = ((corner.y - ORIGIN) / SQUARE).round();
+			assert!((1.0..=7.0).contains(&column) && (1.0..=5.0).contains(&row), "{:?}", corner);
+			assert!((corner.x - ORIGIN - column * SQUARE).abs() < 0.1, "{:?}", corner);
+			assert!((corner.y - ORIGIN - row * SQUARE).abs() < 0.1, "{:?}", corner);
+		}
+	}
+	
+	#[test]
+	fn grows_the_whole_grid() {
+		let corners = detect_corners(&checkerboard());
+		let grid = grow_grid(&corners);
+		assert_eq!(grid.len(), 35);
+		
+		let mut size = [grid.keys().map(|&(i, _)| i).max().unwrap(), grid.keys().map(|&(_, j)| j).max().unwrap()];
+		size.sort();
+		assert_eq!(size, [4, 6]);
+		
+		for (&(i, j), &index) in &grid {
+			for next in [(i + 1, j), (i, j + 1)] {
+				if let Some(&other) = grid.get(&next) {
+					assert!((distance(&corners[index], &corners[other]) - SQUARE).abs() < 0.1);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,32 @@
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum BoardError {
+	Spec(String),
+	Io(io::Error),
+	Dictionary(String),
+}
+
+impl error::Error for BoardError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			BoardError::Io(err) => Some(err),
+			BoardError::Spec(_) | BoardError::Dictionary(_) => None,
+		}
+	}
+}
+
+impl fmt::Display for BoardError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BoardError::Spec(spec) => write!(f, "Invalid board {}, use checkerboard:<columns>x<rows>:<square m> \
+			                                     or charuco:<columns>x<rows>:<square m>:<marker m>:<dictionary file>", spec),
+			BoardError::Io(err) => write!(f, "Unable to read marker dictionary: {}", err),
+			BoardError::Dictionary(reason) => write!(f, "Invalid marker dictionary: {}", reason),
+		}
+	}
+}
+
+impl From<io::Error> for BoardError {
+	fn from(err: io::Error) -> Self { BoardError::Io(err) }
+}
@@ -0,0 +1,280 @@
+use image::{GrayImage, RgbaImage};
+
+/// Half size of the patches compared by normalized cross correlation.
+pub const PATCH_RADIUS: i32 = 5;
+/// Half size of the window the corner response is summed over.
+pub const CORNER_RADIUS: i32 = 2;
+/// Features are spread over a grid of this many cells per side, so a single textured spot can't take all of them.
+pub const FEATURE_GRID: u32 = 8;
+/// Minimum normalized cross correlation of an accepted match.
+pub const MIN_CORRELATION: f64 = 0.85;
+/// The best match has to beat the second best one by this much.
+pub const UNIQUENESS_MARGIN: f64 = 0.03;
+/// Search range of the stereo matcher, in rectified pixels.
+pub const MAX_VERTICAL_DISPARITY: f64 = 24.0;
+pub const MAX_DISPARITY: f64 = 256.0;
+
+pub fn to_gray(image: &RgbaImage) -> GrayImage {
+	image::imageops::grayscale(image)
+}
+
+/// Corner at the center of pixel (`x`, `y`).
+#[derive(Debug, Clone, Copy)]
+pub struct Feature {
+	pub x: u32,
+	pub y: u32,
+	pub response: f32,
+}
+
+/// Pair of matched points, in pixel coordinates where pixel centers are at +0.5.
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureMatch {
+	pub first: [f64; 2],
+	pub second: [f64; 2],
+	pub correlation: f64,
+}
+
+impl FeatureMatch {
+	/// `y_second - y_first`, zero for a perfectly rectified pair.
+	pub fn vertical_disparity(&self) -> f64 {
+		self.second[1] - self.first[1]
+	}
+}
+
+/// Shi-Tomasi corners, strongest first.
+///
+/// `mask` is row major and marks valid pixels, features are only taken where the whole patch is valid.
+pub fn detect_features(image: &GrayImage, mask: Option<&[bool]>, max_features: usize) -> Vec<Feature> {
+	let width = image.width() as i32;
+	let height = image.height() as i32;
+	let at = |x: i32, y: i32| image.get_pixel(x.max(0).min(width - 1) as u32, y.max(0).min(height - 1) as u32)[0] as f32;
+	
+	// Structure tensor terms, and how many masked out pixels are around each pixel
+	let margin = PATCH_RADIUS + CORNER_RADIUS + 2;
+	let mut xx = Integral::new(image.width(), image.height());
+	let mut yy = Integral::new(image.width(), image.height());
+	let mut xy = Integral::new(image.width(), image.height());
+	let mut invalid = Integral::new(image.width(), image.height());
+	
+	for y in 0..height {
+		for x in 0..width {
+			let gx = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1) - at(x - 1, y - 1) - 2.0 * at(x - 1, y) - at(x - 1, y + 1)) / 8.0;
+			let gy = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1) - at(x - 1, y - 1) - 2.0 * at(x, y - 1) - at(x + 1, y - 1)) / 8.0;
+			let masked = mask.is_some_and(|mask| !mask[(y * width + x) as usize]);
+			
+			xx.push(gx * gx);
+			yy.push(gy * gy);
+			xy.push(gx * gy);
+			invalid.push(if masked { 1.0 } else { 0.0 });
+		}
+	}
+	
+	let mut response = vec![0.0f32; (width * height) as usize];
+	for y in margin..height - margin {
+		for x in margin..width - margin {
+			if invalid.sum(x - margin, y - margin, x + margin + 1, y + margin + 1) > 0.0 {
+				continue;
+			}
+			
+			let window = |integral: &Integral| integral.sum(x - CORNER_RADIUS, y - CORNER_RADIUS, x + CORNER_RADIUS + 1, y + CORNER_RADIUS + 1);
+			let (a, b, c) = (window(&xx), window(&xy), window(&yy));
+			
+			response[(y * width + x) as usize] = (a + c) / 2.0 - (((a - c) / 2.0).powi(2) + b * b).sqrt();
+		}
+	}
+	
+	let strongest = response.iter().cloned().fold(0.0f32, f32::max);
+	let threshold = strongest * 0.01;
+	let cells = (FEATURE_GRID * FEATURE_GRID) as usize;
+	let mut grid = vec![vec![]; cells];
+	
+	for y in margin..height - margin {
+		for x in margin..width - margin {
+			let value = response[(y * width + x) as usize];
+			if value <= threshold {
+				continue;
+			}
+			
+			let is_maximum = (-CORNER_RADIUS..=CORNER_RADIUS).all(|dy| (-CORNER_RADIUS..=CORNER_RADIUS).all(|dx| {
+				(dx == 0 && dy == 0) || response[((y + dy) * width + x + dx) as usize] < value
+			}));
+			
+			if is_maximum {
+				let cell = (y as u32 * FEATURE_GRID / height as u32) * FEATURE_GRID + x as u32 * FEATURE_GRID / width as u32;
+				grid[cell as usize].push(Feature { x: x as u32, y: y as u32, response: value });
+			}
+		}
+	}
+	
+	let per_cell = max_features.div_ceil(cells);
+	let mut features = vec![];
+	
+	for mut cell in grid {
+		cell.sort_by(|a, b| b.response.total_cmp(&a.response));
+		cell.truncate(per_cell);
+		features.extend(cell);
+	}
+	
+	features.sort_by(|a, b| b.response.total_cmp(&a.response));
+	features.truncate(max_features);
+	features
+}
+
+/// Matches features between two images by normalized cross correlation of the patches around them.
+///
+/// Only pairs accepted by `candidate` are compared. Matches have to be unique and mutual, the position in the second
+/// image is refined to subpixel precision.
+pub fn match_features<F>(
+	first: &GrayImage,
+	second: &GrayImage,
+	first_features: &[Feature],
+	second_features: &[Feature],
+	candidate: F,
+) -> Vec<FeatureMatch>
+	where F: Fn(&Feature, &Feature) -> bool {
+	let first_patches: Vec<_> = first_features.iter().map(|f| Patch::new(first, f.x as i32, f.y as i32)).collect();
+	let second_patches: Vec<_> = second_features.iter().map(|f| Patch::new(second, f.x as i32, f.y as i32)).collect();
+	
+	// Best candidate of every second feature, for the mutual check
+	let mut second_best = vec![(f64::MIN, usize::MAX); second_features.len()];
+	let mut first_best = vec![None; first_features.len()];
+	
+	for (i, (feature, patch)) in first_features.iter().zip(&first_patches).enumerate() {
+		let patch = match patch { Some(patch) => patch, None => continue };
+		let mut best = (f64::MIN, usize::MAX);
+		let mut runner_up = f64::MIN;
+		
+		for (j, (other, other_patch)) in second_features.iter().zip(&second_patches).enumerate() {
+			let other_patch = match other_patch { Some(other_patch) => other_patch, None => continue };
+			if !candidate(feature, other) {
+				continue;
+			}
+			
+			let correlation = patch.correlate(other_patch);
+			
+			if correlation > best.0 {
+				runner_up = best.0;
+				best = (correlation, j);
+			} else if correlation > runner_up {
+				runner_up = correlation;
+			}
+			
+			if correlation > second_best[j].0 {
+				second_best[j] = (correlation, i);
+			}
+		}
+		
+		if best.0 >= MIN_CORRELATION && best.0 - runner_up >= UNIQUENESS_MARGIN {
+			first_best[i] = Some(best);
+		}
+	}
+	
+	first_best.iter().enumerate().filter_map(|(i, best)| {
+		let (correlation, j) = (*best)?;
+		if second_best[j].1 != i {
+			return None;
+		}
+		
+		let feature = &first_features[i];
+		let other = &second_features[j];
+		let patch = first_patches[i].as_ref()?;
+		let offset = patch.refine(second, other.x as i32, other.y as i32)?;
+		
+		Some(FeatureMatch {
+			first: [feature.x as f64 + 0.5, feature.y as f64 + 0.5],
+			second: [other.x as f64 + 0.5 + offset[0], other.y as f64 + 0.5 + offset[1]],
+			correlation,
+		})
+	}).collect()
+}
+
+/// Matches features between a rectified left and right image, allowing some vertical disparity.
+pub fn match_stereo(left: &GrayImage, right: &GrayImage, left_features: &[Feature], right_features: &[Feature]) -> Vec<FeatureMatch> {
+	match_features(left, right, left_features, right_features, |l, r| {
+		let disparity = l.x as f64 - r.x as f64;
+		let vertical = r.y as f64 - l.y as f64;
+		
+		(-MAX_VERTICAL_DISPARITY..=MAX_DISPARITY).contains(&disparity) && vertical.abs() <= MAX_VERTICAL_DISPARITY
+	})
+}
+
+/// Summed area table.
+struct Integral {
+	width: i32,
+	data: Vec<f32>,
+	row: f32,
+	cursor: usize,
+}
+
+impl Integral {
+	fn new(width: u32, height: u32) -> Integral {
+		Integral { width: width as i32, data: vec![0.0; ((width + 1) * (height + 1)) as usize], row: 0.0, cursor: 0 }
+	}
+	
+	/// Appends the next pixel in row major order.
+	fn push(&mut self, value: f32) {
+		let stride = (self.width + 1) as usize;
+		let (x, y) = (self.cursor % self.width as usize, self.cursor / self.width as usize);
+		
+		if x == 0 {
+			self.row = 0.0;
+		}
+		
+		self.row += value;
+		self.data[(y + 1) * stride + x + 1] = self.data[y * stride + x + 1] + self.row;
+		self.cursor += 1;
+	}
+	
+	/// Sum over `x0..x1`, `y0..y1`.
+	fn sum(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> f32 {
+		let at = |x: i32, y: i32| self.data[(y * (self.width + 1) + x) as usize];
+		
+		at(x1, y1) + at(x0, y0) - at(x1, y0) - at(x0, y1)
+	}
+}
+
+/// Zero mean, unit length patch.
+struct Patch(Vec<f32>);
+
+impl Patch {
+	fn new(image: &GrayImage, x: i32, y: i32) -> Option<Patch> {
+		if x < PATCH_RADIUS || y < PATCH_RADIUS || x + PATCH_RADIUS >= image.width() as i32 || y + PATCH_RADIUS >= image.height() as i32 {
+			return None;
+		}
+		
+		let mut values = Vec::with_capacity(((2 * PATCH_RADIUS + 1) * (2 * PATCH_RADIUS + 1)) as usize);
+		for dy in -PATCH_RADIUS..=PATCH_RADIUS {
+			for dx in -PATCH_RADIUS..=PATCH_RADIUS {
+				values.push(image.get_pixel((x + dx) as u32, (y + dy) as u32)[0] as f32);
+			}
+		}
+		
+		let mean = values.iter().sum::<f32>() / values.len() as f32;
+		values.iter_mut().for_each(|v| *v -= mean);
+		let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+		
+		if norm < 1e-3 {
+			return None;
+		}
+		
+		values.iter_mut().for_each(|v| *v /= norm);
+		Some(Patch(values))
+	}
+	
+	fn correlate(&self, other: &Patch) -> f64 {
+		self.0.iter().zip(&other.0).map(|(a, b)| a * b).sum::<f32>() as f64
+	}
+	
+	/// Subpixel offset of the best correlation around (`x`, `y`) in `image`, from a parabola fit on each axis.
+	fn refine(&self, image: &GrayImage, x: i32, y: i32) -> Option<[f64; 2]> {
+		let at = |dx: i32, dy: i32| Patch::new(image, x + dx, y + dy).map(|patch| self.correlate(&patch));
+		let center = at(0, 0)?;
+		let peak = |minus: f64, plus: f64| {
+			let curvature = minus - 2.0 * center + plus;
+			
+			if curvature < 0.0 { ((minus - plus) / (2.0 * curvature)).clamp(-0.5, 0.5) } else { 0.0 }
+		};
+		
+		Some([peak(at(-1, 0)?, at(1, 0)?), peak(at(0, -1)?, at(0, 1)?)])
+	}
+}
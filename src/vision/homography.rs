@@ -0,0 +1,90 @@
+use nalgebra::{DMatrix, Matrix3, Vector3};
+
+/// Plane to plane projective transform.
+#[derive(Debug, Clone, Copy)]
+pub struct Homography(pub Matrix3<f64>);
+
+impl Homography {
+	/// Direct linear transform from at least four point pairs, with the points normalized first.
+	pub fn from_points(from: &[[f64; 2]], to: &[[f64; 2]]) -> Option<Homography> {
+		if from.len() < 4 || from.len() != to.len() {
+			return None;
+		}
+		
+		let (from_norm, from_points) = normalize(from);
+		let (to_norm, to_points) = normalize(to);
+		
+		let mut a = DMatrix::zeros(2 * from.len(), 9);
+		for (row, (p, q)) in from_points.iter().zip(&to_points).enumerate() {
+			let (x, y, u, v) = (p[0], p[1], q[0], q[1]);
+			
+			a.row_mut(2 * row).copy_from_slice(&[-x, -y, -1.0, 0.0, 0.0, 0.0, u * x, u * y, u]);
+			a.row_mut(2 * row + 1).copy_from_slice(&[0.0, 0.0, 0.0, -x, -y, -1.0, v * x, v * y, v]);
+		}
+		
+		// Null vector of A is the eigenvector of AᵀA with the smallest eigenvalue
+		let eigen = (a.transpose() * a).symmetric_eigen();
+		let smallest = eigen.eigenvalues.imin();
+		let h = eigen.eigenvectors.column(smallest);
+		let normalized = Matrix3::new(h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7], h[8]);
+		
+		let matrix = to_norm.try_inverse()? * normalized * from_norm;
+		let scale = matrix[(2, 2)];
+		
+		if scale.abs() < 1e-12 || !matrix.iter().all(|v| v.is_finite()) {
+			return None;
+		}
+		
+		Some(Homography(matrix / scale))
+	}
+	
+	pub fn apply(&self, point: [f64; 2]) -> [f64; 2] {
+		let p = self.0 * Vector3::new(point[0], point[1], 1.0);
+		[p.x / p.z, p.y / p.z]
+	}
+}
+
+/// Moves the centroid to the origin and scales the mean distance to √2.
+fn normalize(points: &[[f64; 2]]) -> (Matrix3<f64>, Vec<[f64; 2]>) {
+	let n = points.len() as f64;
+	let cx = points.iter().map(|p| p[0]).sum::<f64>() / n;
+	let cy = points.iter().map(|p| p[1]).sum::<f64>() / n;
+	let distance = points.iter().map(|p| ((p[0] - cx).powi(2) + (p[1] - cy).powi(2)).sqrt()).sum::<f64>() / n;
+	let scale = if distance > 1e-12 { std::f64::consts::SQRT_2 / distance } else { 1.0 };
+	
+	let transform = Matrix3::new(scale, 0.0, -scale * cx,
+	                             0.0, scale, -scale * cy,
+	                             0.0, 0.0, 1.0);
+	
+	(transform, points.iter().map(|p| [(p[0] - cx) * scale, (p[1] - cy) * scale]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn recovers_a_known_homography() {
+		let truth = Homography(Matrix3::new(1.2, 0.1, 30.0,
+		                                    -0.05, 0.9, 12.0,
+		                                    0.0004, -0.0002, 1.0));
+		let from = [[0.0, 0.0], [100.0, 0.0], [100.0, 80.0], [0.0, 80.0], [50.0, 40.0], [20.0, 70.0]];
+		let to = from.iter().map(|&p| truth.apply(p)).collect::<Vec<_>>();
+		
+		let fitted = Homography::from_points(&from, &to).unwrap();
+		for (a, b) in fitted.0.iter().zip(truth.0.iter()) {
+			assert!((a - b).abs() < 1e-6 * b.abs().max(1.0), "{} != {}", fitted.0, truth.0);
+		}
+		
+		let point = fitted.apply([73.0, 21.0]);
+		let expected = truth.apply([73.0, 21.0]);
+		assert!((point[0] - expected[0]).abs() < 1e-6 && (point[1] - expected[1]).abs() < 1e-6);
+	}
+	
+	#[test]
+	fn needs_four_matching_points() {
+		let points = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+		assert!(Homography::from_points(&points, &points).is_none());
+		assert!(Homography::from_points(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]], &points).is_none());
+	}
+}
@@ -0,0 +1,393 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use image::GrayImage;
+
+use super::{BoardError, FloatImage, Homography, MIN_CORNER_CONTRAST};
+
+/// Side of the window the tag threshold is averaged over, in pixels.
+pub const THRESHOLD_WINDOW: u32 = 31;
+/// How much darker than its surroundings a pixel has to be to belong to a tag border.
+pub const THRESHOLD_OFFSET: f32 = 7.0;
+/// Smallest tag side worth decoding, in pixels.
+pub const MIN_TAG_SIDE: f64 = 12.0;
+/// How far from the thresholded outline the tag edges are looked for, and the sampling step, in pixels.
+pub const EDGE_SEARCH: f64 = 3.0;
+pub const EDGE_STEP: f64 = 0.25;
+
+/// ArUco or AprilTag marker codes, index is the marker id.
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+	/// Marker side in bits, without the black border.
+	pub bits: usize,
+	/// Row major, `true` is white.
+	pub codes: Vec<Vec<bool>>,
+}
+
+impl Dictionary {
+	/// Reads one marker per line as `0`/`1` bits, row major, where `1` is white. Blank lines and lines starting with
+	/// `#` are skipped.
+	///
+	/// This is what OpenCV's `Dictionary.getBitsFromByteList` gives for every entry of `bytesList`.
+	pub fn load(path: &Path) -> Result<Dictionary, BoardError> {
+		let text = fs::read_to_string(path)?;
+		let codes = text.lines()
+		                .map(|line| line.trim())
+		                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+		                .map(|line| line.chars().filter(|c| !c.is_whitespace()).map(|c| match c {
+			                '0' => Ok(false),
+			                '1' => Ok(true),
+			                _ => Err(BoardError::Dictionary(format!("unexpected {:?}", c))),
+		                }).collect::<Result<Vec<_>, _>>())
+		                .collect::<Result<Vec<_>, _>>()?;
+		
+		let length = codes.first().map_or(0, Vec::len);
+		let bits = (length as f64).sqrt().round() as usize;
+		
+		if bits == 0 || bits * bits != length || codes.iter().any(|code| code.len() != length) {
+			return Err(BoardError::Dictionary("every marker needs the same square number of bits".into()));
+		}
+		
+		Ok(Dictionary { bits, codes })
+	}
+	
+	pub fn find(&self, code: &[bool]) -> Option<u32> {
+		self.codes.iter().position(|other| other.as_slice() == code).map(|id| id as u32)
+	}
+}
+
+/// Reads the bits of a black bordered marker. `homography` maps the unit square around the marker to pixels with
+/// integer centers and `margin` is the white space between the square edges and the marker.
+pub fn read_marker(image: &FloatImage, homography: &Homography, bits: usize, margin: f64) -> Option<Vec<bool>> {
+	let sample = |u: f64, v: f64| {
+		let [x, y] = homography.apply([u, v]);
+		image.sample(x, y)
+	};
+	
+	// Mean of each marker cell, border included
+	let cells = bits + 2;
+	let cell_size = (1.0 - 2.0 * margin) / cells as f64;
+	let values = (0..cells * cells).map(|index| {
+		let (cx, cy) = ((index % cells) as f64, (index / cells) as f64);
+		let mut sum = 0.0;
+		for (sx, sy) in [(0.35, 0.35), (0.65, 0.35), (0.35, 0.65), (0.65, 0.65), (0.5, 0.5)].iter() {
+			sum += sample(margin + (cx + sx) * cell_size, margin + (cy + sy) * cell_size);
+		}
+		sum / 5.0
+	}).collect::<Vec<_>>();
+	
+	let white = [(0.5, margin / 2.0), (0.5, 1.0 - margin / 2.0), (margin / 2.0, 0.5), (1.0 - margin / 2.0, 0.5)]
+		.iter().map(|&(u, v)| sample(u, v)).sum::<f32>() / 4.0;
+	let border = (0..cells * cells).filter(|&index| {
+		let (x, y) = (index % cells, index / cells);
+		x == 0 || y == 0 || x == cells - 1 || y == cells - 1
+	}).collect::<Vec<_>>();
+	let black = border.iter().map(|&index| values[index]).sum::<f32>() / border.len() as f32;
+	let threshold = (white + black) / 2.0;
+	
+	if white - black < MIN_CORNER_CONTRAST || border.iter().any(|&index| values[index] > threshold) {
+		return None;
+	}
+	
+	Some((0..bits * bits).map(|index| values[(index / bits + 1) * cells + index % bits + 1] > threshold).collect())
+}
+
+/// A decoded tag, corners go clockwise in the image from the top left of the tag, pixel centers at +0.5.
+#[derive(Debug, Clone, Copy)]
+pub struct TagDetection {
+	pub id: u32,
+	pub corners: [[f64; 2]; 4],
+}
+
+/// Finds black bordered square tags with a white space around them and decodes them with `dictionary`.
+pub fn detect_tags(image: &GrayImage, dictionary: &Dictionary) -> Vec<TagDetection> {
+	let float = FloatImage::from_gray(image);
+	let dark = threshold(image);
+	let mut seen = HashSet::new();
+	let mut detections = vec![];
+	
+	for quad in components(&dark, image.width() as usize, image.height() as usize).iter().filter_map(|component| fit_quad(component)).filter_map(|quad| refine_quad(&float, &quad)) {
+		// Tag side in cells, with a one cell white space around it
+		let margin = 1.0 / (dictionary.bits + 4) as f64;
+		let square = [[margin, margin], [1.0 - margin, margin], [1.0 - margin, 1.0 - margin], [margin, 1.0 - margin]];
+		
+		for shift in 0..4 {
+			let corners = [quad[shift], quad[(shift + 1) % 4], quad[(shift + 2) % 4], quad[(shift + 3) % 4]];
+			let pixels = corners.iter().map(|corner| [corner[0] - 0.5, corner[1] - 0.5]).collect::<Vec<_>>();
+			
+			let id = Homography::from_points(&square, &pixels).and_then(|homography| read_marker(&float, &homography, dictionary.bits, margin))
+			                                                   .and_then(|code| dictionary.find(&code));
+			
+			if let Some(id) = id {
+				if seen.insert(id) {
+					detections.push(TagDetection { id, corners });
+				}
+				break;
+			}
+		}
+	}
+	
+	detections
+}
+
+/// Pixels darker than their surroundings, row major.
+fn threshold(image: &GrayImage) -> Vec<bool> {
+	let (width, height) = (image.width() as usize, image.height() as usize);
+	let mut integral = vec![0u64; (width + 1) * (height + 1)];
+	
+	for y in 0..height {
+		let mut row = 0u64;
+		for x in 0..width {
+			row += image.get_pixel(x as u32, y as u32).0[0] as u64;
+			integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+		}
+	}
+	
+	let radius = (THRESHOLD_WINDOW / 2) as usize;
+	(0..width * height).map(|index| {
+		let (x, y) = (index % width, index / width);
+		let (x0, y0, x1, y1) = (x.saturating_sub(radius), y.saturating_sub(radius), (x + radius + 1).min(width), (y + radius + 1).min(height));
+		let sum = integral[y1 * (width + 1) + x1] + integral[y0 * (width + 1) + x0] - integral[y0 * (width + 1) + x1] - integral[y1 * (width + 1) + x0];
+		let mean = sum as f32 / ((x1 - x0) * (y1 - y0)) as f32;
+		
+		(image.get_pixel(x as u32, y as u32).0[0] as f32) < mean - THRESHOLD_OFFSET
+	}).collect()
+}
+
+/// Pixel centers along the edges of the 4-connected dark regions.
+fn components(dark: &[bool], width: usize, height: usize) -> Vec<Vec<[f64; 2]>> {
+	let mut visited = vec![false; dark.len()];
+	let mut components = vec![];
+	
+	for start in 0..dark.len() {
+		if !dark[start] || visited[start] {
+			continue;
+		}
+		
+		let mut pixels = vec![];
+		let mut stack = vec![start];
+		visited[start] = true;
+		
+		while let Some(index) = stack.pop() {
+			let (x, y) = (index % width, index / width);
+			let neighbours = [(x > 0, index.wrapping_sub(1)), (x + 1 < width, index + 1), (y > 0, index.wrapping_sub(width)), (y + 1 < height, index + width)];
+			
+			if neighbours.iter().any(|&(inside, neighbour)| !inside || !dark[neighbour]) {
+				pixels.push([x as f64 + 0.5, y as f64 + 0.5]);
+			}
+			
+			for &(inside, neighbour) in neighbours.iter() {
+				if inside && dark[neighbour] && !visited[neighbour] {
+					visited[neighbour] = true;
+					stack.push(neighbour);
+				}
+			}
+		}
+		
+		if pixels.len() as f64 >= MIN_TAG_SIDE * 3.0 {
+			components.push(pixels);
+		}
+	}
+	
+	components
+}
+
+/// Corners of the quadrilateral around a dark region, clockwise in the image, or `None` if it isn't one. The corners
+/// are on edge pixel centers.
+fn fit_quad(edge: &[[f64; 2]]) -> Option<[[f64; 2]; 4]> {
+	let hull = convex_hull(edge);
+	let centroid = hull.iter().fold([0.0, 0.0], |sum, p| [sum[0] + p[0], sum[1] + p[1]]);
+	let centroid = [centroid[0] / hull.len() as f64, centroid[1] / hull.len() as f64];
+	let distance = |a: &[f64; 2], b: &[f64; 2]| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+	let side = |a: &[f64; 2], b: &[f64; 2], p: &[f64; 2]| ((b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])) / distance(a, b);
+	
+	let first = *hull.iter().max_by(|a, b| distance(a, &centroid).total_cmp(&distance(b, &centroid)))?;
+	let third = *hull.iter().max_by(|a, b| distance(a, &first).total_cmp(&distance(b, &first)))?;
+	if distance(&first, &third) < MIN_TAG_SIDE * std::f64::consts::SQRT_2 {
+		return None;
+	}
+	
+	let second = *hull.iter().max_by(|a, b| side(&first, &third, a).total_cmp(&side(&first, &third, b)))?;
+	let fourth = *hull.iter().min_by(|a, b| side(&first, &third, a).total_cmp(&side(&first, &third, b)))?;
+	let mut quad = [first, second, third, fourth];
+	
+	// Clockwise in the image, which has +Y down
+	if side(&first, &third, &second) > 0.0 {
+		quad.swap(1, 3);
+	}
+	
+	// Every hull point has to be close to one of the sides
+	let tolerance = (distance(&first, &third) * 0.04).max(1.5);
+	let fits = hull.iter().all(|p| (0..4).any(|k| side(&quad[k], &quad[(k + 1) % 4], p).abs() <= tolerance));
+	let area = |p: &[[f64; 2]; 4]| (0..4).map(|k| p[k][0] * p[(k + 1) % 4][1] - p[(k + 1) % 4][0] * p[k][1]).sum::<f64>().abs() / 2.0;
+	if !fits || (0..4).any(|k| distance(&quad[k], &quad[(k + 1) % 4]) < MIN_TAG_SIDE) || area(&quad) < MIN_TAG_SIDE * MIN_TAG_SIDE {
+		return None;
+	}
+	
+	Some(quad)
+}
+
+/// Fits a line to the intensity edge along every side and intersects them. Edges are found to subpixel precision as
+/// the gradient weighted mean position across the side.
+fn refine_quad(image: &FloatImage, quad: &[[f64; 2]; 4]) -> Option<[[f64; 2]; 4]> {
+	let center = [(quad[0][0] + quad[2][0]) / 2.0, (quad[0][1] + quad[2][1]) / 2.0];
+	let sample = |p: [f64; 2]| image.sample(p[0] - 0.5, p[1] - 0.5) as f64;
+	
+	let lines = (0..4).map(|k| {
+		let (a, b) = (quad[k], quad[(k + 1) % 4]);
+		let length = ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt();
+		let direction = [(b[0] - a[0]) / length, (b[1] - a[1]) / length];
+		let mut normal = [-direction[1], direction[0]];
+		if (a[0] - center[0]) * normal[0] + (a[1] - center[1]) * normal[1] < 0.0 {
+			normal = [-normal[0], -normal[1]];
+		}
+		
+		// Stay within the black border, which is an eighth of the side or more
+		let reach = (length / 16.0).clamp(1.0, EDGE_SEARCH);
+		let steps = (reach / EDGE_STEP).round() as i32;
+		
+		let points = (1..length as usize).map(|i| i as f64).filter(|&along| along > length * 0.1 && along < length * 0.9).filter_map(|along| {
+			let base = [a[0] + direction[0] * along, a[1] + direction[1] * along];
+			let at = |offset: f64| sample([base[0] + normal[0] * offset, base[1] + normal[1] * offset]);
+			
+			let (mut sum, mut weight) = (0.0, 0.0);
+			for step in -steps..=steps {
+				let offset = step as f64 * EDGE_STEP;
+				let gradient = (at(offset + EDGE_STEP / 2.0) - at(offset - EDGE_STEP / 2.0)).abs();
+				sum += gradient * offset;
+				weight += gradient;
+			}
+			
+			if weight < MIN_CORNER_CONTRAST as f64 {
+				return None;
+			}
+			
+			let offset = sum / weight;
+			Some([base[0] + normal[0] * offset, base[1] + normal[1] * offset])
+		}).collect::<Vec<_>>();
+		
+		if points.len() < 2 {
+			return (a, direction);
+		}
+		
+		let mean = points.iter().fold([0.0, 0.0], |sum, p| [sum[0] + p[0], sum[1] + p[1]]);
+		let mean = [mean[0] / points.len() as f64, mean[1] / points.len() as f64];
+		let (mut xx, mut xy, mut yy) = (0.0, 0.0, 0.0);
+		for p in &points {
+			let (dx, dy) = (p[0] - mean[0], p[1] - mean[1]);
+			xx += dx * dx;
+			xy += dx * dy;
+			yy += dy * dy;
+		}
+		let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+		
+		(mean, [angle.cos(), angle.sin()])
+	}).collect::<Vec<_>>();
+	
+	let mut corners = [[0.0; 2]; 4];
+	for k in 0..4 {
+		// Corner k is where the side before it meets the side after it
+		let ((p, d), (q, e)) = (lines[(k + 3) % 4], lines[k]);
+		let determinant = d[0] * e[1] - d[1] * e[0];
+		if determinant.abs() < 1e-6 {
+			return None;
+		}
+		
+		let t = ((q[0] - p[0]) * e[1] - (q[1] - p[1]) * e[0]) / determinant;
+		corners[k] = [p[0] + d[0] * t, p[1] + d[1] * t];
+		
+		let moved = ((corners[k][0] - quad[k][0]).powi(2) + (corners[k][1] - quad[k][1]).powi(2)).sqrt();
+		if moved > 3.0 {
+			return None;
+		}
+	}
+	
+	Some(corners)
+}
+
+/// Andrew's monotone chain, counter clockwise in a +Y up frame.
+fn convex_hull(points: &[[f64; 2]]) -> Vec<[f64; 2]> {
+	let mut points = points.to_vec();
+	points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+	
+	let cross = |o: &[f64; 2], a: &[f64; 2], b: &[f64; 2]| (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]);
+	let mut hull: Vec<[f64; 2]> = vec![];
+	
+	for pass in 0..2 {
+		let start = hull.len();
+		let iter: Box<dyn Iterator<Item = &[f64; 2]>> = if pass == 0 { Box::new(points.iter()) } else { Box::new(points.iter().rev()) };
+		
+		for p in iter {
+			while hull.len() >= start + 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0 {
+				hull.pop();
+			}
+			hull.push(*p);
+		}
+		hull.pop();
+	}
+	
+	hull
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const CELL: u32 = 8;
+	
+	fn dictionary() -> Dictionary {
+		let code = |bits: &str| bits.chars().map(|c| c == '1').collect();
+		
+		Dictionary { bits: 4, codes: vec![code("1000110001010011"), code("0110100111000010")] }
+	}
+	
+	/// Draws a tag with its top left at `origin`, turned a quarter clockwise `turns` times, on a white image.
+	fn draw(image: &mut GrayImage, code: &[bool], origin: (u32, u32), turns: u32) {
+		let cells = 6;
+		
+		for y in 0..cells * CELL {
+			for x in 0..cells * CELL {
+				// Tag cell shown at this image cell
+				let (mut u, mut v) = (x / CELL, y / CELL);
+				for _ in 0..turns {
+					(u, v) = (v, cells - 1 - u);
+				}
+				
+				let border = u == 0 || v == 0 || u == cells - 1 || v == cells - 1;
+				let white = !border && code[((v - 1) * 4 + u - 1) as usize];
+				image.put_pixel(origin.0 + x, origin.1 + y, image::Luma([if white { 220 } else { 30 }]));
+			}
+		}
+	}
+	
+	fn assert_corners(actual: &[[f64; 2]; 4], expected: [[f64; 2]; 4]) {
+		for (actual, expected) in actual.iter().zip(expected.iter()) {
+			assert!((actual[0] - expected[0]).abs() < 0.2 && (actual[1] - expected[1]).abs() < 0.2, "{:?} != {:?}", actual, expected);
+		}
+	}
+	
+	#[test]
+	fn decodes_tags_with_their_corners_in_order() {
+		let dictionary = dictionary();
+		let mut image = GrayImage::from_pixel(200, 110, image::Luma([220]));
+		draw(&mut image, &dictionary.codes[0], (24, 30), 0);
+		draw(&mut image, &dictionary.codes[1], (120, 30), 1);
+		
+		let mut tags = detect_tags(&image, &dictionary);
+		tags.sort_by_key(|tag| tag.id);
+		assert_eq!(tags.iter().map(|tag| tag.id).collect::<Vec<_>>(), [0, 1]);
+		
+		// Tag sides are on pixel edges, which are integers with pixel centers at +0.5
+		assert_corners(&tags[0].corners, [[24.0, 30.0], [72.0, 30.0], [72.0, 78.0], [24.0, 78.0]]);
+		// Turned a quarter clockwise, its top left is at the top right in the image
+		assert_corners(&tags[1].corners, [[168.0, 30.0], [168.0, 78.0], [120.0, 78.0], [120.0, 30.0]]);
+	}
+	
+	#[test]
+	fn ignores_codes_not_in_the_dictionary() {
+		let mut image = GrayImage::from_pixel(100, 100, image::Luma([220]));
+		draw(&mut image, &[true; 16], (26, 26), 0);
+		
+		assert!(detect_tags(&image, &dictionary()).is_empty());
+	}
+}
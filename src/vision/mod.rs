@@ -0,0 +1,17 @@
+mod error;
+mod raster;
+mod features;
+mod homography;
+mod corners;
+mod markers;
+mod board;
+mod stereo;
+
+pub use error::*;
+pub use raster::*;
+pub use features::*;
+pub use homography::*;
+pub use corners::*;
+pub use markers::*;
+pub use board::*;
+pub use stereo::*;
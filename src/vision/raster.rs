@@ -0,0 +1,81 @@
+use image::{RgbaImage, Rgba, GenericImageView};
+
+use crate::tracked_camera::{FrameLayout, FrameSize};
+
+/// Splits a camera frame into separate images, one per camera.
+pub fn split_frame(frame: &RgbaImage, layout: FrameLayout) -> Vec<RgbaImage> {
+	let size = FrameSize { width: frame.width(), height: frame.height(), frame_buffer_size: 0 };
+	let (width, height) = size.camera_size(layout);
+	
+	(0..layout.camera_count()).map(|index| {
+		let (x, y) = size.camera_offset(layout, index);
+		frame.view(x, y, width, height).to_image()
+	}).collect()
+}
+
+/// Bilinear sample at pixel coordinates, where pixel centers are at +0.5.
+pub fn sample_bilinear(image: &RgbaImage, x: f64, y: f64) -> Option<Rgba<u8>> {
+	let x = x - 0.5;
+	let y = y - 0.5;
+	
+	if image.width() == 0 || image.height() == 0 {
+		return None;
+	}
+	
+	if x < 0.0 || y < 0.0 || x > (image.width() - 1) as f64 || y > (image.height() - 1) as f64 {
+		return None;
+	}
+	
+	let x0 = x.floor() as u32;
+	let y0 = y.floor() as u32;
+	let x1 = (x0 + 1).min(image.width() - 1);
+	let y1 = (y0 + 1).min(image.height() - 1);
+	let fx = x - x0 as f64;
+	let fy = y - y0 as f64;
+	
+	let mut out = [0u8; 4];
+	for (c, out) in out.iter_mut().enumerate() {
+		let top = image.get_pixel(x0, y0)[c] as f64 * (1.0 - fx) + image.get_pixel(x1, y0)[c] as f64 * fx;
+		let bottom = image.get_pixel(x0, y1)[c] as f64 * (1.0 - fx) + image.get_pixel(x1, y1)[c] as f64 * fx;
+		*out = (top * (1.0 - fy) + bottom * fy).round() as u8;
+	}
+	
+	Some(Rgba(out))
+}
+
+/// Blends a horizontal line across the whole image.
+pub fn draw_horizontal_line(image: &mut RgbaImage, y: u32, color: Rgba<u8>) {
+	if y >= image.height() {
+		return;
+	}
+	
+	let alpha = color[3] as u32;
+	
+	for x in 0..image.width() {
+		let pixel = image.get_pixel_mut(x, y);
+		for c in 0..3 {
+			pixel[c] = ((pixel[c] as u32 * (255 - alpha) + color[c] as u32 * alpha) / 255) as u8;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn empty_images_have_no_samples() {
+		assert_eq!(sample_bilinear(&RgbaImage::new(0, 0), 0.5, 0.5), None);
+		assert_eq!(sample_bilinear(&RgbaImage::new(4, 0), 0.5, 0.5), None);
+	}
+	
+	#[test]
+	fn samples_between_pixel_centers() {
+		let image = RgbaImage::from_fn(2, 1, |x, _| Rgba([x as u8 * 100, 0, 0, 255]));
+		
+		assert_eq!(sample_bilinear(&image, 0.5, 0.5), Some(Rgba([0, 0, 0, 255])));
+		assert_eq!(sample_bilinear(&image, 1.0, 0.5), Some(Rgba([50, 0, 0, 255])));
+		assert_eq!(sample_bilinear(&image, 1.5, 0.5), Some(Rgba([100, 0, 0, 255])));
+		assert_eq!(sample_bilinear(&image, 1.6, 0.5), None);
+	}
+}
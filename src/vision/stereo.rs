@@ -0,0 +1,277 @@
+use std::collections::VecDeque;
+use image::{GrayImage, Luma};
+
+/// Disparities searched by the semi-global matcher, in rectified pixels.
+pub const SGM_DISPARITIES: usize = 64;
+/// Half size of the census window, 7x7 gives 48 bits.
+pub const CENSUS_RADIUS: i32 = 3;
+/// Penalties for a one pixel disparity change and a larger jump between neighbouring pixels, in census bits.
+pub const SGM_SMALL_PENALTY: u16 = 6;
+pub const SGM_LARGE_PENALTY: u16 = 32;
+/// The best disparity has to cost this much less than any other one that isn't next to it.
+pub const SGM_UNIQUENESS: f32 = 0.1;
+/// Largest difference between the left and the right disparity of a pixel.
+pub const SGM_LEFT_RIGHT_TOLERANCE: f32 = 1.0;
+/// Regions of similar disparity smaller than this are dropped as speckles, in pixels.
+pub const SPECKLE_SIZE: usize = 100;
+pub const SPECKLE_RANGE: f32 = 1.0;
+
+const CENSUS_BITS: u8 = ((CENSUS_RADIUS * 2 + 1) * (CENSUS_RADIUS * 2 + 1) - 1) as u8;
+
+/// Disparity `x_left - x_right` of every left pixel, row major.
+#[derive(Debug, Clone)]
+pub struct DisparityMap {
+	pub width: u32,
+	pub height: u32,
+	pub values: Vec<Option<f32>>,
+}
+
+impl DisparityMap {
+	pub fn get(&self, x: u32, y: u32) -> Option<f32> {
+		self.values[(y * self.width + x) as usize]
+	}
+	
+	pub fn valid_count(&self) -> usize {
+		self.values.iter().filter(|value| value.is_some()).count()
+	}
+	
+	/// Disparity scaled to the whole search range, black where it's unknown.
+	pub fn to_image(&self) -> GrayImage {
+		GrayImage::from_fn(self.width, self.height, |x, y| {
+			Luma([self.get(x, y).map_or(0, |disparity| (disparity / SGM_DISPARITIES as f32 * 254.0 + 1.0).min(255.0) as u8)])
+		})
+	}
+}
+
+/// Semi-global matching of a rectified pair, on census costs aggregated along 8 directions.
+///
+/// Masks are row major and mark the pixels that have image data, like `Rectification::valid_mask`.
+pub fn semi_global_matching(left: &GrayImage, right: &GrayImage, left_mask: Option<&[bool]>, right_mask: Option<&[bool]>) -> DisparityMap {
+	let (width, height) = (left.width() as usize, left.height() as usize);
+	let valid = |mask: Option<&[bool]>, index: usize| mask.is_none_or(|mask| mask[index]);
+	let left_census = census(left);
+	let right_census = census(right);
+	
+	let mut costs = vec![CENSUS_BITS; width * height * SGM_DISPARITIES];
+	for y in 0..height {
+		for x in 0..width {
+			let index = y * width + x;
+			if !valid(left_mask, index) {
+				continue;
+			}
+			
+			for d in 0..SGM_DISPARITIES.min(x + 1) {
+				if valid(right_mask, index - d) {
+					costs[index * SGM_DISPARITIES + d] = (left_census[index] ^ right_census[index - d]).count_ones() as u8;
+				}
+			}
+		}
+	}
+	
+	let mut sums = vec![0u16; costs.len()];
+	for &direction in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, 1), (1, -1), (-1, -1)].iter() {
+		aggregate(&costs, &mut sums, width, height, direction);
+	}
+	
+	// Best disparity of every right pixel, from the same sums, for the left-right check
+	let right_disparities = (0..width * height).map(|index| {
+		let x = index % width;
+		(0..SGM_DISPARITIES.min(width - x)).min_by_key(|&d| sums[(index + d) * SGM_DISPARITIES + d])
+	}).collect::<Vec<_>>();
+	
+	let mut values = (0..width * height).map(|index| {
+		if !valid(left_mask, index) {
+			return None;
+		}
+		
+		let x = index % width;
+		let sums = &sums[index * SGM_DISPARITIES..(index + 1) * SGM_DISPARITIES][..SGM_DISPARITIES.min(x + 1)];
+		let best = (0..sums.len()).min_by_key(|&d| sums[d])?;
+		
+		let unique = sums.iter().enumerate().filter(|&(d, _)| (d as i32 - best as i32).abs() > 1)
+		                 .all(|(_, &sum)| sum as f32 * (1.0 - SGM_UNIQUENESS) >= sums[best] as f32);
+		if !unique {
+			return None;
+		}
+		
+		// Parabola through the neighbouring sums
+		let disparity = if best > 0 && best + 1 < sums.len() {
+			let (before, at, after) = (sums[best - 1] as f32, sums[best] as f32, sums[best + 1] as f32);
+			let curvature = before + after - 2.0 * at;
+			best as f32 + if curvature > 0.0 { (before - after) / (2.0 * curvature) } else { 0.0 }
+		} else {
+			best as f32
+		};
+		
+		let right = right_disparities[index - (disparity.round() as usize).min(x)]?;
+		if (right as f32 - disparity).abs() > SGM_LEFT_RIGHT_TOLERANCE {
+			return None;
+		}
+		
+		Some(disparity)
+	}).collect::<Vec<_>>();
+	
+	remove_speckles(&mut values, width, height);
+	
+	DisparityMap { width: width as u32, height: height as u32, values }
+}
+
+/// Which pixels of the window around each pixel are darker than it, edges are clamped.
+fn census(image: &GrayImage) -> Vec<u64> {
+	let (width, height) = (image.width() as i32, image.height() as i32);
+	let at = |x: i32, y: i32| image.get_pixel(x.max(0).min(width - 1) as u32, y.max(0).min(height - 1) as u32)[0];
+	
+	(0..width * height).map(|index| {
+		let (x, y) = (index % width, index / width);
+		let center = at(x, y);
+		let mut bits = 0u64;
+		
+		for dy in -CENSUS_RADIUS..=CENSUS_RADIUS {
+			for dx in -CENSUS_RADIUS..=CENSUS_RADIUS {
+				if dx != 0 || dy != 0 {
+					bits = bits << 1 | (at(x + dx, y + dy) < center) as u64;
+				}
+			}
+		}
+		
+		bits
+	}).collect()
+}
+
+/// Adds the costs aggregated along one direction to `sums`, the previous pixel on the path is at `-direction`.
+fn aggregate(costs: &[u8], sums: &mut [u16], width: usize, height: usize, direction: (i32, i32)) {
+	let (dx, dy) = direction;
+	let mut previous = vec![0u16; width * SGM_DISPARITIES];
+	let mut current = vec![0u16; width * SGM_DISPARITIES];
+	let mut previous_min = vec![0u16; width];
+	let mut current_min = vec![0u16; width];
+	let mut path = [0u16; SGM_DISPARITIES];
+	
+	let rows = if dy >= 0 { (0..height).collect::<Vec<_>>() } else { (0..height).rev().collect() };
+	let columns = if dx >= 0 { (0..width).collect::<Vec<_>>() } else { (0..width).rev().collect() };
+	
+	for (row, &y) in rows.iter().enumerate() {
+		for &x in &columns {
+			let before = x as i32 - dx;
+			let has_before = before >= 0 && (before as usize) < width && (dy == 0 || row > 0);
+			let before_min = if !has_before {
+				None
+			} else if dy == 0 {
+				path.copy_from_slice(&current[before as usize * SGM_DISPARITIES..][..SGM_DISPARITIES]);
+				Some(current_min[before as usize])
+			} else {
+				path.copy_from_slice(&previous[before as usize * SGM_DISPARITIES..][..SGM_DISPARITIES]);
+				Some(previous_min[before as usize])
+			};
+			
+			let index = (y * width + x) * SGM_DISPARITIES;
+			let mut min = u16::MAX;
+			
+			for d in 0..SGM_DISPARITIES {
+				let cost = costs[index + d] as u16;
+				let value = match before_min {
+					None => cost,
+					Some(before_min) => {
+						let mut best = path[d].min(before_min + SGM_LARGE_PENALTY);
+						if d > 0 {
+							best = best.min(path[d - 1] + SGM_SMALL_PENALTY);
+						}
+						if d + 1 < SGM_DISPARITIES {
+							best = best.min(path[d + 1] + SGM_SMALL_PENALTY);
+						}
+						cost + best - before_min
+					},
+				};
+				
+				current[x * SGM_DISPARITIES + d] = value;
+				sums[index + d] += value;
+				min = min.min(value);
+			}
+			
+			current_min[x] = min;
+		}
+		
+		std::mem::swap(&mut previous, &mut current);
+		std::mem::swap(&mut previous_min, &mut current_min);
+	}
+}
+
+/// Drops small 4-connected regions whose neighbouring disparities are within `SPECKLE_RANGE` of each other.
+fn remove_speckles(values: &mut [Option<f32>], width: usize, height: usize) {
+	let mut visited = vec![false; values.len()];
+	
+	for start in 0..values.len() {
+		if visited[start] || values[start].is_none() {
+			continue;
+		}
+		
+		let mut region = vec![start];
+		let mut queue = VecDeque::from(vec![start]);
+		visited[start] = true;
+		
+		while let Some(index) = queue.pop_front() {
+			let (x, y) = (index % width, index / width);
+			let value = values[index].unwrap();
+			let neighbours = [(x > 0, index.wrapping_sub(1)), (x + 1 < width, index + 1), (y > 0, index.wrapping_sub(width)), (y + 1 < height, index + width)];
+			
+			for &(inside, neighbour) in neighbours.iter() {
+				if inside && !visited[neighbour] && values[neighbour].is_some_and(|other| (other - value).abs() <= SPECKLE_RANGE) {
+					visited[neighbour] = true;
+					region.push(neighbour);
+					queue.push_back(neighbour);
+				}
+			}
+		}
+		
+		if region.len() < SPECKLE_SIZE {
+			for index in region {
+				values[index] = None;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const SHIFT: u32 = 12;
+	
+	/// Random texture, and the same texture moved `SHIFT` pixels left as the right image.
+	fn pair() -> (GrayImage, GrayImage) {
+		let (width, height) = (120, 60);
+		let mut seed = 12345u32;
+		let texture = (0..(width + SHIFT) * height).map(|_| {
+			seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+			(seed >> 24) as u8
+		}).collect::<Vec<_>>();
+		let at = |x: u32, y: u32| Luma([texture[(y * (width + SHIFT) + x) as usize]]);
+		
+		(GrayImage::from_fn(width, height, at), GrayImage::from_fn(width, height, |x, y| at(x + SHIFT, y)))
+	}
+	
+	#[test]
+	fn finds_the_shift_of_a_texture() {
+		let (left, right) = pair();
+		let disparity = semi_global_matching(&left, &right, None, None);
+		
+		// Left pixels closer to the edge than the shift have no match
+		let matchable = (0..disparity.height).flat_map(|y| (SHIFT..disparity.width).map(move |x| (x, y))).collect::<Vec<_>>();
+		let found = matchable.iter().filter_map(|&(x, y)| disparity.get(x, y)).collect::<Vec<_>>();
+		
+		assert!(found.len() > matchable.len() * 9 / 10, "{} of {} pixels matched", found.len(), matchable.len());
+		for value in found {
+			assert!((value - SHIFT as f32).abs() < 0.5, "disparity {}", value);
+		}
+	}
+	
+	#[test]
+	fn masked_pixels_have_no_disparity() {
+		let (left, right) = pair();
+		let mask = (0..left.width() * left.height()).map(|index| index % left.width() < 60).collect::<Vec<_>>();
+		let disparity = semi_global_matching(&left, &right, Some(&mask), None);
+		
+		assert!((0..disparity.height).all(|y| (60..disparity.width).all(|x| disparity.get(x, y).is_none())));
+		assert!(disparity.valid_count() > 0);
+	}
+}